@@ -7,10 +7,10 @@ use rand::rngs::StdRng;
 use rand::Rng;
 use rand::SeedableRng;
 use structopt::StructOpt;
+use xorfilter::fuse16::Fuse16Builder;
+use xorfilter::fuse8::Fuse8Builder;
+use xorfilter::xor8::Xor8Builder;
 use xorfilter::BuildHasherDefault;
-use xorfilter::Fuse16;
-use xorfilter::Fuse8;
-use xorfilter::Xor8;
 
 /// Command line options.
 #[derive(Clone, StructOpt)]
@@ -47,12 +47,13 @@ fn main() {
 fn run_xor8(opts: Opt) {
     let keys: Vec<u64> = (0..(opts.loads as u64)).collect();
 
-    let mut filter = Xor8::<BuildHasherDefault>::new();
-    filter.populate(&keys);
+    let mut builder = Xor8Builder::<BuildHasherDefault>::new();
+    builder.populate(&keys);
 
     let start = time::Instant::now();
-    filter.build().unwrap();
+    let (filter, stats) = builder.build_with_stats().unwrap();
     println!("Took {:?} to build {} keys", start.elapsed(), keys.len());
+    println!("{}", stats);
 
     let mut handles = vec![];
     let keys = Arc::new(keys);
@@ -86,12 +87,13 @@ fn run_xor8(opts: Opt) {
 fn run_fuse8(opts: Opt) {
     let keys: Vec<u64> = (0..(opts.loads as u64)).collect();
 
-    let mut filter = Fuse8::<BuildHasherDefault>::new(keys.len() as u32);
-    filter.populate(&keys);
+    let mut builder = Fuse8Builder::<BuildHasherDefault>::new();
+    builder.populate(&keys);
 
     let start = time::Instant::now();
-    filter.build().unwrap();
+    let (filter, stats) = builder.build_with_stats().unwrap();
     println!("Took {:?} to build {} keys", start.elapsed(), keys.len());
+    println!("{}", stats);
 
     let mut handles = vec![];
     let keys = Arc::new(keys);
@@ -125,12 +127,13 @@ fn run_fuse8(opts: Opt) {
 fn run_fuse16(opts: Opt) {
     let keys: Vec<u64> = (0..(opts.loads as u64)).collect();
 
-    let mut filter = Fuse16::<BuildHasherDefault>::new(keys.len() as u32);
-    filter.populate(&keys);
+    let mut builder = Fuse16Builder::<BuildHasherDefault>::new();
+    builder.populate(&keys);
 
     let start = time::Instant::now();
-    filter.build().unwrap();
+    let (filter, stats) = builder.build_with_stats().unwrap();
     println!("Took {:?} to build {} keys", start.elapsed(), keys.len());
+    println!("{}", stats);
 
     let mut handles = vec![];
     let keys = Arc::new(keys);
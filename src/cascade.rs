@@ -0,0 +1,440 @@
+//! Exact set membership against a known universe, using a cascade of filters.
+//!
+//! Refer to [FilterCascade] for details, which is constructed using
+//! [FilterCascadeBuilder].
+
+use std::borrow::Borrow;
+#[allow(unused_imports)]
+use std::collections::hash_map::DefaultHasher;
+#[allow(unused_imports)]
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::time::Duration;
+use std::time::Instant;
+
+#[cfg(feature = "cbordata")]
+use cbordata::Cbor;
+#[cfg(feature = "cbordata")]
+use cbordata::Cborize;
+#[cfg(feature = "cbordata")]
+use cbordata::FromCbor;
+#[cfg(feature = "cbordata")]
+use cbordata::IntoCbor;
+#[cfg(feature = "cbordata")]
+use cbordata::{self as cbor};
+
+use crate::fuse::binary_fuse_mix_split;
+use crate::fuse::FUSE_RNG_SEED;
+use crate::fuse8::Fuse8;
+use crate::fuse8::Fuse8Builder;
+use crate::hasher::random_seed;
+use crate::BuildHasherDefault;
+use crate::BuildStats;
+use crate::Error;
+use crate::Filter;
+use crate::NoHash;
+use crate::Result;
+
+// levels shrink by a factor of about 256 every other level, so disjoint sets never
+// need these many levels, while a digest both included and excluded is a false
+// positive at every level.
+const MAX_LEVELS: usize = 64;
+
+/// Builds a [FilterCascade] from a set of included keys and a set of excluded keys.
+///
+/// Example:
+/// ```
+/// # use xorfilter::FilterCascadeBuilder;
+///
+/// let mut b: FilterCascadeBuilder = FilterCascadeBuilder::new();
+///
+/// b.include(&["foo", "bar"]);
+/// b.exclude(&["baz", "qux"]);
+/// let cascade = b.build().unwrap();
+///
+/// assert!(cascade.contains("foo"));
+/// assert!(!cascade.contains("baz"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct FilterCascadeBuilder<H = BuildHasherDefault>
+where H: BuildHasher + Clone
+{
+    included: Vec<u64>,
+    excluded: Vec<u64>,
+    hash_time: Duration,
+    pub hash_builder: H,
+    /// Seed for the random number generator drawing the peeling seeds.
+    pub rng_seed: u64,
+}
+
+impl<H> Default for FilterCascadeBuilder<H>
+where H: BuildHasher + Clone + Default
+{
+    fn default() -> Self {
+        Self::with_hasher(H::default())
+    }
+}
+
+impl<H> FilterCascadeBuilder<H>
+where H: BuildHasher + Clone
+{
+    /// New cascade builder initialized with [BuildHasherDefault].
+    pub fn new() -> Self
+    where H: Default {
+        Self::default()
+    }
+
+    /// New cascade builder initialized with supplied `hasher`.
+    pub fn with_hasher(hash_builder: H) -> Self {
+        Self {
+            included: Vec::default(),
+            excluded: Vec::default(),
+            hash_time: Duration::default(),
+            hash_builder,
+            rng_seed: FUSE_RNG_SEED,
+        }
+    }
+
+    /// Seed the builds with a random `rng_seed`, and return the same.
+    pub fn set_random_seed(&mut self) -> u64 {
+        self.rng_seed = random_seed();
+        self.rng_seed
+    }
+
+    pub fn get_hasher(&self) -> H::Hasher {
+        self.hash_builder.build_hasher()
+    }
+
+    /// Calculate hash of a key.
+    #[inline]
+    pub fn hash<K: Hash + ?Sized>(&self, key: &K) -> u64 {
+        let mut hasher = self.get_hasher();
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Include 64-bit digests for a collection of keys, owned or borrowed,
+    /// cascade shall report them as members.
+    ///
+    /// Digest for key shall be generated using the default-hasher or via hasher supplied
+    /// via [FilterCascadeBuilder::with_hasher] method.
+    pub fn include<I>(&mut self, keys: I)
+    where
+        I: IntoIterator,
+        I::Item: Hash,
+    {
+        let start = Instant::now();
+        for key in keys.into_iter() {
+            let digest = self.hash(&key);
+            self.included.push(digest);
+        }
+        self.hash_time += start.elapsed();
+    }
+
+    /// Exclude 64-bit digests for a collection of keys, owned or borrowed,
+    /// cascade shall report them as non-members.
+    ///
+    /// Digest for key shall be generated using the default-hasher or via hasher supplied
+    /// via [FilterCascadeBuilder::with_hasher] method.
+    pub fn exclude<I>(&mut self, keys: I)
+    where
+        I: IntoIterator,
+        I::Item: Hash,
+    {
+        let start = Instant::now();
+        for key in keys.into_iter() {
+            let digest = self.hash(&key);
+            self.excluded.push(digest);
+        }
+        self.hash_time += start.elapsed();
+    }
+
+    /// Include pre-compute collection of 64-bit digests.
+    pub fn include_digests<I>(&mut self, digests: I)
+    where
+        I: IntoIterator,
+        I::Item: Borrow<u64>,
+    {
+        self.included.extend(digests.into_iter().map(|d| *d.borrow()))
+    }
+
+    /// Exclude pre-compute collection of 64-bit digests.
+    pub fn exclude_digests<I>(&mut self, digests: I)
+    where
+        I: IntoIterator,
+        I::Item: Borrow<u64>,
+    {
+        self.excluded.extend(digests.into_iter().map(|d| *d.borrow()))
+    }
+
+    /// Build cascade for keys that where previously included and excluded. Duplicate
+    /// digests are removed before building the cascade.
+    ///
+    /// Fails if a digest is both included and excluded.
+    pub fn build(&mut self) -> Result<FilterCascade<H>> {
+        self.build_with_stats().map(|(cascade, _)| cascade)
+    }
+
+    /// Same as [FilterCascadeBuilder::build], and return statistics on the build,
+    /// merged across levels. Duplicate digests removed before the build are
+    /// accounted as well.
+    pub fn build_with_stats(&mut self) -> Result<(FilterCascade<H>, BuildStats)> {
+        let (num_digests, start) =
+            (self.included.len() + self.excluded.len(), Instant::now());
+        self.included.sort_unstable();
+        self.included.dedup();
+        self.excluded.sort_unstable();
+        self.excluded.dedup();
+
+        let (mut i, mut e) = (0, 0);
+        while i < self.included.len() && e < self.excluded.len() {
+            let (a, b) = (self.included[i], self.excluded[e]);
+            if a == b {
+                err_at!(Fatal, msg: "digest {} is both included and excluded", a)?;
+            }
+            i += usize::from(a < b);
+            e += usize::from(b < a);
+        }
+
+        let dedup_time = start.elapsed();
+
+        let (included, excluded) = (self.included.clone(), self.excluded.clone());
+        let (cascade, mut stats) =
+            self.build_from_digests_with_stats(&included, &excluded)?;
+        let num_duplicates = num_digests - included.len() - excluded.len();
+        stats.num_digests += num_duplicates;
+        stats.num_duplicates += num_duplicates;
+        stats.hash_time = self.hash_time;
+        stats.dedup_time += dedup_time;
+        Ok((cascade, stats))
+    }
+
+    /// Build cascade for pre-computed 64-bit digests of `included` and `excluded` keys.
+    ///
+    /// If keys where previously included or excluded, they shall be ignored.
+    ///
+    /// It is upto the caller to ensure that digests are unique, that there no duplicates,
+    /// and that `included` and `excluded` are disjoint. Digests common to both sets
+    /// fail the build once the cascade grows beyond 64 levels.
+    pub fn build_from_digests(
+        &mut self,
+        included: &[u64],
+        excluded: &[u64],
+    ) -> Result<FilterCascade<H>> {
+        self.build_from_digests_with_stats(included, excluded).map(|(cascade, _)| cascade)
+    }
+
+    /// Same as [FilterCascadeBuilder::build_from_digests], and return statistics on
+    /// the build, merged across levels.
+    pub fn build_from_digests_with_stats(
+        &mut self,
+        included: &[u64],
+        excluded: &[u64],
+    ) -> Result<(FilterCascade<H>, BuildStats)> {
+        let mut levels: Vec<Fuse8<NoHash>> = vec![];
+        let mut stats = vec![];
+        let mut builder = Fuse8Builder::with_hasher(NoHash);
+        builder.rng_seed = self.rng_seed;
+
+        // each level encodes the keys that passed through all the previous levels,
+        // alternating between included and excluded keys.
+        let (mut keys, mut others) = (included.to_vec(), excluded.to_vec());
+        loop {
+            let level = levels.len();
+            let digests: Vec<u64> =
+                keys.iter().map(|d| level_digest(*d, level)).collect();
+
+            let (filter, level_stats) =
+                builder.build_from_digests_with_stats(&digests)?;
+            stats.push(level_stats);
+
+            let false_positives: Vec<u64> = others
+                .into_iter()
+                .filter(|d| filter.contains_digest(level_digest(*d, level)))
+                .collect();
+            levels.push(filter);
+
+            if false_positives.is_empty() {
+                break;
+            } else if levels.len() == MAX_LEVELS {
+                err_at!(
+                    Fatal,
+                    msg: "cascade beyond {} levels, included and excluded overlap",
+                    MAX_LEVELS
+                )?;
+            }
+            others = keys;
+            keys = false_positives;
+        }
+
+        let cascade = FilterCascade {
+            hash_builder: self.hash_builder.clone(),
+            num_keys: Some(included.len()),
+            levels,
+        };
+
+        Ok((cascade, BuildStats::merge(&stats)))
+    }
+}
+
+/// Type FilterCascade answers membership queries without false positives, for every
+/// key in a known universe of included and excluded keys.
+///
+/// First level is a [Fuse8] filter over included keys. Every following level is a
+/// [Fuse8] filter over the false positives of the previous level, taken from the
+/// opposite set, until there are no more false positives. Second level holds about
+/// `1/256` of the excluded keys, and every level after that holds about `1/256` of
+/// the keys in the level before the previous one, so only a few levels are needed.
+///
+/// Keys outside the universe are reported as members with the false positive rate
+/// of [Fuse8].
+///
+/// If `RandomState` is used as BuildHasher, `std` has got this to say
+/// > _A particular instance RandomState will create the same instances
+/// > of Hasher, but the hashers created by two different RandomState
+/// > instances are unlikely to produce the same result for the same values._
+///
+/// If [DefaultHasher] is used as BuildHasher, `std` has got this to say,
+/// > _The internal algorithm is not specified, and so its hashes
+/// > should not be relied upon over releases._
+#[derive(Clone)]
+pub struct FilterCascade<H = BuildHasherDefault>
+where H: BuildHasher
+{
+    pub hash_builder: H,
+    pub num_keys: Option<usize>,
+    pub levels: Vec<Fuse8<NoHash>>,
+}
+
+impl<H> FilterCascade<H>
+where H: BuildHasher
+{
+    #[allow(clippy::len_without_is_empty)]
+    /// Return the number of included keys built into the cascade.
+    pub fn len(&self) -> Option<usize> {
+        self.num_keys
+    }
+
+    /// Return the size of index.
+    #[inline]
+    pub fn size_of(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.levels.iter().map(|level| level.size_of()).sum::<usize>()
+    }
+
+    /// Contains tell you whether the key is part of the included set. Exact for
+    /// keys in the universe, with false positive rate for other keys.
+    pub fn contains<K: ?Sized + Hash>(&self, key: &K) -> bool {
+        let digest = self.hash(key);
+        self.contains_digest(digest)
+    }
+
+    /// Contains tell you whether the key, as pre-computed digest form, is part of
+    /// the included set. Exact for keys in the universe, with false positive rate for
+    /// other keys.
+    pub fn contains_digest(&self, digest: u64) -> bool {
+        for (level, filter) in self.levels.iter().enumerate() {
+            if !filter.contains_digest(level_digest(digest, level)) {
+                return level % 2 == 1;
+            }
+        }
+        self.levels.len() % 2 == 1
+    }
+
+    pub fn get_hasher(&self) -> H::Hasher {
+        self.hash_builder.build_hasher()
+    }
+
+    /// Calculate hash of a key.
+    #[inline]
+    pub fn hash<K: Hash + ?Sized>(&self, key: &K) -> u64 {
+        let mut hasher = self.get_hasher();
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+impl<H> Filter for FilterCascade<H>
+where H: BuildHasher
+{
+    fn contains_digest(&self, digest: u64) -> bool {
+        self.contains_digest(digest)
+    }
+
+    fn len(&self) -> Option<usize> {
+        self.num_keys
+    }
+
+    fn size_of(&self) -> usize {
+        self.size_of()
+    }
+}
+
+// salt digests with level, so that false positives are independent across levels.
+#[inline]
+fn level_digest(digest: u64, level: usize) -> u64 {
+    binary_fuse_mix_split(digest, level as u64)
+}
+
+//------ Implement cbordata related functionalities
+
+// Intermediate type to serialize and de-serialized FilterCascade into bytes.
+#[cfg(feature = "cbordata")]
+#[derive(Cborize)]
+struct CborFilterCascade {
+    hash_builder: Vec<u8>,
+    num_keys: Option<usize>,
+    levels: Vec<Cbor>,
+}
+
+#[cfg(feature = "cbordata")]
+impl CborFilterCascade {
+    const ID: &'static str = "filtercascade/0.0.1";
+}
+
+#[cfg(feature = "cbordata")]
+impl<H> IntoCbor for FilterCascade<H>
+where H: BuildHasher + Into<Vec<u8>>
+{
+    fn into_cbor(self) -> cbor::Result<Cbor> {
+        let mut levels = vec![];
+        for level in self.levels.into_iter() {
+            levels.push(level.into_cbor()?);
+        }
+
+        let val = CborFilterCascade {
+            hash_builder: self.hash_builder.into(),
+            num_keys: self.num_keys,
+            levels,
+        };
+        val.into_cbor()
+    }
+}
+
+#[cfg(feature = "cbordata")]
+impl<H> FromCbor for FilterCascade<H>
+where H: BuildHasher + From<Vec<u8>>
+{
+    fn from_cbor(val: Cbor) -> cbor::Result<Self> {
+        let val = CborFilterCascade::from_cbor(val)?;
+
+        let mut levels = vec![];
+        for level in val.levels.into_iter() {
+            levels.push(Fuse8::<NoHash>::from_cbor(level)?);
+        }
+
+        let cascade = FilterCascade {
+            hash_builder: val.hash_builder.into(),
+            num_keys: val.num_keys,
+            levels,
+        };
+
+        Ok(cascade)
+    }
+}
+
+#[cfg(test)]
+#[path = "cascade_test.rs"]
+mod cascade_test;
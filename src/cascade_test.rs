@@ -0,0 +1,188 @@
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+
+#[cfg(feature = "cbordata")]
+use cbordata::FromCbor;
+#[cfg(feature = "cbordata")]
+use cbordata::IntoCbor;
+use rand::prelude::random;
+use rand::rngs::StdRng;
+use rand::Rng;
+use rand::SeedableRng;
+
+use super::*;
+
+fn test_cascade_build<H>(name: &str, seed: u64, included: usize, excluded: usize)
+where H: BuildHasher + Clone + Default {
+    println!(
+        "test_cascade_build<{}> included:{} excluded:{}",
+        name, included, excluded
+    );
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let keys: Vec<u64> = (0..(included + excluded)).map(|_| rng.gen::<u64>()).collect();
+    let (ikeys, ekeys) = keys.split_at(included);
+
+    let mut builder = FilterCascadeBuilder::<H>::new();
+    // include/exclude api
+    builder.include(ikeys);
+    builder.exclude(ekeys);
+    // include_digests/exclude_digests api, duplicates are removed before build.
+    let digests: Vec<u64> = ekeys.iter().take(10).map(|k| builder.hash(k)).collect();
+    builder.exclude_digests(digests.iter());
+
+    let cascade = builder.build().expect("failed build");
+    assert_eq!(cascade.len(), Some(included));
+    println!(
+        "test_cascade_build<{}> levels:{} bits per entry {}",
+        name,
+        cascade.levels.len(),
+        ((cascade.size_of() * 8) as f64) / (included as f64),
+    );
+
+    for key in ikeys.iter() {
+        assert!(cascade.contains(key), "included key {}", key);
+    }
+    for key in ekeys.iter() {
+        assert!(!cascade.contains(key), "excluded key {}", key);
+    }
+    for digest in digests.iter() {
+        assert!(
+            !cascade.contains_digest(*digest),
+            "excluded digest {}",
+            digest
+        );
+    }
+}
+
+#[test]
+fn test_cascade() {
+    let mut seed: u64 = random();
+    println!("test_cascade seed:{}", seed);
+
+    let sizes = [
+        (0, 0),
+        (1, 0),
+        (0, 1),
+        (10, 1000),
+        (10_000, 1_000_000),
+        (100_000, 100),
+    ];
+    for (included, excluded) in sizes.iter() {
+        seed = seed.wrapping_add(*included as u64);
+        test_cascade_build::<RandomState>("RandomState", seed, *included, *excluded);
+        test_cascade_build::<BuildHasherDefault>(
+            "BuildHasherDefault",
+            seed,
+            *included,
+            *excluded,
+        );
+    }
+}
+
+#[test]
+fn test_cascade_overlap() {
+    let mut builder = FilterCascadeBuilder::<BuildHasherDefault>::new();
+    builder.include(&["foo", "bar"]);
+    builder.exclude(&["baz", "bar"]);
+    assert!(builder.build().is_err());
+
+    let (included, excluded) = (vec![10, 20, 30], vec![40, 20]);
+    assert!(builder.build_from_digests(&included, &excluded).is_err());
+}
+
+#[test]
+fn test_cascade_build_stats() {
+    let seed: u64 = random();
+    println!("test_cascade_build_stats seed:{}", seed);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let keys: Vec<u64> = (0..110_000).map(|_| rng.gen::<u64>()).collect();
+    let (ikeys, ekeys) = keys.split_at(10_000);
+
+    let mut builder = FilterCascadeBuilder::<BuildHasherDefault>::new();
+    builder.include(ikeys);
+    builder.include(&ikeys[..100]);
+    builder.exclude(ekeys);
+    let (cascade, stats) = builder.build_with_stats().expect("failed build");
+    println!("test_cascade_build_stats {}", stats);
+
+    // level statistics are merged, keys are counted once per level.
+    let num_keys: usize = cascade.levels.iter().map(|l| l.len().unwrap()).sum();
+    let array_length: usize = cascade.levels.iter().map(|l| l.finger_prints.len()).sum();
+    assert_eq!(stats.num_unique, num_keys);
+    assert_eq!(stats.num_duplicates, 100);
+    assert_eq!(stats.num_digests, num_keys + 100);
+    assert_eq!(stats.seed, cascade.levels[0].seed);
+    assert_eq!(stats.array_length, array_length);
+    assert!(stats.bits_per_key > 8.0 && stats.bits_per_key < 12.0);
+}
+
+#[test]
+fn test_cascade_rng_seed() {
+    let seed: u64 = random();
+    println!("test_cascade_rng_seed seed:{}", seed);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let keys: Vec<u64> = (0..110_000).map(|_| rng.gen::<u64>()).collect();
+    let (ikeys, ekeys) = keys.split_at(10_000);
+
+    let build = |rng_seed: u64| {
+        let mut builder = FilterCascadeBuilder::<BuildHasherDefault>::new();
+        builder.rng_seed = rng_seed;
+        builder.include(ikeys);
+        builder.exclude(ekeys);
+        builder.build().expect("failed build")
+    };
+
+    let (cascade1, cascade2) = (build(seed), build(seed));
+    assert_eq!(cascade1.levels.len(), cascade2.levels.len());
+    for (a, b) in cascade1.levels.iter().zip(cascade2.levels.iter()) {
+        assert!(a == b);
+    }
+    let cascade3 = build(seed.wrapping_add(1));
+    assert_ne!(cascade1.levels[0].seed, cascade3.levels[0].seed);
+    for key in ikeys.iter() {
+        assert!(cascade3.contains(key), "key {} not included", key);
+    }
+    for key in ekeys.iter() {
+        assert!(!cascade3.contains(key), "key {} not excluded", key);
+    }
+
+    let mut builder = FilterCascadeBuilder::<BuildHasherDefault>::new();
+    let rng_seed = builder.set_random_seed();
+    assert_eq!(builder.rng_seed, rng_seed);
+    assert_ne!(builder.set_random_seed(), rng_seed);
+}
+
+#[cfg(feature = "cbordata")]
+#[test]
+fn test_cascade_cbor() {
+    let seed: u64 = random();
+    println!("test_cascade_cbor seed:{}", seed);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let keys: Vec<u64> = (0..200_000).map(|_| rng.gen::<u64>()).collect();
+    let (ikeys, ekeys) = keys.split_at(10_000);
+
+    let cascade = {
+        let mut builder = FilterCascadeBuilder::<BuildHasherDefault>::new();
+        builder.include(ikeys);
+        builder.exclude(ekeys);
+        builder.build().expect("failed build")
+    };
+    let levels = cascade.levels.len();
+
+    let cascade = {
+        let val = cascade.into_cbor().unwrap();
+        FilterCascade::<BuildHasherDefault>::from_cbor(val).unwrap()
+    };
+    assert_eq!(cascade.levels.len(), levels);
+
+    for key in ikeys.iter() {
+        assert!(cascade.contains(key), "included key {}", key);
+    }
+    for key in ekeys.iter() {
+        assert!(!cascade.contains(key), "excluded key {}", key);
+    }
+}
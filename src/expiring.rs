@@ -0,0 +1,367 @@
+//! Filter that forgets keys after a time window, using a ring of binary fuse
+//! filters.
+//!
+//! Refer to [ExpiringFilter] for details.
+
+use std::borrow::Borrow;
+#[allow(unused_imports)]
+use std::collections::hash_map::DefaultHasher;
+#[allow(unused_imports)]
+use std::collections::hash_map::RandomState;
+use std::collections::HashSet;
+use std::collections::VecDeque;
+use std::hash::BuildHasher;
+use std::hash::Hash;
+use std::hash::Hasher;
+
+#[cfg(feature = "cbordata")]
+use cbordata::Cbor;
+#[cfg(feature = "cbordata")]
+use cbordata::Cborize;
+#[cfg(feature = "cbordata")]
+use cbordata::FromCbor;
+#[cfg(feature = "cbordata")]
+use cbordata::IntoCbor;
+#[cfg(feature = "cbordata")]
+use cbordata::{self as cbor};
+
+use crate::fuse::FUSE_RNG_SEED;
+use crate::fuse8::Fuse8;
+use crate::fuse8::Fuse8Builder;
+use crate::hasher::random_seed;
+use crate::BuildHasherDefault;
+use crate::Filter;
+use crate::NoHash;
+use crate::Result;
+
+/// Type ExpiringFilter is probabilistic data-structure to test whether an element
+/// was seen within a sliding time window.
+///
+/// Time is divided into buckets of `bucket_width`, and the window spans
+/// `num_buckets` buckets, including the current one. Keys inserted during the
+/// current bucket are buffered, and on [ExpiringFilter::rotate] they are sealed
+/// into an immutable [Fuse8] filter, while sealed buckets that fell out of the
+/// window are dropped. Lookups are exact on buffered keys, and check every sealed
+/// bucket, so the false positive rate grows with the number of buckets, about
+/// `(num_buckets - 1) / 256`.
+///
+/// Time is supplied by the caller, as `now`, in any unit, say seconds since epoch,
+/// as long as `bucket_width` is in the same unit. Keys expire only on rotation,
+/// that is, a key is reported as present for at least `num_buckets - 1` and at most
+/// `num_buckets` bucket widths after insertion, given that `rotate` is called
+/// periodically.
+///
+/// Example:
+/// ```
+/// # use xorfilter::ExpiringFilter;
+///
+/// // one hour buckets, for a window of 3 hours.
+/// let mut filter: ExpiringFilter = ExpiringFilter::new(3600, 3, 0);
+///
+/// filter.insert("foo");
+/// filter.rotate(3600).unwrap();
+/// filter.insert("bar");
+/// assert!(filter.contains("foo"));
+///
+/// filter.rotate(3 * 3600).unwrap();
+/// assert!(!filter.contains("foo"));
+/// assert!(filter.contains("bar"));
+/// ```
+///
+/// If `RandomState` is used as BuildHasher, `std` has got this to say
+/// > _A particular instance RandomState will create the same instances
+/// > of Hasher, but the hashers created by two different RandomState
+/// > instances are unlikely to produce the same result for the same values._
+///
+/// If [DefaultHasher] is used as BuildHasher, `std` has got this to say,
+/// > _The internal algorithm is not specified, and so its hashes
+/// > should not be relied upon over releases._
+#[derive(Clone)]
+pub struct ExpiringFilter<H = BuildHasherDefault>
+where H: BuildHasher
+{
+    pub hash_builder: H,
+    /// Width of each bucket, in caller's time unit.
+    pub bucket_width: u64,
+    /// Number of buckets in the window, including the current bucket.
+    pub num_buckets: usize,
+    /// Seed for sealing buckets, not serialized.
+    pub rng_seed: u64,
+    epoch: u64,
+    buffer: HashSet<u64>,
+    buckets: VecDeque<(u64, Fuse8<NoHash>)>,
+}
+
+impl<H> ExpiringFilter<H>
+where H: BuildHasher
+{
+    /// New expiring filter with `num_buckets` buckets of `bucket_width` each, whose
+    /// current bucket starts at `now`, initialized with [BuildHasherDefault].
+    ///
+    /// Panics if `bucket_width` or `num_buckets` is ZERO.
+    pub fn new(bucket_width: u64, num_buckets: usize, now: u64) -> Self
+    where H: Default {
+        Self::with_hasher(bucket_width, num_buckets, now, H::default())
+    }
+
+    /// New expiring filter with `num_buckets` buckets of `bucket_width` each, whose
+    /// current bucket starts at `now`, initialized with supplied `hasher`.
+    ///
+    /// Panics if `bucket_width` or `num_buckets` is ZERO.
+    pub fn with_hasher(
+        bucket_width: u64,
+        num_buckets: usize,
+        now: u64,
+        hash_builder: H,
+    ) -> Self {
+        assert!(bucket_width > 0, "bucket_width is ZERO");
+        assert!(num_buckets > 0, "num_buckets is ZERO");
+
+        ExpiringFilter {
+            hash_builder,
+            bucket_width,
+            num_buckets,
+            rng_seed: FUSE_RNG_SEED,
+            epoch: now,
+            buffer: HashSet::default(),
+            buckets: VecDeque::default(),
+        }
+    }
+
+    /// Seed the builds with a random `rng_seed`, and return the same.
+    pub fn set_random_seed(&mut self) -> u64 {
+        self.rng_seed = random_seed();
+        self.rng_seed
+    }
+
+    #[allow(clippy::len_without_is_empty)]
+    /// Return the number of keys within the window, including buffered keys. Keys
+    /// inserted in more than one bucket are counted once per bucket.
+    pub fn len(&self) -> Option<usize> {
+        let n: usize = self.buckets.iter().filter_map(|(_, b)| b.len()).sum();
+        Some(n + self.buffer.len())
+    }
+
+    /// Return the start time of the current bucket.
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    /// Return the sealed buckets, oldest first, along with their start time.
+    /// Buckets without keys are not sealed.
+    pub fn buckets(&self) -> impl Iterator<Item = (u64, &Fuse8<NoHash>)> {
+        self.buckets.iter().map(|(start, bucket)| (*start, bucket))
+    }
+
+    /// Return the number of keys buffered in the current bucket.
+    pub fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Return the size of index.
+    #[inline]
+    pub fn size_of(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + (self.buffer.capacity() * std::mem::size_of::<u64>())
+            + self.buckets.iter().map(|(_, b)| 8 + b.size_of()).sum::<usize>()
+    }
+
+    /// Insert 64-bit digest of a single key into the current bucket. Digest for the
+    /// key shall be generated using the default-hasher or via hasher supplied via
+    /// [ExpiringFilter::with_hasher] method.
+    pub fn insert<K: ?Sized + Hash>(&mut self, key: &K) {
+        let digest = self.hash(key);
+        self.insert_digest(digest)
+    }
+
+    /// Insert pre-computed 64-bit digest of a single key into the current bucket.
+    pub fn insert_digest(&mut self, digest: u64) {
+        self.buffer.insert(digest);
+    }
+
+    /// Populate the current bucket with 64-bit digests for a collection of keys,
+    /// owned or borrowed. Digest for key shall be generated using the default-hasher or
+    /// via hasher supplied via [ExpiringFilter::with_hasher] method.
+    pub fn populate<I>(&mut self, keys: I)
+    where
+        I: IntoIterator,
+        I::Item: Hash,
+    {
+        for key in keys.into_iter() {
+            self.insert(&key)
+        }
+    }
+
+    /// Populate the current bucket with pre-compute collection of 64-bit digests.
+    pub fn populate_digests<I>(&mut self, digests: I)
+    where
+        I: IntoIterator,
+        I::Item: Borrow<u64>,
+    {
+        self.buffer.extend(digests.into_iter().map(|digest| *digest.borrow()))
+    }
+
+    /// Rotate the ring as of time `now`. If `now` is past the current bucket, the
+    /// buffer is sealed into a new bucket, and sealed buckets that fell out of the
+    /// window are dropped. Return the number of bucket widths the ring moved by,
+    /// which is ZERO if `now` is within the current bucket, or before it.
+    ///
+    /// Sealing the buffer can fail, in which case the ring is not rotated.
+    pub fn rotate(&mut self, now: u64) -> Result<u64> {
+        let elapsed = now.saturating_sub(self.epoch) / self.bucket_width;
+        if elapsed == 0 {
+            return Ok(0);
+        }
+
+        if !self.buffer.is_empty() {
+            let digests: Vec<u64> = self.buffer.iter().copied().collect();
+            let mut builder = Fuse8Builder::with_hasher(NoHash);
+            builder.rng_seed = self.rng_seed;
+            let bucket = builder.build_from_digests(&digests)?;
+            self.buckets.push_back((self.epoch, bucket));
+            self.buffer.clear();
+        }
+
+        self.epoch += elapsed * self.bucket_width;
+
+        let window = (self.num_buckets as u64).saturating_mul(self.bucket_width);
+        while let Some((start, _)) = self.buckets.front() {
+            match self.epoch - start >= window {
+                true => self.buckets.pop_front(),
+                false => break,
+            };
+        }
+
+        Ok(elapsed)
+    }
+
+    /// Contains tell you whether the key was likely inserted within the window,
+    /// with false positive rate.
+    pub fn contains<K: ?Sized + Hash>(&self, key: &K) -> bool {
+        let digest = self.hash(key);
+        self.contains_digest(digest)
+    }
+
+    /// Contains tell you whether the key, as pre-computed digest form, was likely
+    /// inserted within the window, with false positive rate.
+    pub fn contains_digest(&self, digest: u64) -> bool {
+        self.buffer.contains(&digest)
+            || self.buckets.iter().rev().any(|(_, b)| b.contains_digest(digest))
+    }
+
+    pub fn get_hasher(&self) -> H::Hasher {
+        self.hash_builder.build_hasher()
+    }
+
+    /// Calculate hash of a key.
+    #[inline]
+    pub fn hash<K: Hash + ?Sized>(&self, key: &K) -> u64 {
+        let mut hasher = self.get_hasher();
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+impl<H> Filter for ExpiringFilter<H>
+where H: BuildHasher
+{
+    fn contains_digest(&self, digest: u64) -> bool {
+        self.contains_digest(digest)
+    }
+
+    fn len(&self) -> Option<usize> {
+        self.len()
+    }
+
+    fn size_of(&self) -> usize {
+        self.size_of()
+    }
+}
+
+//------ Implement cbordata related functionalities
+
+// Intermediate type to serialize and de-serialized ExpiringFilter into bytes, start
+// time of each sealed bucket is held in `starts`.
+#[cfg(feature = "cbordata")]
+#[derive(Cborize)]
+struct CborExpiringFilter {
+    hash_builder: Vec<u8>,
+    bucket_width: u64,
+    num_buckets: usize,
+    epoch: u64,
+    buffer: Vec<u64>,
+    starts: Vec<u64>,
+    buckets: Vec<Cbor>,
+}
+
+#[cfg(feature = "cbordata")]
+impl CborExpiringFilter {
+    const ID: &'static str = "expiringfilter/0.0.1";
+}
+
+#[cfg(feature = "cbordata")]
+impl<H> IntoCbor for ExpiringFilter<H>
+where H: BuildHasher + Into<Vec<u8>>
+{
+    fn into_cbor(self) -> cbor::Result<Cbor> {
+        let (mut starts, mut buckets) = (vec![], vec![]);
+        for (start, bucket) in self.buckets.into_iter() {
+            starts.push(start);
+            buckets.push(bucket.into_cbor()?);
+        }
+
+        let val = CborExpiringFilter {
+            hash_builder: self.hash_builder.into(),
+            bucket_width: self.bucket_width,
+            num_buckets: self.num_buckets,
+            epoch: self.epoch,
+            buffer: self.buffer.into_iter().collect(),
+            starts,
+            buckets,
+        };
+        val.into_cbor()
+    }
+}
+
+#[cfg(feature = "cbordata")]
+impl<H> FromCbor for ExpiringFilter<H>
+where H: BuildHasher + From<Vec<u8>>
+{
+    fn from_cbor(val: Cbor) -> cbor::Result<Self> {
+        let val = CborExpiringFilter::from_cbor(val)?;
+
+        if val.starts.len() != val.buckets.len() || val.bucket_width == 0 {
+            let msg = format!(
+                "starts:{} buckets:{} bucket_width:{}",
+                val.starts.len(),
+                val.buckets.len(),
+                val.bucket_width
+            );
+            return Err(cbor::Error::FailConvert(
+                format!("{}:{}", file!(), line!()),
+                msg,
+            ));
+        }
+
+        let mut buckets = VecDeque::default();
+        for (start, bucket) in val.starts.into_iter().zip(val.buckets) {
+            buckets.push_back((start, Fuse8::<NoHash>::from_cbor(bucket)?));
+        }
+
+        let filter = ExpiringFilter {
+            hash_builder: val.hash_builder.into(),
+            bucket_width: val.bucket_width,
+            num_buckets: val.num_buckets,
+            rng_seed: FUSE_RNG_SEED,
+            epoch: val.epoch,
+            buffer: val.buffer.into_iter().collect(),
+            buckets,
+        };
+
+        Ok(filter)
+    }
+}
+
+#[cfg(test)]
+#[path = "expiring_test.rs"]
+mod expiring_test;
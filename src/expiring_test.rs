@@ -0,0 +1,155 @@
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+
+#[cfg(feature = "cbordata")]
+use cbordata::FromCbor;
+#[cfg(feature = "cbordata")]
+use cbordata::IntoCbor;
+use rand::prelude::random;
+use rand::rngs::StdRng;
+use rand::Rng;
+use rand::SeedableRng;
+
+use super::*;
+
+fn test_expiring_window<H>(name: &str, seed: u64, num_buckets: usize, size: usize)
+where H: BuildHasher + Clone + Default {
+    println!(
+        "test_expiring_window<{}> num_buckets:{} size:{}",
+        name, num_buckets, size
+    );
+    let mut rng = StdRng::seed_from_u64(seed);
+    let (width, start) = (3600, 1_000_000);
+    let rounds = num_buckets * 3;
+
+    let keys: Vec<Vec<u64>> =
+        (0..rounds).map(|_| (0..size).map(|_| rng.gen::<u64>()).collect()).collect();
+
+    let mut filter = ExpiringFilter::<H>::new(width, num_buckets, start);
+    for (round, round_keys) in keys.iter().enumerate() {
+        let (keys1, keys2) = round_keys.split_at(size / 2);
+        // populate/populate_digests api, at some point within the bucket.
+        let now = filter.epoch() + rng.gen_range(0, width);
+        assert_eq!(filter.rotate(now).expect("failed rotate"), 0);
+        filter.populate(keys1);
+        let digests: Vec<u64> = keys2.iter().map(|k| filter.hash(k)).collect();
+        filter.populate_digests(digests.iter());
+        assert_eq!(filter.buffered(), size);
+
+        // keys inserted within the window shall be present.
+        let window = round.saturating_sub(num_buckets - 1)..=round;
+        for round_keys in keys[window].iter() {
+            for key in round_keys.iter() {
+                assert!(filter.contains(key), "key {} not present", key);
+            }
+        }
+        // keys of expired buckets are absent, but for false positives.
+        let (mut present, mut total) = (0_f64, 0_f64);
+        for round_keys in keys[..round.saturating_sub(num_buckets - 1)].iter() {
+            for key in round_keys.iter() {
+                present += if filter.contains(key) { 1.0 } else { 0.0 };
+                total += 1.0;
+            }
+        }
+        if total > 0.0 {
+            let rate = present / total;
+            let limit = (num_buckets as f64) / 256.0 * 1.5;
+            assert!(rate < limit, "rate({}) >= {}", rate, limit);
+        }
+
+        let now = filter.epoch() + width + rng.gen_range(0, width);
+        assert_eq!(filter.rotate(now).expect("failed rotate"), 1);
+        assert_eq!(filter.buffered(), 0);
+        assert!(filter.buckets().count() < num_buckets);
+    }
+    assert_eq!(filter.len(), Some(size * (num_buckets - 1)));
+
+    // going back in time is a no-op.
+    let epoch = filter.epoch();
+    assert_eq!(filter.rotate(epoch - 1).expect("failed rotate"), 0);
+    assert_eq!(filter.epoch(), epoch);
+
+    // skipping buckets expires all but the most recent ones.
+    let n = std::cmp::max(num_buckets as u64, 2) - 1;
+    let now = epoch + (n * width);
+    assert_eq!(filter.rotate(now).expect("failed rotate"), n);
+    assert_eq!(filter.epoch(), now);
+    assert_eq!(filter.buckets().count(), 0);
+    assert_eq!(filter.len(), Some(0));
+}
+
+#[test]
+fn test_expiring() {
+    let mut seed: u64 = random();
+    println!("test_expiring seed:{}", seed);
+
+    for (num_buckets, size) in [(1, 1000), (4, 10_000), (24, 2_000)].iter() {
+        seed = seed.wrapping_add(*size as u64);
+        test_expiring_window::<RandomState>("RandomState", seed, *num_buckets, *size);
+        test_expiring_window::<BuildHasherDefault>(
+            "BuildHasherDefault",
+            seed,
+            *num_buckets,
+            *size,
+        );
+    }
+}
+
+#[test]
+fn test_expiring_rng_seed() {
+    let seed: u64 = random();
+    println!("test_expiring_rng_seed seed:{}", seed);
+
+    let keys: Vec<u64> = (0..10_000).collect();
+    let build = |rng_seed: u64| {
+        let mut filter = ExpiringFilter::<BuildHasherDefault>::new(60, 4, 0);
+        filter.rng_seed = rng_seed;
+        filter.populate(&keys);
+        filter.rotate(60).expect("failed rotate");
+        let (_, bucket) = filter.buckets().next().unwrap();
+        bucket.clone()
+    };
+
+    assert!(build(seed) == build(seed));
+    assert_ne!(build(seed).seed, build(seed.wrapping_add(1)).seed);
+
+    let mut filter = ExpiringFilter::<BuildHasherDefault>::new(60, 4, 0);
+    let rng_seed = filter.set_random_seed();
+    assert_eq!(filter.rng_seed, rng_seed);
+}
+
+#[cfg(feature = "cbordata")]
+#[test]
+fn test_expiring_cbor() {
+    let seed: u64 = random();
+    println!("test_expiring_cbor seed:{}", seed);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let keys: Vec<u64> = (0..40_000).map(|_| rng.gen::<u64>()).collect();
+
+    let filter = {
+        let mut filter = ExpiringFilter::<BuildHasherDefault>::new(60, 8, 0);
+        for (i, chunk) in keys.chunks(10_000).enumerate() {
+            filter.rotate((i as u64) * 60).expect("failed rotate");
+            filter.populate(chunk);
+        }
+        filter
+    };
+    let (epoch, buckets, buffered) =
+        (filter.epoch(), filter.buckets().count(), filter.buffered());
+
+    let mut filter = {
+        let val = filter.into_cbor().unwrap();
+        ExpiringFilter::<BuildHasherDefault>::from_cbor(val).unwrap()
+    };
+    assert_eq!(filter.epoch(), epoch);
+    assert_eq!(filter.buckets().count(), buckets);
+    assert_eq!(filter.buffered(), buffered);
+
+    for key in keys.iter() {
+        assert!(filter.contains(key), "key {} not present", key);
+    }
+
+    filter.rotate(epoch + (8 * 60)).expect("failed rotate");
+    assert_eq!(filter.len(), Some(0));
+}
@@ -0,0 +1,391 @@
+//! Build filters for key sets larger than memory, spilling digests to files.
+//!
+//! Refer to [ExternalBuilder] for details.
+
+use std::borrow::Borrow;
+#[allow(unused_imports)]
+use std::collections::hash_map::DefaultHasher;
+#[allow(unused_imports)]
+use std::collections::hash_map::RandomState;
+use std::convert::TryInto;
+use std::fs;
+use std::hash::BuildHasher;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::io::BufReader;
+use std::io::BufWriter;
+use std::io::Read;
+use std::io::Write;
+use std::path;
+
+use crate::fuse::FUSE_RNG_SEED;
+use crate::fuse8::Fuse8;
+use crate::fuse8::Fuse8Builder;
+use crate::hasher::random_seed;
+use crate::sharded::build_dedup_shard;
+use crate::sharded::shard_of;
+use crate::BuildHasherDefault;
+use crate::BuildStats;
+use crate::Error;
+use crate::NoHash;
+use crate::Result;
+use crate::ShardedFilter;
+use crate::MAX_SHARD_BITS;
+
+/// Estimated peak memory, in bytes, needed per key while building a [Fuse8]
+/// filter, including the digests, peeling order and fingerprints.
+pub const BUILD_BYTES_PER_KEY: usize = 40;
+
+/// File signature write on first 4 bytes of output file.
+/// ^ stands for xor
+/// S stands for sharded
+/// 8 stands for 8-bit fingerprints
+/// 1 stands for version 1
+const SIGNATURE_V1: [u8; 4] = [b'^', b'S', 8, 1];
+
+/// Builds a sharded [Fuse8] filter for key sets larger than memory, with peak memory
+/// bounded by `memory_budget`.
+///
+/// Digests are buffered in memory upto `memory_budget`, and then spilled into one
+/// temporary file per shard, under `dir`. Shards are picked by high bits of the
+/// digest, refer to [ShardedFilter], and their number is chosen so that each shard,
+/// for an expected `capacity` number of keys, can be built within `memory_budget`.
+/// While building, shards are built one at a time, each one from its own file,
+/// and fingerprints are written straight to the output file, so that neither the
+/// digests nor the filter are held in memory as a whole.
+///
+/// Built filter can be loaded using [ShardedFilter::read_file], which needs about
+/// 9 bits per key of memory.
+///
+/// `memory_budget` is approximate, if more than `capacity` number of keys are
+/// inserted, or if digests are skewed, shards might need more memory than
+/// budgeted. `dir` is expected to be exclusive to the builder.
+///
+/// Example:
+/// ```
+/// # use xorfilter::{ExternalBuilder, ShardedFilter};
+///
+/// let dir = std::env::temp_dir().join("xorfilter-external-doc");
+/// let mut b: ExternalBuilder = ExternalBuilder::new(&dir, 1_000, 64 * 1024);
+///
+/// b.populate(&["foo", "bar", "baz"]).unwrap();
+/// let file = dir.join("filter.data");
+/// assert_eq!(b.build(&file).unwrap(), 3);
+///
+/// let filter: ShardedFilter<_> = ShardedFilter::read_file(&file, b.hash_builder).unwrap();
+/// assert!(filter.contains("foo"));
+/// # std::fs::remove_dir_all(&dir).unwrap();
+/// ```
+///
+/// If `RandomState` is used as BuildHasher, `std` has got this to say
+/// > _A particular instance RandomState will create the same instances
+/// > of Hasher, but the hashers created by two different RandomState
+/// > instances are unlikely to produce the same result for the same values._
+///
+/// If [DefaultHasher] is used as BuildHasher, `std` has got this to say,
+/// > _The internal algorithm is not specified, and so its hashes
+/// > should not be relied upon over releases._
+#[derive(Clone, Debug)]
+pub struct ExternalBuilder<H = BuildHasherDefault>
+where H: BuildHasher + Clone
+{
+    buffer: Vec<u64>,
+    pub dir: path::PathBuf,
+    pub memory_budget: usize,
+    pub shard_bits: u32,
+    pub num_spills: usize,
+    pub hash_builder: H,
+    /// Seed for building the shards.
+    pub rng_seed: u64,
+}
+
+impl<H> ExternalBuilder<H>
+where H: BuildHasher + Clone
+{
+    /// New external builder, spilling into `dir`, for an expected `capacity` number
+    /// of keys and `memory_budget` in bytes, initialized with [BuildHasherDefault].
+    pub fn new<P: AsRef<path::Path>>(
+        dir: P,
+        capacity: usize,
+        memory_budget: usize,
+    ) -> Self
+    where
+        H: Default,
+    {
+        Self::with_hasher(dir, capacity, memory_budget, H::default())
+    }
+
+    /// New external builder, spilling into `dir`, for an expected `capacity` number
+    /// of keys and `memory_budget` in bytes, initialized with supplied `hasher`.
+    pub fn with_hasher<P: AsRef<path::Path>>(
+        dir: P,
+        capacity: usize,
+        memory_budget: usize,
+        hash_builder: H,
+    ) -> Self {
+        let per_shard = std::cmp::max(memory_budget / BUILD_BYTES_PER_KEY, 1);
+        let shards = capacity.div_ceil(per_shard);
+        let shard_bits = match shards {
+            0 | 1 => 0,
+            shards => 64 - ((shards - 1) as u64).leading_zeros(),
+        };
+
+        ExternalBuilder {
+            buffer: Vec::default(),
+            dir: dir.as_ref().to_path_buf(),
+            memory_budget,
+            shard_bits: std::cmp::min(shard_bits, MAX_SHARD_BITS),
+            num_spills: 0,
+            hash_builder,
+            rng_seed: FUSE_RNG_SEED,
+        }
+    }
+
+    /// Seed the builds with a random `rng_seed`, and return the same.
+    pub fn set_random_seed(&mut self) -> u64 {
+        self.rng_seed = random_seed();
+        self.rng_seed
+    }
+
+    pub fn get_hasher(&self) -> H::Hasher {
+        self.hash_builder.build_hasher()
+    }
+
+    /// Calculate hash of a key.
+    #[inline]
+    pub fn hash<K: Hash + ?Sized>(&self, key: &K) -> u64 {
+        let mut hasher = self.get_hasher();
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Insert 64-bit digest of a single key. Digest for the key shall be generated
+    /// using the default-hasher or via hasher supplied via
+    /// [ExternalBuilder::with_hasher] method.
+    ///
+    /// Spilling buffered digests to disk can fail.
+    pub fn insert<K: ?Sized + Hash>(&mut self, key: &K) -> Result<()> {
+        let digest = self.hash(key);
+        self.insert_digest(digest)
+    }
+
+    /// Insert pre-computed 64-bit digest of a single key.
+    pub fn insert_digest(&mut self, digest: u64) -> Result<()> {
+        if self.buffer.capacity() == 0 {
+            self.buffer.reserve_exact(std::cmp::max(self.memory_budget / 8, 1));
+        }
+        self.buffer.push(digest);
+        match self.buffer.len() * 8 >= self.memory_budget {
+            true => self.spill(),
+            false => Ok(()),
+        }
+    }
+
+    /// Populate with 64-bit digests for a collection of keys, owned or borrowed.
+    /// Digest for key shall be generated using the default-hasher or via hasher
+    /// supplied via [ExternalBuilder::with_hasher] method.
+    pub fn populate<I>(&mut self, keys: I) -> Result<()>
+    where
+        I: IntoIterator,
+        I::Item: Hash,
+    {
+        for key in keys.into_iter() {
+            self.insert(&key)?
+        }
+        Ok(())
+    }
+
+    /// Populate with pre-compute collection of 64-bit digests.
+    pub fn populate_digests<I>(&mut self, digests: I) -> Result<()>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<u64>,
+    {
+        for digest in digests.into_iter() {
+            self.insert_digest(*digest.borrow())?
+        }
+        Ok(())
+    }
+
+    /// Spill buffered digests into shard files. Digests are sorted, which groups
+    /// them by shard, and appended to their shard file.
+    pub fn spill(&mut self) -> Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        err_at!(IOError, fs::create_dir_all(&self.dir))?;
+
+        self.buffer.sort_unstable();
+        let mut start = 0;
+        while start < self.buffer.len() {
+            let shard = self.shard_of(self.buffer[start]);
+            let n = self.buffer[start..]
+                .iter()
+                .take_while(|d| self.shard_of(**d) == shard)
+                .count();
+
+            let file = {
+                let mut opts = fs::OpenOptions::new();
+                err_at!(
+                    IOError,
+                    opts.create(true).append(true).open(self.shard_file(shard))
+                )?
+            };
+            let mut w = BufWriter::new(file);
+            for digest in self.buffer[start..start + n].iter() {
+                err_at!(IOError, w.write_all(&digest.to_be_bytes()))?;
+            }
+            err_at!(IOError, w.flush())?;
+            start += n;
+        }
+
+        self.buffer.clear();
+        self.num_spills += 1;
+        Ok(())
+    }
+
+    /// Build filter for keys that where previously inserted, writing it to `file`,
+    /// and return the number of unique keys. Digests are de-duplicated within each
+    /// shard. Filter is written to a temporary file, next to `file`, and renamed to
+    /// `file` once complete, after which shard files are removed. If the build fails,
+    /// `file` is left untouched and shard files are retained, so that the build can
+    /// be retried.
+    pub fn build<P: AsRef<path::Path>>(&mut self, file: P) -> Result<usize> {
+        self.build_with_stats(file).map(|(num_keys, _)| num_keys)
+    }
+
+    /// Same as [ExternalBuilder::build], and return statistics on the build, merged
+    /// across shards. Time spent hashing and spilling digests, and reading them
+    /// back, is not accounted.
+    pub fn build_with_stats<P: AsRef<path::Path>>(
+        &mut self,
+        file: P,
+    ) -> Result<(usize, BuildStats)> {
+        self.spill()?;
+        self.buffer = Vec::default();
+
+        let tmp_file = {
+            let mut tmp_file = file.as_ref().as_os_str().to_os_string();
+            tmp_file.push(".tmp");
+            path::PathBuf::from(tmp_file)
+        };
+        let res = self.write_shards(&tmp_file).and_then(|stats| {
+            err_at!(IOError, fs::rename(&tmp_file, file.as_ref()))?;
+            Ok(stats)
+        });
+        let stats = match res {
+            Ok(stats) => stats,
+            Err(err) => {
+                fs::remove_file(&tmp_file).ok();
+                return Err(err);
+            }
+        };
+
+        for shard in 0..(1_usize << self.shard_bits) {
+            let shard_file = self.shard_file(shard);
+            if shard_file.exists() {
+                err_at!(IOError, fs::remove_file(&shard_file))?;
+            }
+        }
+
+        self.num_spills = 0;
+        let stats = BuildStats::merge(&stats);
+        Ok((stats.num_unique, stats))
+    }
+
+    // build shards one at a time, from their shard files, and write them to `file`,
+    // return statistics for each shard.
+    fn write_shards(&self, file: &path::Path) -> Result<Vec<BuildStats>> {
+        let mut w = BufWriter::new(err_at!(IOError, fs::File::create(file))?);
+        err_at!(IOError, w.write_all(&SIGNATURE_V1))?;
+        err_at!(IOError, w.write_all(&self.shard_bits.to_be_bytes()))?;
+
+        let build = |digests: &[u64], rng_seed| {
+            let mut builder = Fuse8Builder::with_hasher(NoHash);
+            builder.rng_seed = rng_seed;
+            builder.build_from_digests_with_stats(digests)
+        };
+
+        let mut stats = vec![];
+        for shard in 0..(1_usize << self.shard_bits) {
+            let shard_file = self.shard_file(shard);
+            let mut digests = match shard_file.exists() {
+                true => read_digests(&shard_file)?,
+                false => Vec::default(),
+            };
+
+            let (filter, shard_stats) =
+                build_dedup_shard(&mut digests, self.rng_seed, &build)?;
+            filter.write_to(&mut w)?;
+            stats.push(shard_stats);
+        }
+        err_at!(IOError, w.flush())?;
+
+        Ok(stats)
+    }
+
+    #[inline]
+    fn shard_of(&self, digest: u64) -> usize {
+        shard_of(digest, self.shard_bits)
+    }
+
+    fn shard_file(&self, shard: usize) -> path::PathBuf {
+        self.dir.join(format!("shard-{}.digests", shard))
+    }
+}
+
+impl<H> ShardedFilter<Fuse8<NoHash>, H>
+where H: BuildHasher
+{
+    /// Read filter from `file`, that was built using [ExternalBuilder::build].
+    /// Supplied `hash_builder` shall be the same as the one used to build the
+    /// filter.
+    pub fn read_file<P: AsRef<path::Path>>(file: P, hash_builder: H) -> Result<Self> {
+        let mut r = BufReader::new(err_at!(IOError, fs::File::open(file.as_ref()))?);
+
+        let mut buf = [0_u8; 8];
+        err_at!(IOError, r.read_exact(&mut buf))?;
+        if buf[..4] != SIGNATURE_V1 {
+            err_at!(InvalidFormat, msg: "file signature incorrect {:?}", &buf[..4])?;
+        }
+        let shard_bits = u32::from_be_bytes(buf[4..].try_into().unwrap());
+        if shard_bits > MAX_SHARD_BITS {
+            err_at!(InvalidFormat, msg: "shard_bits {} > {}", shard_bits, MAX_SHARD_BITS)?;
+        }
+
+        let mut shards = vec![];
+        for _ in 0..(1_usize << shard_bits) {
+            shards.push(Fuse8::read_from(&mut r, NoHash)?);
+        }
+
+        let filter = ShardedFilter {
+            hash_builder,
+            shard_bits,
+            shards,
+        };
+
+        Ok(filter)
+    }
+}
+
+fn read_digests(file: &path::Path) -> Result<Vec<u64>> {
+    let len = err_at!(IOError, fs::metadata(file))?.len() as usize;
+    let n = len / 8;
+    if (n * 8) != len {
+        err_at!(InvalidFormat, msg: "{:?} length {} not multiple of 8", file, len)?;
+    }
+
+    let mut r = BufReader::new(err_at!(IOError, fs::File::open(file))?);
+    let mut digests = Vec::with_capacity(n);
+    let mut buf = [0_u8; 8];
+    for _ in 0..n {
+        err_at!(IOError, r.read_exact(&mut buf))?;
+        digests.push(u64::from_be_bytes(buf));
+    }
+
+    Ok(digests)
+}
+
+#[cfg(test)]
+#[path = "external_test.rs"]
+mod external_test;
@@ -0,0 +1,229 @@
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+
+use rand::prelude::random;
+use rand::rngs::StdRng;
+use rand::Rng;
+use rand::SeedableRng;
+
+use super::*;
+
+fn test_external_build<H>(name: &str, seed: u64, size: usize, budget: usize)
+where H: BuildHasher + Clone + Default {
+    println!(
+        "test_external_build<{}> size:{} budget:{}",
+        name, size, budget
+    );
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let dir = std::env::temp_dir().join(format!("xorfilter-external-{}", seed));
+    let file = dir.join("filter.data");
+    fs::create_dir_all(&dir).unwrap();
+
+    let keys: Vec<u64> = (0..size).map(|_| rng.gen::<u64>()).collect();
+    let (keys1, keys2) = keys.split_at(size / 2);
+
+    let mut builder = ExternalBuilder::<H>::new(&dir, size, budget);
+    // populate api
+    builder.populate(keys1).expect("failed populate");
+    // populate_digests api
+    let digests: Vec<u64> = keys2.iter().map(|k| builder.hash(k)).collect();
+    builder.populate_digests(digests.iter()).expect("failed populate_digests");
+    // insert api, duplicates are removed while building.
+    if let Some(key) = keys.first() {
+        builder.insert(key).expect("failed insert");
+    }
+    println!(
+        "test_external_build<{}> shard_bits:{} spills:{}",
+        name, builder.shard_bits, builder.num_spills
+    );
+    assert!((size * 8) < budget || builder.num_spills > 0);
+
+    assert_eq!(builder.build(&file).expect("failed build"), size);
+    // shard files are removed after build.
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+    let filter =
+        ShardedFilter::<Fuse8<NoHash>, H>::read_file(&file, builder.hash_builder.clone())
+            .expect("failed read_file");
+    assert_eq!(filter.shard_bits, builder.shard_bits);
+    assert_eq!(filter.len(), Some(size));
+    // each shard fits within the budget.
+    for shard in filter.shards.iter() {
+        let n = shard.len().unwrap();
+        assert!(n * BUILD_BYTES_PER_KEY < budget * 2, "shard {} keys", n);
+    }
+
+    for key in keys.iter() {
+        assert!(filter.contains(key), "key {} not present", key);
+    }
+
+    let (falsesize, mut matches) = (1_000_000, 0_f64);
+    for _ in 0..falsesize {
+        if filter.contains(&rng.gen::<u64>()) {
+            matches += 1_f64;
+        }
+    }
+    let rate = matches / (falsesize as f64);
+    println!("test_external_build<{}> false positive rate {}", name, rate);
+    assert!(rate < 0.006, "rate({}) >= 0.006", rate);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_external() {
+    let mut seed: u64 = random();
+    println!("test_external seed:{}", seed);
+
+    for (size, budget) in [(0, 1024), (1000, 1 << 20), (1_000_000, 1 << 20)].iter() {
+        seed = seed.wrapping_add(*size as u64);
+        test_external_build::<RandomState>("RandomState", seed, *size, *budget);
+        test_external_build::<BuildHasherDefault>(
+            "BuildHasherDefault",
+            seed,
+            *size,
+            *budget,
+        );
+    }
+}
+
+#[test]
+fn test_external_build_stats() {
+    let seed: u64 = random();
+    println!("test_external_build_stats seed:{}", seed);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let dir = std::env::temp_dir().join(format!("xorfilter-external-stats-{}", seed));
+    let file = dir.join("filter.data");
+
+    let keys: Vec<u64> = (0..100_000).map(|_| rng.gen::<u64>()).collect();
+
+    let mut builder = ExternalBuilder::<BuildHasherDefault>::new(&dir, 100_000, 1 << 20);
+    builder.populate(&keys).expect("failed populate");
+    builder.populate(&keys[..1000]).expect("failed populate");
+    let (num_keys, stats) = builder.build_with_stats(&file).expect("failed build");
+    println!("test_external_build_stats {}", stats);
+
+    assert_eq!(num_keys, keys.len());
+    assert_eq!(stats.num_digests, keys.len() + 1000);
+    assert_eq!(stats.num_unique, keys.len());
+    assert_eq!(stats.num_duplicates, 1000);
+    assert!(stats.bits_per_key > 8.0 && stats.bits_per_key < 12.0);
+
+    let filter =
+        ShardedFilter::<Fuse8<NoHash>, _>::read_file(&file, builder.hash_builder.clone())
+            .expect("failed read_file");
+    assert_eq!(stats.seed, filter.shards[0].seed);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_external_rebuild() {
+    let seed: u64 = random();
+    println!("test_external_rebuild seed:{}", seed);
+    let dir = std::env::temp_dir().join(format!("xorfilter-external-rebuild-{}", seed));
+    let file = dir.join("filter.data");
+
+    let mut builder = ExternalBuilder::<BuildHasherDefault>::new(&dir, 10_000, 1 << 16);
+    builder.populate(0..10_000_u64).expect("failed populate");
+    let num_shard_files = fs::read_dir(&dir).unwrap().count();
+    assert!(num_shard_files > 1, "{}", num_shard_files);
+
+    // a directory in place of `file` fails the build, after all shards are built.
+    fs::create_dir_all(file.join("blocker")).unwrap();
+    assert!(builder.build(&file).is_err());
+    // shard files are retained, and no temporary file is left behind.
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), num_shard_files + 1);
+
+    fs::remove_dir_all(&file).unwrap();
+    assert_eq!(builder.build(&file).expect("failed build"), 10_000);
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+    let filter =
+        ShardedFilter::<Fuse8<NoHash>>::read_file(&file, BuildHasherDefault::default())
+            .expect("failed read_file");
+    for key in 0..10_000_u64 {
+        assert!(filter.contains(&key), "key {} not present", key);
+    }
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_external_corrupt() {
+    let seed: u64 = random();
+    let dir = std::env::temp_dir().join(format!("xorfilter-external-{}", seed));
+    let file = dir.join("filter.data");
+
+    let mut builder = ExternalBuilder::<BuildHasherDefault>::new(&dir, 10_000, 1 << 16);
+    builder.populate(0..10_000_u64).expect("failed populate");
+    builder.build(&file).expect("failed build");
+
+    let mut data = fs::read(&file).unwrap();
+    data.truncate(data.len() - 1);
+    fs::write(&file, &data).unwrap();
+    let res =
+        ShardedFilter::<Fuse8<NoHash>>::read_file(&file, BuildHasherDefault::default());
+    assert!(matches!(res, Err(Error::IOError(_, _))), "{:?}", res.err());
+
+    data[0] = b'x';
+    fs::write(&file, &data).unwrap();
+    let res =
+        ShardedFilter::<Fuse8<NoHash>>::read_file(&file, BuildHasherDefault::default());
+    assert!(
+        matches!(res, Err(Error::InvalidFormat(_, _))),
+        "{:?}",
+        res.err()
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_external_corrupt_header() {
+    let seed: u64 = random();
+    let dir = std::env::temp_dir().join(format!("xorfilter-external-{}", seed));
+    let file = dir.join("filter.data");
+
+    let mut builder = ExternalBuilder::<BuildHasherDefault>::new(&dir, 10_000, 1 << 16);
+    builder.populate(0..10_000_u64).expect("failed populate");
+    builder.build(&file).expect("failed build");
+    let data = fs::read(&file).unwrap();
+
+    // header of the first shard follows the 8 byte file header.
+    let u32_at = |n: usize| u32::from_be_bytes(data[n..n + 4].try_into().unwrap());
+    let (segment_length, fp_len) = (u32_at(8 + 16), u32_at(8 + 32));
+    let corruptions: Vec<Vec<(usize, u32)>> = vec![
+        // zero segment_length, with a mask that wraps around.
+        vec![(8 + 16, 0), (8 + 20, u32::MAX)],
+        // segment_length not a power of two.
+        vec![(8 + 16, 3), (8 + 20, 2)],
+        // segment_count_length inconsistent with segment_count.
+        vec![(8 + 28, u32_at(8 + 28) + segment_length)],
+        // fingerprints shorter than the segment layout.
+        vec![(8 + 32, fp_len - segment_length)],
+    ];
+
+    for corruption in corruptions.into_iter() {
+        let mut data = data.clone();
+        for (off, val) in corruption.iter() {
+            data[*off..*off + 4].copy_from_slice(&val.to_be_bytes());
+        }
+        fs::write(&file, &data).unwrap();
+
+        let res = ShardedFilter::<Fuse8<NoHash>>::read_file(
+            &file,
+            BuildHasherDefault::default(),
+        );
+        assert!(
+            matches!(res, Err(Error::InvalidFormat(_, _))),
+            "{:?} {:?}",
+            corruption,
+            res.err()
+        );
+    }
+
+    fs::remove_dir_all(&dir).unwrap();
+}
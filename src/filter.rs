@@ -0,0 +1,483 @@
+//! Construct filters from a target false positive rate.
+//!
+//! Refer to [FilterBuilder] for details. All filter types implement the [Filter]
+//! trait, a common lookup API over pre-computed digests.
+
+use std::borrow::Borrow;
+use std::hash::BuildHasher;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::fuse::Segments;
+use crate::fuse::FUSE_RNG_SEED;
+use crate::fuse16::Fuse16;
+use crate::fuse16::Fuse16Builder;
+use crate::fuse16::Fuse16x4;
+use crate::fuse16::Fuse16x4Builder;
+use crate::fuse32::Fuse32;
+use crate::fuse32::Fuse32Builder;
+use crate::fuse32::Fuse32x4;
+use crate::fuse32::Fuse32x4Builder;
+use crate::fuse8::Fuse8;
+use crate::fuse8::Fuse8Builder;
+use crate::fuse8::Fuse8x4;
+use crate::fuse8::Fuse8x4Builder;
+use crate::hasher::random_seed;
+use crate::xor16::Xor16;
+use crate::xor16::Xor16Builder;
+use crate::xor32::Xor32;
+use crate::xor32::Xor32Builder;
+use crate::xor8::builder::xor_block_length;
+use crate::xor8::builder::XOR_MAX_KEYS;
+use crate::xor8::builder::XOR_RNG_SEED;
+use crate::xor8::Xor8;
+use crate::xor8::Xor8Builder;
+use crate::xor_packed::XorPacked;
+use crate::xor_packed::XorPackedBuilder;
+use crate::BuildHasherDefault;
+use crate::BuildStats;
+use crate::Error;
+use crate::FusePacked;
+use crate::FusePackedBuilder;
+use crate::Result;
+
+/// Common lookup API implemented by all filter types.
+///
+/// Methods work on pre-computed 64-bit digests, so that the trait can be used as a
+/// trait object. Digests shall be computed using the same hasher that was used to
+/// build the filter.
+#[allow(clippy::len_without_is_empty)]
+pub trait Filter {
+    /// Contains tell you whether the key, as pre-computed digest form, is likely
+    /// part of the set, with false positive rate.
+    fn contains_digest(&self, digest: u64) -> bool;
+
+    /// Return the number of keys added/built into the bitmap index.
+    fn len(&self) -> Option<usize>;
+
+    /// Return the size of index.
+    fn size_of(&self) -> usize;
+}
+
+/// Builds the smallest filter that meets a target false positive rate.
+///
+/// A false positive rate of `fpp` needs fingerprints of `ceil(log2(1/fpp))` bits.
+/// Among the xor and binary fuse filters, the one that needs the least memory for
+/// the populated number of keys is picked. Fixed width filters, like [Fuse8], are
+/// preferred when the fingerprint width is 8, 16 or 32 bits, otherwise bit-packed
+/// filters, like [FusePacked], are used.
+///
+/// Example:
+/// ```
+/// # use xorfilter::FilterBuilder;
+///
+/// let mut b: FilterBuilder = FilterBuilder::with_fpp(0.001);
+///
+/// b.populate(&["foo", "bar"]);
+/// let filter = b.build().unwrap();
+///
+/// assert!(filter.contains("foo"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct FilterBuilder<H = BuildHasherDefault>
+where H: BuildHasher + Clone
+{
+    digests: Vec<u64>,
+    hash_time: Duration,
+    pub fpp: f64,
+    pub num_digests: usize,
+    pub hash_builder: H,
+    /// Seed for the build, `None` picks the chosen filter type's default.
+    pub rng_seed: Option<u64>,
+}
+
+impl<H> FilterBuilder<H>
+where H: BuildHasher + Clone
+{
+    /// New builder targeting false positive rate `fpp`, initialized with
+    /// [BuildHasherDefault].
+    pub fn with_fpp(fpp: f64) -> Self
+    where H: Default {
+        Self::with_hasher(fpp, H::default())
+    }
+
+    /// New builder targeting false positive rate `fpp`, initialized with supplied
+    /// `hasher`.
+    pub fn with_hasher(fpp: f64, hash_builder: H) -> Self {
+        Self {
+            digests: Vec::default(),
+            hash_time: Duration::default(),
+            fpp,
+            num_digests: 0,
+            hash_builder,
+            rng_seed: None,
+        }
+    }
+
+    /// Seed the builds with a random `rng_seed`, and return the same.
+    pub fn set_random_seed(&mut self) -> u64 {
+        let rng_seed = random_seed();
+        self.rng_seed = Some(rng_seed);
+        rng_seed
+    }
+
+    pub fn get_hasher(&self) -> H::Hasher {
+        self.hash_builder.build_hasher()
+    }
+
+    /// Calculate hash of a key.
+    #[inline]
+    pub fn hash<K: Hash + ?Sized>(&self, key: &K) -> u64 {
+        let mut hasher = self.get_hasher();
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Return the fingerprint width, in bits, needed to meet the target false
+    /// positive rate. Fails if `fpp` is not within `2^-32` and `1`.
+    pub fn bits(&self) -> Result<u32> {
+        let bits = (-self.fpp.log2()).ceil();
+        if self.fpp.is_nan() || self.fpp <= 0.0 || self.fpp > 1.0 || bits > 32.0 {
+            err_at!(Fatal, msg: "fpp {} not within 2^-32 and 1", self.fpp)
+        } else {
+            Ok(std::cmp::max(bits as u32, 1))
+        }
+    }
+
+    /// Insert 64-bit digest of a single key.
+    ///
+    /// Digest for the key shall be generated using the default-hasher or via hasher
+    /// supplied via [FilterBuilder::with_hasher] method.
+    pub fn insert<K: ?Sized + Hash>(&mut self, key: &K) {
+        let digest = self.hash(key);
+
+        self.digests.push(digest);
+        self.num_digests += 1;
+    }
+
+    /// Populate with 64-bit digests for a collection of keys, owned or
+    /// borrowed.
+    ///
+    /// Digest for key shall be generated using the default-hasher or via hasher supplied
+    /// via [FilterBuilder::with_hasher] method.
+    pub fn populate<I>(&mut self, keys: I)
+    where
+        I: IntoIterator,
+        I::Item: Hash,
+    {
+        let (mut n, start) = (0, Instant::now());
+
+        for key in keys.into_iter() {
+            n += 1;
+
+            let digest = self.hash(&key);
+            self.digests.push(digest);
+        }
+
+        self.num_digests += n;
+        self.hash_time += start.elapsed();
+    }
+
+    /// Populate with pre-compute collection of 64-bit digests.
+    pub fn populate_digests<I>(&mut self, digests: I)
+    where
+        I: IntoIterator,
+        I::Item: Borrow<u64>,
+    {
+        let mut n = 0;
+
+        for digest in digests.into_iter() {
+            n += 1;
+            self.digests.push(*digest.borrow());
+        }
+
+        self.num_digests += n;
+    }
+
+    /// Build filter for keys that where previously inserted using
+    /// [FilterBuilder::insert], [FilterBuilder::populate] and
+    /// [FilterBuilder::populate_digests] method. Duplicate digests are removed before
+    /// building the filter.
+    pub fn build(&mut self) -> Result<AnyFilter<H>> {
+        self.build_with_stats().map(|(filter, _)| filter)
+    }
+
+    /// Same as [FilterBuilder::build], and return statistics on the build.
+    pub fn build_with_stats(&mut self) -> Result<(AnyFilter<H>, BuildStats)> {
+        let start = Instant::now();
+        let mut digests = std::mem::take(&mut self.digests);
+        digests.sort_unstable();
+        digests.dedup();
+        let dedup_time = start.elapsed();
+
+        let (filter, mut stats) = self.build_from_digests_with_stats(&digests)?;
+        stats.num_digests = self.num_digests;
+        stats.num_duplicates = self.num_digests - stats.num_unique;
+        stats.hash_time = self.hash_time;
+        stats.dedup_time += dedup_time;
+        Ok((filter, stats))
+    }
+
+    /// Build filter for pre-computed 64-bit digests for keys.
+    ///
+    /// If keys where previously inserted using [FilterBuilder::insert] or
+    /// [FilterBuilder::populate] or [FilterBuilder::populate_digests] methods, they
+    /// shall be ignored.
+    ///
+    /// It is upto the caller to ensure that digests are unique, that there no duplicates.
+    /// Filters are sized with `u32`, more than `u32::MAX` digests fail the build with
+    /// [Error::CapacityExceeded].
+    pub fn build_from_digests(&mut self, digests: &[u64]) -> Result<AnyFilter<H>> {
+        self.build_from_digests_with_stats(digests).map(|(filter, _)| filter)
+    }
+
+    /// Same as [FilterBuilder::build_from_digests], and return statistics on the
+    /// build.
+    pub fn build_from_digests_with_stats(
+        &mut self,
+        digests: &[u64],
+    ) -> Result<(AnyFilter<H>, BuildStats)> {
+        let bits = self.bits()?;
+        let hb = self.hash_builder.clone();
+        if digests.len() > (u32::MAX as usize) {
+            return Err(Error::CapacityExceeded {
+                keys: digests.len(),
+                capacity: u32::MAX as usize,
+            });
+        }
+        let size = digests.len() as u32;
+        let layout = Layout::smallest(bits, size);
+        let rng_seed = self.rng_seed.unwrap_or(layout.rng_seed());
+
+        let (filter, stats) = match (layout, bits) {
+            (Layout::Xor, 8) => {
+                let mut builder = Xor8Builder::with_hasher(hb);
+                builder.rng_seed = rng_seed;
+                let (filter, stats) = builder.build_from_digests_with_stats(digests)?;
+                (AnyFilter::Xor8(filter), stats)
+            }
+            (Layout::Xor, 16) => {
+                let mut builder = Xor16Builder::with_hasher(hb);
+                builder.rng_seed = rng_seed;
+                let (filter, stats) = builder.build_from_digests_with_stats(digests)?;
+                (AnyFilter::Xor16(filter), stats)
+            }
+            (Layout::Xor, 32) => {
+                let mut builder = Xor32Builder::with_hasher(hb);
+                builder.rng_seed = rng_seed;
+                let (filter, stats) = builder.build_from_digests_with_stats(digests)?;
+                (AnyFilter::Xor32(filter), stats)
+            }
+            (Layout::Xor, bits) => {
+                let mut builder = XorPackedBuilder::with_hasher(bits, hb);
+                builder.rng_seed = rng_seed;
+                let (filter, stats) = builder.build_from_digests_with_stats(digests)?;
+                (AnyFilter::XorPacked(filter), stats)
+            }
+            (Layout::Fuse, 8) => {
+                let mut builder = Fuse8Builder::with_hasher(hb);
+                builder.rng_seed = rng_seed;
+                let (filter, stats) = builder.build_from_digests_with_stats(digests)?;
+                (AnyFilter::Fuse8(filter), stats)
+            }
+            (Layout::Fuse, 16) => {
+                let mut builder = Fuse16Builder::with_hasher(hb);
+                builder.rng_seed = rng_seed;
+                let (filter, stats) = builder.build_from_digests_with_stats(digests)?;
+                (AnyFilter::Fuse16(filter), stats)
+            }
+            (Layout::Fuse, 32) => {
+                let mut builder = Fuse32Builder::with_hasher(hb);
+                builder.rng_seed = rng_seed;
+                let (filter, stats) = builder.build_from_digests_with_stats(digests)?;
+                (AnyFilter::Fuse32(filter), stats)
+            }
+            (Layout::Fuse, bits) => {
+                let mut builder = FusePackedBuilder::with_hasher(bits, hb);
+                builder.rng_seed = rng_seed;
+                let (filter, stats) = builder.build_from_digests_with_stats(digests)?;
+                (AnyFilter::FusePacked(filter), stats)
+            }
+            (Layout::Fuse4, 8) => {
+                let mut builder = Fuse8x4Builder::with_hasher(hb);
+                builder.rng_seed = rng_seed;
+                let (filter, stats) = builder.build_from_digests_with_stats(digests)?;
+                (AnyFilter::Fuse8x4(filter), stats)
+            }
+            (Layout::Fuse4, 16) => {
+                let mut builder = Fuse16x4Builder::with_hasher(hb);
+                builder.rng_seed = rng_seed;
+                let (filter, stats) = builder.build_from_digests_with_stats(digests)?;
+                (AnyFilter::Fuse16x4(filter), stats)
+            }
+            (Layout::Fuse4, _) => {
+                let mut builder = Fuse32x4Builder::with_hasher(hb);
+                builder.rng_seed = rng_seed;
+                let (filter, stats) = builder.build_from_digests_with_stats(digests)?;
+                (AnyFilter::Fuse32x4(filter), stats)
+            }
+        };
+
+        Ok((filter, stats))
+    }
+}
+
+// Slot layouts to choose from, 4-wise layout is available only for fixed width
+// fingerprints.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Layout {
+    Xor,
+    Fuse,
+    Fuse4,
+}
+
+impl Layout {
+    fn smallest(bits: u32, size: u32) -> Layout {
+        let mut layouts = vec![(Segments::new(size).array_length as usize, Layout::Fuse)];
+        if let 8 | 16 | 32 = bits {
+            let n = Segments::with_arity(4, size).array_length as usize;
+            layouts.push((n, Layout::Fuse4));
+        }
+        if (size as usize) <= XOR_MAX_KEYS {
+            let n = 3 * (xor_block_length(size as usize) as usize);
+            layouts.push((n, Layout::Xor));
+        }
+
+        // on a tie, prefer the layout listed first.
+        layouts.into_iter().min_by_key(|(n, _)| *n).unwrap().1
+    }
+
+    // default seed of the filters built with this layout.
+    fn rng_seed(&self) -> u64 {
+        match self {
+            Layout::Xor => XOR_RNG_SEED,
+            Layout::Fuse | Layout::Fuse4 => FUSE_RNG_SEED,
+        }
+    }
+}
+
+/// Type-erased filter returned by [FilterBuilder].
+///
+/// Variants wrap the concrete filter that was picked to meet the target false
+/// positive rate, lookups are dispatched to the wrapped filter.
+#[derive(Clone)]
+pub enum AnyFilter<H = BuildHasherDefault>
+where H: BuildHasher + Clone
+{
+    Xor8(Xor8<H>),
+    Xor16(Xor16<H>),
+    Xor32(Xor32<H>),
+    XorPacked(XorPacked<H>),
+    Fuse8(Fuse8<H>),
+    Fuse16(Fuse16<H>),
+    Fuse32(Fuse32<H>),
+    FusePacked(FusePacked<H>),
+    Fuse8x4(Fuse8x4<H>),
+    Fuse16x4(Fuse16x4<H>),
+    Fuse32x4(Fuse32x4<H>),
+}
+
+impl<H> AnyFilter<H>
+where H: BuildHasher + Clone
+{
+    fn as_filter(&self) -> &dyn Filter {
+        match self {
+            AnyFilter::Xor8(f) => f,
+            AnyFilter::Xor16(f) => f,
+            AnyFilter::Xor32(f) => f,
+            AnyFilter::XorPacked(f) => f,
+            AnyFilter::Fuse8(f) => f,
+            AnyFilter::Fuse16(f) => f,
+            AnyFilter::Fuse32(f) => f,
+            AnyFilter::FusePacked(f) => f,
+            AnyFilter::Fuse8x4(f) => f,
+            AnyFilter::Fuse16x4(f) => f,
+            AnyFilter::Fuse32x4(f) => f,
+        }
+    }
+
+    fn as_hash_builder(&self) -> &H {
+        match self {
+            AnyFilter::Xor8(f) => &f.hash_builder,
+            AnyFilter::Xor16(f) => &f.hash_builder,
+            AnyFilter::Xor32(f) => &f.hash_builder,
+            AnyFilter::XorPacked(f) => &f.hash_builder,
+            AnyFilter::Fuse8(f) => &f.hash_builder,
+            AnyFilter::Fuse16(f) => &f.hash_builder,
+            AnyFilter::Fuse32(f) => &f.hash_builder,
+            AnyFilter::FusePacked(f) => &f.hash_builder,
+            AnyFilter::Fuse8x4(f) => &f.hash_builder,
+            AnyFilter::Fuse16x4(f) => &f.hash_builder,
+            AnyFilter::Fuse32x4(f) => &f.hash_builder,
+        }
+    }
+
+    /// Return the fingerprint width, in bits, of the wrapped filter.
+    pub fn bits(&self) -> u32 {
+        match self {
+            AnyFilter::Xor8(_) | AnyFilter::Fuse8(_) | AnyFilter::Fuse8x4(_) => 8,
+            AnyFilter::Xor16(_) | AnyFilter::Fuse16(_) | AnyFilter::Fuse16x4(_) => 16,
+            AnyFilter::Xor32(_) | AnyFilter::Fuse32(_) | AnyFilter::Fuse32x4(_) => 32,
+            AnyFilter::XorPacked(f) => f.bits(),
+            AnyFilter::FusePacked(f) => f.bits(),
+        }
+    }
+
+    #[allow(clippy::len_without_is_empty)]
+    /// Return the number of keys added/built into the bitmap index.
+    pub fn len(&self) -> Option<usize> {
+        self.as_filter().len()
+    }
+
+    /// Return the size of index.
+    pub fn size_of(&self) -> usize {
+        self.as_filter().size_of()
+    }
+
+    /// Contains tell you whether the key is likely part of the set, with false
+    /// positive rate.
+    pub fn contains<K: ?Sized + Hash>(&self, key: &K) -> bool {
+        let digest = self.hash(key);
+        self.contains_digest(digest)
+    }
+
+    /// Contains tell you whether the key, as pre-computed digest form, is likely
+    /// part of the set, with false positive rate.
+    pub fn contains_digest(&self, digest: u64) -> bool {
+        self.as_filter().contains_digest(digest)
+    }
+
+    pub fn get_hasher(&self) -> H::Hasher {
+        self.as_hash_builder().build_hasher()
+    }
+
+    /// Calculate hash of a key.
+    #[inline]
+    pub fn hash<K: Hash + ?Sized>(&self, key: &K) -> u64 {
+        let mut hasher = self.get_hasher();
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+impl<H> Filter for AnyFilter<H>
+where H: BuildHasher + Clone
+{
+    fn contains_digest(&self, digest: u64) -> bool {
+        self.as_filter().contains_digest(digest)
+    }
+
+    fn len(&self) -> Option<usize> {
+        self.as_filter().len()
+    }
+
+    fn size_of(&self) -> usize {
+        self.as_filter().size_of()
+    }
+}
+
+#[cfg(test)]
+#[path = "filter_test.rs"]
+mod filter_test;
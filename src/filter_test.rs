@@ -0,0 +1,206 @@
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+
+use rand::prelude::random;
+use rand::rngs::StdRng;
+use rand::Rng;
+use rand::SeedableRng;
+
+use super::*;
+
+fn test_filter_build<H>(name: &str, seed: u64, size: usize, fpp: f64) -> AnyFilter<H>
+where H: BuildHasher + Clone + Default {
+    println!("test_filter_build<{}> size:{} fpp:{}", name, size, fpp);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let keys: Vec<u64> = (0..size).map(|_| rng.gen::<u64>()).collect();
+    let (keys1, keys2) = keys.split_at(size / 2);
+
+    let mut builder = FilterBuilder::<H>::with_fpp(fpp);
+    // populate api
+    builder.populate(keys1);
+    // populate_digests api
+    let digests: Vec<u64> = keys2.iter().map(|k| builder.hash(k)).collect();
+    builder.populate_digests(digests.iter());
+    // insert api, duplicates are removed before build.
+    builder.insert(&keys[0]);
+
+    let filter = builder.build().expect("failed build");
+    assert_eq!(filter.len(), Some(size));
+    assert!(filter.bits() >= builder.bits().unwrap());
+
+    for key in keys.iter() {
+        assert!(filter.contains(key), "key {} not present", key);
+    }
+    for digest in digests.iter() {
+        assert!(Filter::contains_digest(&filter, *digest));
+    }
+
+    let (falsesize, mut matches) = (1_000_000, 0_f64);
+    for _ in 0..falsesize {
+        if filter.contains(&rng.gen::<u64>()) {
+            matches += 1_f64;
+        }
+    }
+    let rate = matches / (falsesize as f64);
+    println!("test_filter_build<{}> false positive rate {}", name, rate);
+    // allow for few more matches, when fpp is too small to be sampled accurately.
+    let limit = (fpp * 1.2) + (10.0 / (falsesize as f64));
+    assert!(rate < limit, "rate({}) >= {}", rate, limit);
+
+    filter
+}
+
+#[test]
+fn test_filter_fpp() {
+    let mut seed: u64 = random();
+    println!("test_filter_fpp seed:{}", seed);
+
+    for fpp in [0.1, 0.01, 0.0039, 0.001, 0.0001, 0.00001].iter() {
+        for size in [10_000, 100_000, 1_000_000].iter() {
+            seed = seed.wrapping_add(*size as u64);
+            test_filter_build::<RandomState>("RandomState", seed, *size, *fpp);
+            test_filter_build::<BuildHasherDefault>(
+                "BuildHasherDefault",
+                seed,
+                *size,
+                *fpp,
+            );
+        }
+    }
+}
+
+#[test]
+fn test_filter_bits() {
+    let bits = |fpp: f64| FilterBuilder::<BuildHasherDefault>::with_fpp(fpp).bits();
+
+    assert_eq!(bits(1.0).unwrap(), 1);
+    assert_eq!(bits(0.5).unwrap(), 1);
+    assert_eq!(bits(0.3).unwrap(), 2);
+    assert_eq!(bits(1.0 / 256.0).unwrap(), 8);
+    assert_eq!(bits(0.0039).unwrap(), 9);
+    assert_eq!(bits(0.001).unwrap(), 10);
+    assert_eq!(bits(1.0 / 65536.0).unwrap(), 16);
+    assert_eq!(bits(2.0_f64.powi(-32)).unwrap(), 32);
+
+    assert!(bits(0.0).is_err());
+    assert!(bits(-0.1).is_err());
+    assert!(bits(1.5).is_err());
+    assert!(bits(f64::NAN).is_err());
+    assert!(bits(2.0_f64.powi(-33)).is_err());
+}
+
+#[test]
+fn test_filter_variant() {
+    let seed: u64 = random();
+    println!("test_filter_variant seed:{}", seed);
+
+    let filter = test_filter_build::<BuildHasherDefault>("fuse8", seed, 1_000_000, 0.004);
+    assert!(matches!(filter, AnyFilter::Fuse8x4(_)), "{}", filter.bits());
+    let filter = test_filter_build::<BuildHasherDefault>("fuse16", seed, 100_000, 0.0001);
+    assert!(
+        matches!(filter, AnyFilter::FusePacked(_)),
+        "{}",
+        filter.bits()
+    );
+    assert_eq!(filter.bits(), 14);
+    let filter = test_filter_build::<BuildHasherDefault>("xor", seed, 1_000, 0.001);
+    assert!(
+        matches!(filter, AnyFilter::XorPacked(_)),
+        "{}",
+        filter.bits()
+    );
+
+    // a smaller target shall not give a bigger filter.
+    let mut prev = 0;
+    for fpp in [0.1, 0.01, 0.001, 0.0001, 0.00001].iter() {
+        let filter = test_filter_build::<BuildHasherDefault>("size", seed, 100_000, *fpp);
+        assert!(filter.size_of() > prev, "{} {}", filter.size_of(), prev);
+        prev = filter.size_of();
+    }
+}
+
+#[test]
+fn test_filter_build_stats() {
+    let seed: u64 = random();
+    println!("test_filter_build_stats seed:{}", seed);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let keys: Vec<u64> = (0..100_000).map(|_| rng.gen::<u64>()).collect();
+
+    let mut builder = FilterBuilder::<BuildHasherDefault>::with_fpp(0.004);
+    builder.populate(&keys);
+    builder.populate(&keys[..1000]);
+    let (filter, stats) = builder.build_with_stats().expect("failed build");
+    println!("test_filter_build_stats {}", stats);
+
+    assert_eq!(stats.num_digests, keys.len() + 1000);
+    assert_eq!(stats.num_unique, keys.len());
+    assert_eq!(stats.num_duplicates, 1000);
+    assert_eq!(filter.len(), Some(keys.len()));
+    assert!(stats.bits_per_key > 8.0 && stats.bits_per_key < 10.0);
+    assert!(stats.total_time() >= stats.peel_time);
+
+    let digests: Vec<u64> = keys.iter().map(|k| builder.hash(k)).collect();
+    let (_, stats) =
+        builder.build_from_digests_with_stats(&digests).expect("failed build");
+    assert_eq!(stats.num_digests, keys.len());
+    assert_eq!(stats.num_unique, keys.len());
+}
+
+#[test]
+fn test_filter_dyn() {
+    let keys: Vec<u64> = (0..10_000).collect();
+
+    let fuse8 = {
+        let mut builder = crate::fuse8::Fuse8Builder::<BuildHasherDefault>::new();
+        builder.populate(&keys);
+        builder.build().expect("failed build")
+    };
+    let xor16 = {
+        let mut builder = crate::xor16::Xor16Builder::<BuildHasherDefault>::new();
+        builder.populate(&keys);
+        builder.build().expect("failed build")
+    };
+    let any = {
+        let mut builder = FilterBuilder::<BuildHasherDefault>::with_fpp(0.01);
+        builder.populate(&keys);
+        builder.build().expect("failed build")
+    };
+
+    let digests: Vec<u64> = keys.iter().map(|k| any.hash(k)).collect();
+    let filters: Vec<Box<dyn Filter>> =
+        vec![Box::new(fuse8), Box::new(xor16), Box::new(any)];
+    for filter in filters.iter() {
+        assert_eq!(filter.len(), Some(keys.len()));
+        assert!(filter.size_of() > 0);
+        for digest in digests.iter() {
+            assert!(filter.contains_digest(*digest));
+        }
+    }
+}
+
+#[test]
+fn test_filter_rng_seed() {
+    let keys: Vec<u64> = (0..1_000).collect();
+
+    let build = |rng_seed: Option<u64>| {
+        let mut builder = FilterBuilder::<BuildHasherDefault>::with_fpp(1.0 / 256.0);
+        builder.rng_seed = rng_seed;
+        builder.populate(&keys);
+        match builder.build().expect("failed build") {
+            AnyFilter::Xor8(filter) => filter,
+            filter => panic!("unexpected filter, bits:{}", filter.bits()),
+        }
+    };
+    let xor8 = |rng_seed: u64| {
+        let mut builder = Xor8Builder::<BuildHasherDefault>::new();
+        builder.rng_seed = rng_seed;
+        builder.populate(&keys);
+        builder.build().expect("failed build")
+    };
+
+    // xor layout defaults to the seed of xor filters.
+    assert!(build(None) == xor8(XOR_RNG_SEED));
+    assert!(build(Some(FUSE_RNG_SEED)) == xor8(FUSE_RNG_SEED));
+}
@@ -0,0 +1,225 @@
+use std::borrow::Borrow;
+use std::hash::BuildHasher;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::fuse::binary_fuse_build;
+use crate::fuse::binary_fuse_dedup;
+use crate::fuse::BinaryFuseFilter;
+use crate::fuse::FUSE_RNG_SEED;
+use crate::hasher::random_seed;
+use crate::BuildHasherDefault;
+use crate::BuildStats;
+use crate::Fingerprint;
+use crate::Result;
+
+/// Builds a [BinaryFuseFilter], parametrized over fingerprint type `F`, refer to
+/// [Fingerprint], and over the number of slots per key `ARITY`, 3 or 4. Use the
+/// aliases [crate::fuse8::Fuse8Builder], [crate::fuse16::Fuse16Builder] and
+/// [crate::fuse32::Fuse32Builder] for 8, 16 and 32 bit fingerprints, and their `x4`
+/// counterparts for the 4-wise layout.
+///
+/// Example:
+/// ```
+/// # use xorfilter::fuse8::Fuse8Builder;
+///
+/// let mut b: Fuse8Builder = Fuse8Builder::new();
+///
+/// b.populate(&["foo", "bar"]);
+/// let filter = b.build().unwrap();
+///
+/// assert!(filter.contains("foo"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct BinaryFuseBuilder<F, H = BuildHasherDefault, const ARITY: u32 = 3>
+where
+    F: Fingerprint,
+    H: BuildHasher + Clone,
+{
+    digests: Vec<u64>,
+    hash_time: Duration,
+    pub num_digests: usize,
+    pub hash_builder: H,
+    /// Seed for the random number generator drawing the peeling seeds.
+    pub rng_seed: u64,
+    _fp: PhantomData<F>,
+}
+
+impl<F, H, const ARITY: u32> Default for BinaryFuseBuilder<F, H, ARITY>
+where
+    F: Fingerprint,
+    H: BuildHasher + Clone + Default,
+{
+    fn default() -> Self {
+        Self::with_hasher(H::default())
+    }
+}
+
+impl<F, H, const ARITY: u32> BinaryFuseBuilder<F, H, ARITY>
+where
+    F: Fingerprint,
+    H: BuildHasher + Clone,
+{
+    /// New binary fuse builder initialized with [BuildHasherDefault].
+    ///
+    /// Panics if `ARITY` is neither 3 nor 4.
+    pub fn new() -> Self
+    where H: Default {
+        Self::default()
+    }
+
+    /// New binary fuse builder initialized with supplied `hasher`.
+    ///
+    /// Panics if `ARITY` is neither 3 nor 4.
+    pub fn with_hasher(hash_builder: H) -> Self {
+        assert!(
+            ARITY == 3 || ARITY == 4,
+            "arity:{} is neither 3 nor 4",
+            ARITY
+        );
+
+        Self {
+            digests: Vec::new(),
+            hash_time: Duration::default(),
+            num_digests: 0,
+            hash_builder,
+            rng_seed: FUSE_RNG_SEED,
+            _fp: PhantomData,
+        }
+    }
+
+    /// Seed the builds with a random `rng_seed`, and return the same.
+    pub fn set_random_seed(&mut self) -> u64 {
+        self.rng_seed = random_seed();
+        self.rng_seed
+    }
+
+    pub fn get_hasher(&self) -> H::Hasher {
+        self.hash_builder.build_hasher()
+    }
+
+    /// Calculate hash of a key.
+    #[inline]
+    pub fn hash<K: Hash + ?Sized>(&self, key: &K) -> u64 {
+        let mut hasher = self.get_hasher();
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Insert 64-bit digest of a single key.
+    ///
+    /// Digest for the key shall be generated using the default-hasher or via hasher
+    /// supplied via [BinaryFuseBuilder::with_hasher] method.
+    pub fn insert<K: ?Sized + Hash>(&mut self, key: &K) {
+        let digest = self.hash(key);
+
+        self.digests.push(digest);
+        self.num_digests += 1;
+    }
+
+    /// Populate with 64-bit digests for a collection of keys, owned or
+    /// borrowed.
+    ///
+    /// Digest for key shall be generated using the default-hasher or via hasher supplied
+    /// via [BinaryFuseBuilder::with_hasher] method.
+    pub fn populate<I>(&mut self, keys: I)
+    where
+        I: IntoIterator,
+        I::Item: Hash,
+    {
+        let (n, start) = (self.digests.len(), Instant::now());
+
+        let hash_builder = &self.hash_builder;
+        self.digests.extend(keys.into_iter().map(|key| hash_builder.hash_one(key)));
+
+        self.num_digests += self.digests.len() - n;
+        self.hash_time += start.elapsed();
+    }
+
+    /// Populate with pre-compute collection of 64-bit digests.
+    pub fn populate_digests<I>(&mut self, digests: I)
+    where
+        I: IntoIterator,
+        I::Item: Borrow<u64>,
+    {
+        let n = self.digests.len();
+
+        self.digests.extend(digests.into_iter().map(|digest| *digest.borrow()));
+
+        self.num_digests += self.digests.len() - n;
+    }
+
+    /// Build bitmap for keys that where previously inserted using
+    /// [BinaryFuseBuilder::insert], [BinaryFuseBuilder::populate] and
+    /// [BinaryFuseBuilder::populate_digests] method. Duplicate digests are removed
+    /// before building the bitmap. With `parallel` feature, digests are sorted in
+    /// parallel.
+    pub fn build(&mut self) -> Result<BinaryFuseFilter<F, H, ARITY>> {
+        self.build_with_stats().map(|(filter, _)| filter)
+    }
+
+    /// Same as [BinaryFuseBuilder::build], and return statistics on the build.
+    pub fn build_with_stats(
+        &mut self,
+    ) -> Result<(BinaryFuseFilter<F, H, ARITY>, BuildStats)> {
+        let start = Instant::now();
+        binary_fuse_dedup(&mut self.digests);
+        let dedup_time = start.elapsed();
+
+        let digests = std::mem::take(&mut self.digests);
+        let res = self.build_from_digests_with_stats(&digests);
+        self.digests = digests;
+
+        let (filter, mut stats) = res?;
+        stats.num_digests = self.num_digests;
+        stats.num_duplicates = self.num_digests - stats.num_unique;
+        stats.hash_time = self.hash_time;
+        stats.dedup_time += dedup_time;
+        Ok((filter, stats))
+    }
+
+    /// Build a bitmap for pre-computed 64-bit digests for keys.
+    ///
+    /// If keys where previously inserted using [BinaryFuseBuilder::insert] or
+    /// [BinaryFuseBuilder::populate] or [BinaryFuseBuilder::populate_digests] methods,
+    /// they shall be ignored.
+    ///
+    /// Duplicate digests are tolerated, at the cost of de-duplicating a copy of
+    /// digests when they fail the build, and [BinaryFuseFilter::len] reports the number
+    /// of unique digests. Note that the filter is sized for the number of digests
+    /// supplied, duplicates included. Filters are sized with `u32`, more than
+    /// `u32::MAX` digests fail the build with [crate::Error::CapacityExceeded].
+    pub fn build_from_digests(
+        &mut self,
+        digests: &[u64],
+    ) -> Result<BinaryFuseFilter<F, H, ARITY>> {
+        self.build_from_digests_with_stats(digests).map(|(filter, _)| filter)
+    }
+
+    /// Same as [BinaryFuseBuilder::build_from_digests], and return statistics on the
+    /// build.
+    pub fn build_from_digests_with_stats(
+        &mut self,
+        digests: &[u64],
+    ) -> Result<(BinaryFuseFilter<F, H, ARITY>, BuildStats)> {
+        let (segs, finger_prints, stats) =
+            binary_fuse_build::<F>(digests, ARITY, self.rng_seed)?;
+
+        let filter = BinaryFuseFilter {
+            hash_builder: self.hash_builder.clone(),
+            seed: stats.seed,
+            num_keys: Some(stats.num_unique),
+            segment_length: segs.segment_length,
+            segment_length_mask: segs.segment_length_mask,
+            segment_count: segs.segment_count,
+            segment_count_length: segs.segment_count_length,
+            finger_prints: Arc::new(finger_prints),
+        };
+
+        Ok((filter, stats))
+    }
+}
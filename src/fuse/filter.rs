@@ -0,0 +1,238 @@
+#[allow(unused_imports)]
+use std::collections::hash_map::DefaultHasher;
+#[allow(unused_imports)]
+use std::collections::hash_map::RandomState;
+use std::convert::TryInto;
+use std::hash::BuildHasher;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::io;
+use std::sync::Arc;
+
+use crate::fuse::binary_fuse_contains;
+use crate::fuse::Fingerprint;
+use crate::fuse::Segments;
+use crate::BuildHasherDefault;
+use crate::Error;
+use crate::Filter;
+use crate::Result;
+
+/// Type BinaryFuseFilter is probabilistic data-structure to test membership of an
+/// element in a set.
+///
+/// BinaryFuseFilter is parametrized over fingerprint type `F`, refer to [Fingerprint],
+/// and over type `H` which is expected to implement [BuildHasher] trait, like types
+/// [RandomState] and [BuildHasherDefault]. When not supplied, [BuildHasherDefault] is
+/// used as the default hash-builder. Use the aliases [crate::fuse8::Fuse8],
+/// [crate::fuse16::Fuse16] and [crate::fuse32::Fuse32] for 8, 16 and 32 bit
+/// fingerprints.
+///
+/// Every key is mapped onto `ARITY` slots, 3 by default. With 4 slots, aliased as
+/// [crate::fuse8::Fuse8x4], [crate::fuse16::Fuse16x4] and [crate::fuse32::Fuse32x4],
+/// the memory overhead comes down from 1.125x to about 1.075x of the fingerprint
+/// size, for sizeable sets, at the cost of one extra memory access per lookup. For
+/// smaller sets, say less than a million keys, the 3-wise filter is often smaller.
+///
+/// Use [crate::BinaryFuseBuilder] to build the filter, once built it is
+/// immutable, cloning is cheap and shares the fingerprints.
+///
+/// If `RandomState` is used as BuildHasher, `std` has got this to say
+/// > _A particular instance RandomState will create the same instances
+/// > of Hasher, but the hashers created by two different RandomState
+/// > instances are unlikely to produce the same result for the same values._
+///
+/// If [DefaultHasher] is used as BuildHasher, `std` has got this to say,
+/// > _The internal algorithm is not specified, and so its hashes
+/// > should not be relied upon over releases._
+///
+/// The default type for parameter `H` might change when a reliable and commonly used
+/// BuildHasher type is available.
+#[derive(Clone, Debug)]
+pub struct BinaryFuseFilter<F, H = BuildHasherDefault, const ARITY: u32 = 3>
+where
+    F: Fingerprint,
+    H: BuildHasher,
+{
+    pub hash_builder: H,
+    pub seed: u64,
+    pub num_keys: Option<usize>,
+    pub segment_length: u32,
+    pub segment_length_mask: u32,
+    pub segment_count: u32,
+    pub segment_count_length: u32,
+    pub finger_prints: Arc<Vec<F>>,
+}
+
+impl<F, H, const ARITY: u32> PartialEq for BinaryFuseFilter<F, H, ARITY>
+where
+    F: Fingerprint,
+    H: BuildHasher,
+{
+    fn eq(&self, other: &Self) -> bool {
+        let num_keys = match (self.num_keys, other.num_keys) {
+            (Some(a), Some(b)) => a == b,
+            (_, _) => true,
+        };
+
+        self.seed == other.seed
+            && num_keys
+            && self.segment_length == other.segment_length
+            && self.segment_count == other.segment_count
+            && self.finger_prints == other.finger_prints
+    }
+}
+
+impl<F, H, const ARITY: u32> BinaryFuseFilter<F, H, ARITY>
+where
+    F: Fingerprint,
+    H: BuildHasher,
+{
+    #[allow(clippy::len_without_is_empty)]
+    /// Return the number of keys added/built into the bitmap index.
+    pub fn len(&self) -> Option<usize> {
+        self.num_keys
+    }
+
+    /// Return the size of index.
+    #[inline]
+    pub fn size_of(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + (self.finger_prints.len() * std::mem::size_of::<F>())
+    }
+
+    /// Contains tell you whether the key is likely part of the set, with false
+    /// positive rate.
+    pub fn contains<K: ?Sized + Hash>(&self, key: &K) -> bool {
+        let digest = self.hash(key);
+        self.contains_digest(digest)
+    }
+
+    /// Contains tell you whether the key, as pre-computed digest form, is likely
+    /// part of the set, with false positive rate.
+    pub fn contains_digest(&self, digest: u64) -> bool {
+        binary_fuse_contains(&self.segments(), self.seed, &self.finger_prints, digest)
+    }
+
+    pub fn get_hasher(&self) -> H::Hasher {
+        self.hash_builder.build_hasher()
+    }
+
+    /// Calculate hash of a key.
+    #[inline]
+    pub fn hash<K: Hash + ?Sized>(&self, key: &K) -> u64 {
+        let mut hasher = self.get_hasher();
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn segments(&self) -> Segments {
+        Segments {
+            arity: ARITY,
+            segment_length: self.segment_length,
+            segment_length_mask: self.segment_length_mask,
+            segment_count: self.segment_count,
+            segment_count_length: self.segment_count_length,
+            array_length: self.finger_prints.len() as u32,
+        }
+    }
+}
+
+impl<F, H, const ARITY: u32> Filter for BinaryFuseFilter<F, H, ARITY>
+where
+    F: Fingerprint,
+    H: BuildHasher,
+{
+    fn contains_digest(&self, digest: u64) -> bool {
+        self.contains_digest(digest)
+    }
+
+    fn len(&self) -> Option<usize> {
+        self.num_keys
+    }
+
+    fn size_of(&self) -> usize {
+        self.size_of()
+    }
+}
+
+// Binary layout of Fuse8 filter, with 8-bit fingerprints, used to stream filters
+// in and out of files without an intermediate copy. Integers are big-endian, and
+// hash-builder is not serialized.
+//
+// seed + num_keys + segment layout + fingerprint-length.
+const FUSE8_HEADER_LENGTH: usize = 8 + 8 + 4 + 4 + 4 + 4 + 4;
+
+impl<H> BinaryFuseFilter<u8, H>
+where H: BuildHasher
+{
+    pub(crate) fn write_to<W: io::Write>(&self, w: &mut W) -> Result<usize> {
+        let mut buf: Vec<u8> = Vec::with_capacity(FUSE8_HEADER_LENGTH);
+        buf.extend_from_slice(&self.seed.to_be_bytes());
+        buf.extend_from_slice(&(self.num_keys.unwrap_or(0) as u64).to_be_bytes());
+        buf.extend_from_slice(&self.segment_length.to_be_bytes());
+        buf.extend_from_slice(&self.segment_length_mask.to_be_bytes());
+        buf.extend_from_slice(&self.segment_count.to_be_bytes());
+        buf.extend_from_slice(&self.segment_count_length.to_be_bytes());
+        buf.extend_from_slice(&(self.finger_prints.len() as u32).to_be_bytes());
+
+        err_at!(IOError, w.write_all(&buf))?;
+        err_at!(IOError, w.write_all(&self.finger_prints))?;
+        Ok(buf.len() + self.finger_prints.len())
+    }
+
+    pub(crate) fn read_from<R: io::Read>(r: &mut R, hash_builder: H) -> Result<Self> {
+        let mut buf = [0_u8; FUSE8_HEADER_LENGTH];
+        err_at!(IOError, r.read_exact(&mut buf))?;
+
+        let u32_at = |n: usize| u32::from_be_bytes(buf[n..n + 4].try_into().unwrap());
+        let seed = u64::from_be_bytes(buf[0..8].try_into().unwrap());
+        let num_keys = u64::from_be_bytes(buf[8..16].try_into().unwrap()) as usize;
+        let segment_length = u32_at(16);
+        let segment_length_mask = u32_at(20);
+        let segment_count = u32_at(24);
+        let segment_count_length = u32_at(28);
+        let fp_len = u32_at(32) as usize;
+
+        if !segment_length.is_power_of_two()
+            || segment_length_mask.wrapping_add(1) != segment_length
+        {
+            err_at!(
+                InvalidFormat,
+                msg: "invalid segment_length {} mask {}",
+                segment_length,
+                segment_length_mask
+            )?;
+        }
+        let count_length = (segment_count as u64) * (segment_length as u64);
+        if count_length != (segment_count_length as u64) {
+            err_at!(
+                InvalidFormat,
+                msg: "invalid segment_count_length {} != {} * {}",
+                segment_count_length,
+                segment_count,
+                segment_length
+            )?;
+        }
+        // lookups index upto two segments past segment_count_length.
+        let limit = (segment_count_length as usize) + 2 * (segment_length as usize);
+        if fp_len < limit {
+            err_at!(InvalidFormat, msg: "fingerprints {} < {}", fp_len, limit)?;
+        }
+
+        let mut finger_prints = vec![0_u8; fp_len];
+        err_at!(IOError, r.read_exact(&mut finger_prints))?;
+
+        let filter = BinaryFuseFilter {
+            hash_builder,
+            seed,
+            num_keys: Some(num_keys),
+            segment_length,
+            segment_length_mask,
+            segment_count,
+            segment_count_length,
+            finger_prints: Arc::new(finger_prints),
+        };
+
+        Ok(filter)
+    }
+}
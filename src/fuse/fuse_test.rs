@@ -0,0 +1,420 @@
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+
+#[cfg(feature = "cbordata")]
+use cbordata::FromCbor;
+#[cfg(feature = "cbordata")]
+use cbordata::IntoCbor;
+use rand::prelude::random;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use rand::SeedableRng;
+
+use super::*;
+#[cfg(feature = "cbordata")]
+use crate::fuse16::Fuse16x4;
+#[cfg(feature = "cbordata")]
+use crate::fuse16::Fuse16x4Builder;
+use crate::fuse8::Fuse8Builder;
+
+fn generate_unique_keys(rng: &mut StdRng, size: usize) -> Vec<u64> {
+    let mut keys: Vec<u64> = Vec::with_capacity(size);
+    keys.resize(size, u64::default());
+
+    for key in keys.iter_mut() {
+        *key = rng.gen();
+    }
+    keys.sort_unstable();
+    keys.dedup();
+
+    for _i in 0..(size - keys.len()) {
+        let key = rng.gen::<u64>();
+        if !keys.contains(&key) {
+            keys.push(key)
+        }
+    }
+
+    keys
+}
+
+fn test_fuse_build<F, H, const ARITY: u32>(
+    name: &str,
+    seed: u64,
+    size: u32,
+    limits: (f64, f64),
+) where
+    F: Fingerprint,
+    H: BuildHasher + Clone + Default,
+{
+    let (x, y) = {
+        let size = size as usize;
+        (size / 3, size / 3)
+    };
+
+    println!(
+        "test_fuse_build<{}x{},{}> size:{}",
+        F::BITS,
+        ARITY,
+        name,
+        size
+    );
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let mut builder = BinaryFuseBuilder::<F, H, ARITY>::new();
+    let keys = generate_unique_keys(&mut rng, size as usize);
+    let (keys1, keys2, keys3) = (&keys[0..x], &keys[x..x + y], &keys[x + y..]);
+
+    // populate api
+    builder.populate(keys1);
+    // populate_keys api
+    let digests: Vec<u64> = keys2.iter().map(|k| builder.hash(k)).collect();
+    builder.populate_digests(digests.iter());
+    // insert api
+    keys3.iter().for_each(|key| builder.insert(key));
+    // duplicates are removed before build.
+    builder.populate(keys1);
+
+    let filter = builder.build().expect("failed build");
+    assert_eq!(filter.len(), Some(keys.len()));
+    assert_eq!(builder.num_digests, keys.len() + keys1.len());
+
+    // contains api
+    for key in keys.iter() {
+        assert!(filter.contains(key), "key {} not present", key);
+    }
+    // contains_key api
+    for key in keys.iter() {
+        let digest = filter.hash(key);
+        assert!(filter.contains_digest(digest), "key {} not present", key);
+    }
+
+    // builder is not consumed, building again yields the same filter.
+    assert!(filter == builder.build().expect("failed build"));
+
+    // print some statistics
+    let (falsesize, mut matches) = (1_000_000, 0_f64);
+    let bpv = ((filter.finger_prints.len() * std::mem::size_of::<F>()) as f64) * 8.0
+        / (keys.len() as f64);
+    println!(
+        "test_fuse_build<{}x{},{}> bits per entry {} bits",
+        F::BITS,
+        ARITY,
+        name,
+        bpv
+    );
+
+    for _ in 0..falsesize {
+        if filter.contains(&rng.gen::<u64>()) {
+            matches += 1_f64;
+        }
+    }
+
+    let fpp = matches * 100.0 / (falsesize as f64);
+    println!(
+        "test_fuse_build<{}x{},{}> false positive rate {}%",
+        F::BITS,
+        ARITY,
+        name,
+        fpp
+    );
+
+    if size > 100_000 {
+        let (max_bpv, max_fpp) = limits;
+        assert!(bpv < max_bpv, "bpv({}) >= {}", bpv, max_bpv);
+        assert!(fpp < max_fpp, "fpp({}) >= {}", fpp, max_fpp);
+    }
+}
+
+fn test_fuse_build_keys<F, H, const ARITY: u32>(name: &str, seed: u64, size: u32)
+where
+    F: Fingerprint,
+    H: Default + BuildHasher + Clone,
+{
+    println!(
+        "test_fuse_build_keys<{}x{},{}> size:{}",
+        F::BITS,
+        ARITY,
+        name,
+        size
+    );
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let mut builder = BinaryFuseBuilder::<F, H, ARITY>::new();
+
+    // build_keys api
+    let keys = generate_unique_keys(&mut rng, size as usize);
+    let digests: Vec<u64> = keys.iter().map(|k| builder.hash(k)).collect();
+    let filter = builder.build_from_digests(&digests).expect("failed build_keys");
+
+    // contains api
+    for key in keys.iter() {
+        assert!(filter.contains(key), "key {} not present", key);
+    }
+
+    // contains_key api
+    for digest in digests.into_iter() {
+        assert!(
+            filter.contains_digest(digest),
+            "digest {} not present",
+            digest
+        );
+    }
+}
+
+fn test_fuse_sizes<F, const ARITY: u32>(limits: (f64, f64))
+where F: Fingerprint {
+    let mut seed: u64 = random();
+    println!("test_fuse_sizes<{}x{}> seed:{}", F::BITS, ARITY, seed);
+
+    for size in [0, 1, 2, 10, 1000, 10_000, 100_000, 1_000_000].iter() {
+        seed = seed.wrapping_add(*size as u64);
+        test_fuse_build::<F, RandomState, ARITY>("RandomState", seed, *size, limits);
+        test_fuse_build::<F, BuildHasherDefault, ARITY>(
+            "BuildHasherDefault",
+            seed,
+            *size,
+            limits,
+        );
+        test_fuse_build_keys::<F, RandomState, ARITY>("RandomState", seed, *size);
+        test_fuse_build_keys::<F, BuildHasherDefault, ARITY>(
+            "BuildHasherDefault",
+            seed,
+            *size,
+        );
+    }
+}
+
+#[test]
+fn test_fuse8() {
+    test_fuse_sizes::<u8, 3>((12.0, 0.45))
+}
+
+#[test]
+fn test_fuse16() {
+    test_fuse_sizes::<u16, 3>((20.0, 0.01))
+}
+
+#[test]
+fn test_fuse32() {
+    test_fuse_sizes::<u32, 3>((40.0, 0.001))
+}
+
+#[test]
+fn test_fuse8x4() {
+    // 4-wise layout is about 1.075x of the fingerprint size, for sizeable sets.
+    test_fuse_sizes::<u8, 4>((8.8, 0.45))
+}
+
+#[test]
+fn test_fuse16x4() {
+    test_fuse_sizes::<u16, 4>((17.5, 0.01))
+}
+
+#[test]
+#[should_panic]
+fn test_fuse_arity() {
+    BinaryFuseBuilder::<u8, BuildHasherDefault, 5>::new();
+}
+
+fn test_fuse_duplicates_for<F, const ARITY: u32>()
+where F: Fingerprint {
+    let seed: u64 = random();
+    println!("test_fuse_duplicates<{}x{}> seed:{}", F::BITS, ARITY, seed);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let keys = generate_unique_keys(&mut rng, 100_000);
+    let mut builder = BinaryFuseBuilder::<F, BuildHasherDefault, ARITY>::new();
+
+    // every digest is repeated upto 10 times, in random order.
+    let mut digests: Vec<u64> = vec![];
+    for key in keys.iter() {
+        let digest = builder.hash(key);
+        (0..rng.gen_range(1, 11)).for_each(|_| digests.push(digest));
+    }
+    digests.shuffle(&mut rng);
+    println!(
+        "test_fuse_duplicates<{}x{}> {} digests",
+        F::BITS,
+        ARITY,
+        digests.len()
+    );
+
+    let filter = builder.build_from_digests(&digests).expect("failed build");
+    assert_eq!(filter.len(), Some(keys.len()));
+    for key in keys.iter() {
+        assert!(filter.contains(key), "key {} not present", key);
+    }
+
+    // a handful of keys repeated many times.
+    let digests: Vec<u64> = (0..10_000).map(|i| digests[i % 10]).collect();
+    let filter = builder.build_from_digests(&digests).expect("failed build");
+    let mut unique = digests[..10].to_vec();
+    unique.sort_unstable();
+    unique.dedup();
+    assert_eq!(filter.len(), Some(unique.len()));
+    for digest in unique.iter() {
+        assert!(
+            filter.contains_digest(*digest),
+            "digest {} not present",
+            digest
+        );
+    }
+}
+
+#[test]
+fn test_fuse_duplicates() {
+    test_fuse_duplicates_for::<u8, 3>();
+    test_fuse_duplicates_for::<u16, 3>();
+    test_fuse_duplicates_for::<u32, 3>();
+    test_fuse_duplicates_for::<u8, 4>();
+    test_fuse_duplicates_for::<u16, 4>();
+    test_fuse_duplicates_for::<u32, 4>();
+}
+
+#[test]
+fn test_fuse8_build_stats() {
+    let seed: u64 = random();
+    println!("test_fuse8_build_stats seed:{}", seed);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let keys = generate_unique_keys(&mut rng, 100_000);
+
+    let mut builder = Fuse8Builder::<BuildHasherDefault>::new();
+    builder.populate(&keys);
+    builder.populate(&keys[..1000]);
+    let (filter, stats) = builder.build_with_stats().expect("failed build");
+    println!("test_fuse8_build_stats {}", stats);
+
+    assert_eq!(stats.num_digests, keys.len() + 1000);
+    assert_eq!(stats.num_unique, keys.len());
+    assert_eq!(stats.num_duplicates, 1000);
+    assert_eq!(stats.seed, filter.seed);
+    assert_eq!(stats.array_length, filter.finger_prints.len());
+    assert!(stats.bits_per_key > 8.0 && stats.bits_per_key < 10.0);
+    assert!(stats.total_time() >= stats.peel_time);
+
+    // duplicates detected while peeling.
+    let digests: Vec<u64> = keys.iter().chain(keys[..10].iter()).copied().collect();
+    let (filter, stats) =
+        builder.build_from_digests_with_stats(&digests).expect("failed build");
+    assert_eq!(stats.num_digests, digests.len());
+    assert_eq!(stats.num_unique, keys.len());
+    assert_eq!(stats.num_duplicates, 10);
+    assert_eq!(filter.len(), Some(keys.len()));
+}
+
+#[test]
+fn test_fuse8_rng_seed() {
+    let seed: u64 = random();
+    println!("test_fuse8_rng_seed seed:{}", seed);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let keys = generate_unique_keys(&mut rng, 10_000);
+
+    let build = |rng_seed: u64| {
+        let mut builder = Fuse8Builder::<BuildHasherDefault>::new();
+        builder.rng_seed = rng_seed;
+        // order of keys shall not matter.
+        let mut keys = keys.clone();
+        keys.shuffle(&mut rand::thread_rng());
+        builder.populate(&keys);
+        builder.build().expect("failed build")
+    };
+
+    let (filter1, filter2) = (build(seed), build(seed));
+    assert!(filter1 == filter2);
+
+    let filter3 = build(seed.wrapping_add(1));
+    assert_ne!(filter1.seed, filter3.seed);
+    for key in keys.iter() {
+        assert!(filter3.contains(key), "key {} not present", key);
+    }
+
+    let mut builder = Fuse8Builder::<BuildHasherDefault>::new();
+    let rng_seed = builder.set_random_seed();
+    assert_eq!(builder.rng_seed, rng_seed);
+    assert_ne!(builder.set_random_seed(), rng_seed);
+    builder.populate(&keys);
+    let (filter, stats) = builder.build_with_stats().expect("failed build");
+    assert!(filter == build(builder.rng_seed));
+    assert_eq!(filter.seed, stats.seed);
+}
+
+#[cfg(feature = "cbordata")]
+fn test_fuse_cbor_for<F>()
+where
+    F: Fingerprint,
+    BinaryFuseFilter<F, BuildHasherDefault>: IntoCbor + FromCbor,
+    BinaryFuse<F, BuildHasherDefault>: IntoCbor,
+{
+    let seed: u64 = random();
+    println!("test_fuse_cbor<{}> seed:{}", F::BITS, seed);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let keys: Vec<u64> = (0..100_000).map(|_| rng.gen::<u64>()).collect();
+
+    let filter = {
+        let mut builder = BinaryFuseBuilder::<F, BuildHasherDefault>::new();
+        builder.populate(&keys);
+        builder.build().expect("fail building fuse filter")
+    };
+
+    for key in keys.iter() {
+        assert!(filter.contains(key), "key {} not present", key);
+    }
+
+    let filter = {
+        let val = filter.into_cbor().unwrap();
+        BinaryFuseFilter::<F, BuildHasherDefault>::from_cbor(val).unwrap()
+    };
+
+    for key in keys.iter() {
+        assert!(filter.contains(key), "key {} not present", key);
+    }
+
+    // filters serialized by the deprecated types can be read back.
+    let old = {
+        let mut filter = BinaryFuse::<F, BuildHasherDefault>::new(keys.len() as u32);
+        filter.populate(&keys);
+        filter.build().expect("fail building fuse filter");
+        filter
+    };
+    let val = old.clone().into_cbor().unwrap();
+    let new = BinaryFuseFilter::<F, BuildHasherDefault>::from_cbor(val).unwrap();
+    assert_eq!(new.seed, old.seed);
+    assert_eq!(new.finger_prints, old.finger_prints);
+}
+
+#[cfg(feature = "cbordata")]
+#[test]
+fn test_fuse_cbor() {
+    test_fuse_cbor_for::<u8>();
+    test_fuse_cbor_for::<u16>();
+    test_fuse_cbor_for::<u32>();
+}
+
+#[cfg(feature = "cbordata")]
+#[test]
+fn test_fuse16x4_cbor() {
+    let seed: u64 = random();
+    println!("test_fuse16x4_cbor seed:{}", seed);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let keys: Vec<u64> = (0..100_000).map(|_| rng.gen::<u64>()).collect();
+
+    let filter = {
+        let mut builder = Fuse16x4Builder::<BuildHasherDefault>::new();
+        builder.populate(&keys);
+        builder.build().expect("fail building fuse16x4 filter")
+    };
+
+    let new = {
+        let val = filter.clone().into_cbor().unwrap();
+        Fuse16x4::<BuildHasherDefault>::from_cbor(val).unwrap()
+    };
+    assert!(new == filter);
+    for key in keys.iter() {
+        assert!(new.contains(key), "key {} not present", key);
+    }
+}
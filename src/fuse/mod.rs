@@ -0,0 +1,1065 @@
+//! Binary fuse filters, generic over the fingerprint width.
+//!
+//! [BinaryFuseBuilder] builds an immutable [BinaryFuseFilter], refer to modules
+//! [crate::fuse8], [crate::fuse16] and [crate::fuse32] for its aliases. [BinaryFuse]
+//! mixes the buffer of keys with the built filter, type aliases [crate::Fuse8] and
+//! [crate::Fuse16] fix its fingerprint to `u8` and `u16` respectively.
+
+mod builder;
+mod filter;
+
+use std::borrow::Borrow;
+#[allow(unused_imports)]
+use std::collections::hash_map::DefaultHasher;
+#[allow(unused_imports)]
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::BuildHasher;
+use std::hash::Hash;
+use std::ops::BitXor;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+
+pub use builder::BinaryFuseBuilder;
+#[cfg(feature = "cbordata")]
+use cbordata::Cbor;
+#[cfg(feature = "cbordata")]
+use cbordata::Cborize;
+#[cfg(feature = "cbordata")]
+use cbordata::FromCbor;
+#[cfg(feature = "cbordata")]
+use cbordata::IntoCbor;
+#[cfg(feature = "cbordata")]
+use cbordata::{self as cbor};
+pub use filter::BinaryFuseFilter;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::hasher::random_seed;
+use crate::packed::Slots;
+use crate::BuildHasherDefault;
+use crate::BuildStats;
+use crate::Error;
+use crate::Filter;
+use crate::Result;
+
+// probabillity of success should always be > 0.5 so 100 iterations is highly unlikely.
+const XOR_MAX_ITERATIONS: usize = 100;
+
+/// Default seed for the random number generator drawing seeds while building binary
+/// fuse filters, same as the reference implementation.
+pub(crate) const FUSE_RNG_SEED: u64 = 0x726b2b9d438b9d4d;
+
+#[inline]
+pub(crate) fn binary_fuse_murmur64(mut h: u64) -> u64 {
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xff51afd7ed558ccd_u64);
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xc4ceb9fe1a85ec53_u64);
+    h ^= h >> 33;
+    h
+}
+
+#[inline]
+pub(crate) fn binary_fuse_mix_split(key: u64, seed: u64) -> u64 {
+    binary_fuse_murmur64(key.wrapping_add(seed))
+}
+
+#[allow(dead_code)]
+#[inline]
+fn binary_fuse_rotl64(n: u64, c: u32) -> u64 {
+    n.rotate_left(c)
+}
+
+#[allow(dead_code)]
+#[inline]
+fn binary_fuse_reduce(hash: u32, n: u32) -> u32 {
+    // http://lemire.me/blog/2016/06/27/a-fast-alternative-to-the-modulo-reduction/
+    (((hash as u64) * (n as u64)) >> 32) as u32
+}
+
+#[inline]
+pub(crate) fn binary_fuse_fingerprint(hash: u64) -> u64 {
+    hash ^ (hash >> 32)
+}
+
+// returns random number, modifies the seed
+pub(crate) fn binary_fuse_rng_splitmix64(seed: &mut u64) -> u64 {
+    *seed = seed.wrapping_add(0x9E3779B97F4A7C15_u64);
+    let mut z = *seed;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9_u64);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB_u64);
+    z ^ (z >> 31)
+}
+
+#[inline]
+pub(crate) fn binary_fuse_mulhi(a: u64, b: u64) -> u64 {
+    (((a as u128) * (b as u128)) >> 64) as u64
+}
+
+#[inline]
+pub(crate) fn binary_fuse_calculate_segment_length(arity: u32, size: u32) -> u32 {
+    let ln_size = (size as f64).ln();
+
+    // These parameters are very sensitive. Replacing 'floor' by 'round' can
+    // substantially affect the construction time.
+    match arity {
+        3 => 1_u32 << ((ln_size / 3.33_f64.ln() + 2.25).floor() as u32),
+        4 => 1_u32 << ((ln_size / 2.91_f64.ln() - 0.50).floor() as u32),
+        _ => 65536,
+    }
+}
+
+#[inline]
+fn binary_fuse8_max(a: f64, b: f64) -> f64 {
+    if a < b {
+        b
+    } else {
+        a
+    }
+}
+
+#[inline]
+pub(crate) fn binary_fuse_calculate_size_factor(arity: u32, size: u32) -> f64 {
+    let ln_size = (size as f64).ln();
+    match arity {
+        3 => binary_fuse8_max(1.125, 0.875 + 0.250 * 1000000.0_f64.ln() / ln_size),
+        4 => binary_fuse8_max(1.075, 0.770 + 0.305 * 0600000.0_f64.ln() / ln_size),
+        _ => 2.0,
+    }
+}
+
+// `x` modulo `arity`, for `x` less than twice the arity.
+#[inline]
+pub(crate) fn binary_fuse_mod(x: u8, arity: u8) -> u8 {
+    if x >= arity {
+        x - arity
+    } else {
+        x
+    }
+}
+
+/// Fingerprint type stored in each slot of a [BinaryFuse] filter.
+///
+/// Implemented for `u8`, `u16` and `u32`. Wider fingerprints have lower false positive
+/// rate, about `2^-BITS`, at the cost of `BITS` bits of memory per slot.
+pub trait Fingerprint:
+    Copy + Default + PartialEq + BitXor<Output = Self> + fmt::Debug + Send + Sync
+{
+    /// Number of bits in the fingerprint.
+    const BITS: u32;
+
+    /// Compute the fingerprint from a 64-bit `hash`.
+    fn from_hash(hash: u64) -> Self;
+}
+
+impl Fingerprint for u8 {
+    const BITS: u32 = 8;
+
+    #[inline]
+    fn from_hash(hash: u64) -> u8 {
+        binary_fuse_fingerprint(hash) as u8
+    }
+}
+
+impl Fingerprint for u16 {
+    const BITS: u32 = 16;
+
+    #[inline]
+    fn from_hash(hash: u64) -> u16 {
+        binary_fuse_fingerprint(hash) as u16
+    }
+}
+
+impl Fingerprint for u32 {
+    const BITS: u32 = 32;
+
+    #[inline]
+    fn from_hash(hash: u64) -> u32 {
+        binary_fuse_fingerprint(hash) as u32
+    }
+}
+
+/// Type BinaryFuse is probabilistic data-structure to test membership of an element in
+/// a set.
+///
+/// BinaryFuse is parametrized over fingerprint type `F`, refer to [Fingerprint], and
+/// over type `H` which is expected to implement [BuildHasher] trait, like types
+/// [RandomState] and [BuildHasherDefault]. When not supplied, [BuildHasherDefault] is
+/// used as the default hash-builder.
+///
+/// If `RandomState` is used as BuildHasher, `std` has got this to say
+/// > _A particular instance RandomState will create the same instances
+/// > of Hasher, but the hashers created by two different RandomState
+/// > instances are unlikely to produce the same result for the same values._
+///
+/// If [DefaultHasher] is used as BuildHasher, `std` has got this to say,
+/// > _The internal algorithm is not specified, and so its hashes
+/// > should not be relied upon over releases._
+///
+/// The default type for parameter `H` might change when a reliable and commonly used
+/// BuildHasher type available.
+///
+/// IMPORTANT: Keys supplied via [BinaryFuse::insert], [BinaryFuse::populate] and
+/// [BinaryFuse::populate_keys] are de-duplicated before building the filter, while
+/// [BinaryFuse::build_keys] detects duplicates on the fly and falls back to
+/// de-duplicating a copy of the digests, when there are too many of them. Note that
+/// the filter is sized for the number of digests supplied, duplicates included.
+pub struct BinaryFuse<F, H = BuildHasherDefault>
+where
+    F: Fingerprint,
+    H: BuildHasher,
+{
+    keys: Option<Vec<u64>>,
+    hash_time: Duration,
+    capacity: usize,
+    pub hash_builder: H,
+    pub seed: u64,
+    /// Seed for the random number generator drawing the peeling seeds.
+    pub rng_seed: u64,
+    pub num_keys: Option<usize>,
+    pub segment_length: u32,
+    pub segment_length_mask: u32,
+    pub segment_count: u32,
+    pub segment_count_length: u32,
+    pub finger_prints: Arc<Vec<F>>,
+}
+
+#[derive(Default)]
+pub(crate) struct BinaryHashes {
+    pub(crate) h0: u32,
+    pub(crate) h1: u32,
+    pub(crate) h2: u32,
+}
+
+impl<F, H> Clone for BinaryFuse<F, H>
+where
+    F: Fingerprint,
+    H: Clone + BuildHasher,
+{
+    fn clone(&self) -> Self {
+        BinaryFuse {
+            keys: Some(Vec::default()),
+            hash_time: Duration::default(),
+            capacity: self.capacity,
+            hash_builder: self.hash_builder.clone(),
+            seed: self.seed,
+            rng_seed: self.rng_seed,
+            num_keys: self.num_keys,
+            segment_length: self.segment_length,
+            segment_length_mask: self.segment_length_mask,
+            segment_count: self.segment_count,
+            segment_count_length: self.segment_count_length,
+            finger_prints: Arc::clone(&self.finger_prints),
+        }
+    }
+}
+
+impl<F, H> BinaryFuse<F, H>
+where
+    F: Fingerprint,
+    H: BuildHasher,
+{
+    /// New BinaryFuse instance that can index size number of keys. Internal
+    /// data-structures are pre-allocated for `size`.  `size` should be at least 2.
+    pub fn new(size: u32) -> BinaryFuse<F, H>
+    where H: Default {
+        Self::with_hasher(size, H::default())
+    }
+
+    /// New BinaryFuse instance initialized with supplied hasher.
+    pub fn with_hasher(size: u32, hash_builder: H) -> BinaryFuse<F, H> {
+        let segs = Segments::new(size);
+
+        BinaryFuse {
+            keys: Some(Vec::default()),
+            hash_time: Duration::default(),
+            capacity: size as usize,
+            hash_builder,
+            seed: u64::default(),
+            rng_seed: FUSE_RNG_SEED,
+            num_keys: None,
+            segment_length: segs.segment_length,
+            segment_length_mask: segs.segment_length_mask,
+            segment_count: segs.segment_count,
+            segment_count_length: segs.segment_count_length,
+            finger_prints: Arc::new(vec![F::default(); segs.array_length as usize]),
+        }
+    }
+
+    /// Seed the builds with a random `rng_seed`, and return the same.
+    pub fn set_random_seed(&mut self) -> u64 {
+        self.rng_seed = random_seed();
+        self.rng_seed
+    }
+
+    /// Build a filter from an iterator of keys, owned or borrowed, hashing them on
+    /// the fly with supplied hasher. Digests are pre-allocated using the iterator's
+    /// lower size-hint, and duplicate digests are removed before building the
+    /// bitmap, so keys need not be materialized as a slice. With `parallel`
+    /// feature, digests are sorted in parallel.
+    pub fn build_from_iter<I>(keys: I, hash_builder: H) -> Result<BinaryFuse<F, H>>
+    where
+        I: IntoIterator,
+        I::Item: Hash,
+    {
+        let keys = keys.into_iter();
+        let mut digests = Vec::with_capacity(keys.size_hint().0);
+        digests.extend(keys.map(|key| hash_builder.hash_one(key)));
+        binary_fuse_dedup(&mut digests);
+
+        if digests.len() > (u32::MAX as usize) {
+            return Err(Error::CapacityExceeded {
+                keys: digests.len(),
+                capacity: u32::MAX as usize,
+            });
+        }
+
+        let mut filter = Self::with_hasher(digests.len() as u32, hash_builder);
+        filter.build_keys(&digests)?;
+        Ok(filter)
+    }
+}
+
+impl<F, H> BinaryFuse<F, H>
+where
+    F: Fingerprint,
+    H: BuildHasher,
+{
+    /// Return the size of index.
+    #[inline]
+    pub fn size_of(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + (self.finger_prints.len() * std::mem::size_of::<F>())
+    }
+
+    /// Insert 64-bit digest of a single key. Digest for the key shall be generated
+    /// using the default-hasher or via hasher supplied via [BinaryFuse::with_hasher]
+    /// method.
+    pub fn insert<K: ?Sized + Hash>(&mut self, key: &K) {
+        let digest = self.hash_builder.hash_one(key);
+        if let Some(x) = self.num_keys.as_mut() {
+            *x += 1
+        }
+        self.keys.as_mut().unwrap().push(digest);
+    }
+
+    /// Populate with 64-bit digests for a collection of keys, owned or borrowed.
+    /// Digest for key shall be generated using the default-hasher or via hasher
+    /// supplied via [BinaryFuse::with_hasher] method.
+    pub fn populate<I>(&mut self, keys: I)
+    where
+        I: IntoIterator,
+        I::Item: Hash,
+    {
+        let start = Instant::now();
+        let hash_builder = &self.hash_builder;
+        let digests = self.keys.as_mut().unwrap();
+        let n = digests.len();
+        digests.extend(keys.into_iter().map(|key| hash_builder.hash_one(key)));
+        let n = digests.len() - n;
+        self.hash_time += start.elapsed();
+
+        if let Some(x) = self.num_keys.as_mut() {
+            *x += n
+        }
+    }
+
+    /// Populate with pre-compute collection of 64-bit digests.
+    pub fn populate_keys<I>(&mut self, digests: I)
+    where
+        I: IntoIterator,
+        I::Item: Borrow<u64>,
+    {
+        let keys = self.keys.as_mut().unwrap();
+        let n = keys.len();
+        keys.extend(digests.into_iter().map(|digest| *digest.borrow()));
+        let n = keys.len() - n;
+
+        if let Some(x) = self.num_keys.as_mut() {
+            *x += n
+        }
+    }
+
+    /// Build bitmap for keys that where previously inserted using [BinaryFuse::insert],
+    /// [BinaryFuse::populate] and [BinaryFuse::populate_keys] method. Duplicate
+    /// digests are removed before building the bitmap. With `parallel` feature,
+    /// digests are sorted in parallel.
+    pub fn build(&mut self) -> Result<()> {
+        self.build_with_stats().map(|_| ())
+    }
+
+    /// Same as [BinaryFuse::build], and return statistics on the build. If the
+    /// filter is already built, return empty statistics.
+    pub fn build_with_stats(&mut self) -> Result<BuildStats> {
+        match self.keys.take() {
+            Some(mut keys) => {
+                let (num_digests, start) = (keys.len(), Instant::now());
+                binary_fuse_dedup(&mut keys);
+                let dedup_time = start.elapsed();
+
+                let mut stats = self.build_keys_with_stats(&keys)?;
+                stats.num_digests = num_digests;
+                stats.num_duplicates = num_digests - stats.num_unique;
+                stats.hash_time = self.hash_time;
+                stats.dedup_time += dedup_time;
+                Ok(stats)
+            }
+            None => Ok(BuildStats::default()),
+        }
+    }
+
+    /// Build a bitmap for pre-computed 64-bit digests for keys. If keys where
+    /// previously inserted using [BinaryFuse::insert] or [BinaryFuse::populate] or
+    /// [BinaryFuse::populate_keys] methods, they shall be ignored.
+    ///
+    /// Duplicate digests are tolerated, at the cost of de-duplicating a copy of
+    /// digests when they fail the build, and [BinaryFuse::len] reports the number of
+    /// unique digests.
+    ///
+    /// More digests, duplicates included, than the `size` supplied to
+    /// [BinaryFuse::new] fail the build with [Error::CapacityExceeded].
+    pub fn build_keys(&mut self, digests: &[u64]) -> Result<()> {
+        self.build_keys_with_stats(digests).map(|_| ())
+    }
+
+    /// Same as [BinaryFuse::build_keys], and return statistics on the build.
+    pub fn build_keys_with_stats(&mut self, digests: &[u64]) -> Result<BuildStats> {
+        if digests.len() > self.capacity {
+            return Err(Error::CapacityExceeded {
+                keys: digests.len(),
+                capacity: self.capacity,
+            });
+        }
+
+        let segs = self.segments();
+        let mut stats = BuildStats::default();
+        let mut rng_counter = self.rng_seed;
+        let peeled = binary_fuse_peel(&segs, digests, &mut rng_counter, &mut stats)?;
+        self.seed = peeled.seed;
+        self.num_keys = Some(peeled.len());
+
+        let start = Instant::now();
+        let finger_prints = Arc::get_mut(&mut self.finger_prints).unwrap();
+        binary_fuse_assign(&segs, &peeled, finger_prints.as_mut_slice(), F::from_hash);
+        stats.assign_time = start.elapsed();
+        stats.set_array(finger_prints.len(), F::BITS);
+
+        Ok(stats)
+    }
+}
+
+impl<F, H> BinaryFuse<F, H>
+where
+    F: Fingerprint,
+    H: BuildHasher,
+{
+    #[allow(clippy::len_without_is_empty)]
+    /// Return the number of keys added/built into the bitmap index.
+    pub fn len(&self) -> Option<usize> {
+        self.num_keys
+    }
+
+    /// Contains tell you whether the key is likely part of the set, with false
+    /// positive rate.
+    pub fn contains<K: ?Sized + Hash>(&self, key: &K) -> bool {
+        let digest = self.hash_builder.hash_one(key);
+        self.contains_key(digest)
+    }
+
+    /// Contains tell you whether the key, as pre-computed digest form, is likely
+    /// part of the set, with false positive rate.
+    pub fn contains_key(&self, digest: u64) -> bool {
+        binary_fuse_contains(&self.segments(), self.seed, &self.finger_prints, digest)
+    }
+
+    pub fn get_hasher(&self) -> H::Hasher {
+        self.hash_builder.build_hasher()
+    }
+
+    fn segments(&self) -> Segments {
+        Segments {
+            arity: 3,
+            segment_length: self.segment_length,
+            segment_length_mask: self.segment_length_mask,
+            segment_count: self.segment_count,
+            segment_count_length: self.segment_count_length,
+            array_length: self.finger_prints.len() as u32,
+        }
+    }
+}
+
+impl<F, H> Filter for BinaryFuse<F, H>
+where
+    F: Fingerprint,
+    H: BuildHasher,
+{
+    fn contains_digest(&self, digest: u64) -> bool {
+        self.contains_key(digest)
+    }
+
+    fn len(&self) -> Option<usize> {
+        self.num_keys
+    }
+
+    fn size_of(&self) -> usize {
+        self.size_of()
+    }
+}
+
+/// Segment layout of a binary fuse filter, mapping every key onto `arity` slots.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Segments {
+    pub(crate) arity: u32,
+    pub(crate) segment_length: u32,
+    pub(crate) segment_length_mask: u32,
+    pub(crate) segment_count: u32,
+    pub(crate) segment_count_length: u32,
+    pub(crate) array_length: u32,
+}
+
+impl Segments {
+    /// Compute the segment layout of a 3-wise filter indexing `size` number of keys.
+    pub(crate) fn new(size: u32) -> Segments {
+        Segments::with_arity(3, size)
+    }
+
+    /// Compute the segment layout of an `arity`-wise filter indexing `size` number of
+    /// keys.
+    pub(crate) fn with_arity(arity: u32, size: u32) -> Segments {
+        use std::cmp;
+
+        let segment_length = match size {
+            0 => 4,
+            size => cmp::min(binary_fuse_calculate_segment_length(arity, size), 262144),
+        };
+
+        let segment_length_mask = segment_length - 1;
+        let mut array_length = {
+            let size_factor = binary_fuse_calculate_size_factor(arity, size);
+            let cap = match size {
+                0 | 1 => 0,
+                size => ((size as f64) * size_factor).round() as u32,
+            };
+            let n = cap.div_ceil(segment_length).wrapping_sub(arity - 1);
+            (n.wrapping_add(arity) - 1) * segment_length
+        };
+
+        let mut segment_count = array_length.div_ceil(segment_length);
+        segment_count = if segment_count <= (arity - 1) {
+            1
+        } else {
+            segment_count - (arity - 1)
+        };
+
+        array_length = (segment_count + arity - 1) * segment_length;
+        let segment_count_length = segment_count * segment_length;
+
+        Segments {
+            arity,
+            segment_length,
+            segment_length_mask,
+            segment_count,
+            segment_count_length,
+            array_length,
+        }
+    }
+
+    #[inline]
+    fn hash(&self, index: u32, hash: u64) -> u32 {
+        let mut h = binary_fuse_mulhi(hash, self.segment_count_length.into());
+        h += (index * self.segment_length) as u64;
+        // keep the lower 36 bits for 3-wise, 54 bits for 4-wise layout.
+        let bits = 18 * (self.arity - 1);
+        let hh = hash & ((1_u64 << bits) - 1);
+        // for 3-wise, index 0: right shift by 36; index 1: right shift by 18;
+        // index 2: no shift
+        h ^= (hh >> (bits - 18 * index)) & (self.segment_length_mask as u64);
+
+        h as u32
+    }
+
+    /// Compute the slots of a key, repeated such that `(found + k)`, for `k` less
+    /// than arity, can index the slots without modulo.
+    #[inline]
+    fn slots(&self, hash: u64, h: &mut [u32; 7]) {
+        let arity = self.arity as usize;
+        for (j, h) in h.iter_mut().enumerate().take(arity) {
+            *h = self.hash(j as u32, hash);
+        }
+        for j in 0..(arity - 1) {
+            h[arity + j] = h[j];
+        }
+    }
+}
+
+#[inline]
+pub(crate) fn binary_fuse_hash_batch(
+    hash: u64,
+    segment_length: u32,
+    segment_length_mask: u32,
+    segment_count_length: u32,
+) -> BinaryHashes {
+    let mut ans = BinaryHashes::default();
+
+    ans.h0 = binary_fuse_mulhi(hash, segment_count_length.into()) as u32;
+    ans.h1 = ans.h0 + segment_length;
+    ans.h2 = ans.h1 + segment_length;
+    ans.h1 ^= ((hash >> 18) as u32) & segment_length_mask;
+    ans.h2 ^= (hash as u32) & segment_length_mask;
+    ans
+}
+
+/// Outcome of peeling the keys of a binary fuse filter, refer [binary_fuse_peel].
+pub(crate) struct Peeled {
+    /// Seed that lead to a successful peeling.
+    pub(crate) seed: u64,
+    /// Hash of keys, in peeling order.
+    pub(crate) reverse_order: Vec<u64>,
+    /// Slot, among the `arity` slots, owned by the key in `reverse_order`.
+    pub(crate) reverse_h: Vec<u8>,
+}
+
+impl Peeled {
+    /// Return the number of unique keys peeled.
+    pub(crate) fn len(&self) -> usize {
+        self.reverse_h.len()
+    }
+}
+
+// construct the filter, returns true on success, false on failure.
+// most likely, a failure is due to too high a memory usage
+// size is the number of keys
+// The caller is responsable for calling binary_fuse8_allocate(size,filter)
+// before. The caller is responsible to ensure that there are no duplicated
+// keys. The inner loop will run up to XOR_MAX_ITERATIONS times (default on
+// 100), it should never fail, except if there are duplicated keys. If it fails,
+// a return value of false is provided.
+/// Peel the keys, as `digests`, over the segment layout `segs`, retrying with a fresh
+/// seed drawn from `rng_counter` until every key is peeled. Few duplicate digests are
+/// detected and skipped on the fly. Counts and timings of the peeling are recorded
+/// in `stats`.
+pub(crate) fn binary_fuse_peel(
+    segs: &Segments,
+    digests: &[u64],
+    rng_counter: &mut u64,
+    stats: &mut BuildStats,
+) -> Result<Peeled> {
+    let capacity = segs.array_length as usize;
+    let mut size = digests.len();
+    // sorted and de-duplicated copy of digests, when duplicates get in the way.
+    let mut unique: Option<Vec<u64>> = None;
+
+    let mut seed = binary_fuse_rng_splitmix64(rng_counter);
+
+    let mut reverse_order: Vec<u64> = vec![0; size + 1];
+    let mut reverse_h: Vec<u8> = vec![0; size];
+    let mut alone: Vec<u32> = vec![0; capacity];
+    let mut t2count: Vec<u8> = vec![0; capacity];
+    let mut t2hash: Vec<u64> = vec![0; capacity];
+
+    let mut block_bits: u32 = 1;
+    while (1_u32 << block_bits) < segs.segment_count {
+        block_bits += 1;
+    }
+
+    let block = 1_u32 << block_bits;
+    let mut start_pos: Vec<u32> = vec![0; 1 << block_bits];
+
+    // slots of a key, repeated so that (found + k) can index without modulo.
+    let arity = segs.arity as usize;
+    let mut h012 = [0_u32; 7];
+
+    reverse_order[size] = 1; // sentinel
+    let (mut attempts, mut duplicates) = (0, 0);
+    let stack_size = loop {
+        if attempts > XOR_MAX_ITERATIONS {
+            // count duplicates removed by the fallback as well.
+            let duplicates = duplicates + (digests.len() - size);
+            return Err(Error::TooManyIterations {
+                attempts,
+                duplicates,
+            });
+        }
+        attempts += 1;
+        let digests = unique.as_deref().unwrap_or(digests);
+
+        let start = Instant::now();
+        for i in 0_u32..block {
+            // important : i * size would overflow as a 32-bit number in some
+            // cases.
+            start_pos[i as usize] = (((i as u64) * (size as u64)) >> block_bits) as u32;
+        }
+
+        let mask_block = (block - 1) as u64;
+        for (_, digest) in digests.iter().enumerate().take(size) {
+            let hash: u64 = binary_fuse_murmur64(digest.wrapping_add(seed));
+            let mut segment_index: u64 = hash >> (64 - block_bits);
+            while reverse_order[start_pos[segment_index as usize] as usize] != 0 {
+                segment_index += 1;
+                segment_index &= mask_block;
+            }
+            reverse_order[start_pos[segment_index as usize] as usize] = hash;
+            start_pos[segment_index as usize] += 1;
+        }
+
+        let mut error: isize = 0;
+        duplicates = 0;
+        for (_, rev_order) in reverse_order.iter().enumerate().take(size) {
+            let hash: u64 = *rev_order;
+
+            let hs = &mut h012[..arity];
+            for (i, h) in hs.iter_mut().enumerate() {
+                *h = segs.hash(i as u32, hash);
+                let h = *h as usize;
+                t2count[h] = t2count[h].wrapping_add(4);
+                t2count[h] ^= i as u8;
+                t2hash[h] ^= hash;
+            }
+
+            // If we have duplicated hash values, then it is likely that
+            // the next comparison is true
+            if hs.iter().fold(u64::MAX, |acc, h| acc & t2hash[*h as usize]) == 0 {
+                // next we do the actual test
+                let dup = hs.iter().any(|h| {
+                    let h = *h as usize;
+                    (t2hash[h] == 0) && (t2count[h] == 8)
+                });
+                if dup {
+                    duplicates += 1;
+                    for (i, h) in hs.iter().enumerate() {
+                        let h = *h as usize;
+                        t2count[h] = t2count[h].wrapping_sub(4);
+                        t2count[h] ^= i as u8;
+                        t2hash[h] ^= hash;
+                    }
+                }
+            }
+
+            for h in hs.iter() {
+                error = if t2count[*h as usize] < 4 { 1 } else { error };
+            }
+        }
+        stats.count_time += start.elapsed();
+
+        if error > 0 {
+            if duplicates > 0 && unique.is_none() {
+                let start = Instant::now();
+                let mut digests = digests.to_vec();
+                size = binary_fuse_dedup(&mut digests);
+                stats.dedup_time += start.elapsed();
+                unique = Some(digests);
+                reverse_order.truncate(size + 1);
+                reverse_h.truncate(size);
+            }
+            reverse_order.fill(0);
+            reverse_order[size] = 1; // sentinel
+            t2count.fill(0);
+            t2hash.fill(0);
+            seed = binary_fuse_rng_splitmix64(rng_counter);
+            continue;
+        }
+
+        let start = Instant::now();
+        let mut q_size = 0_usize; // End of key addition
+
+        // Add sets with one key to the queue.
+        for (i, x) in t2count.iter().enumerate().take(capacity) {
+            alone[q_size] = i as u32;
+            q_size += if (x >> 2) == 1 { 1 } else { 0 };
+        }
+
+        let mut stack_size = 0_usize;
+
+        while q_size > 0 {
+            q_size -= 1;
+            let index = alone[q_size] as usize;
+            if (t2count[index] >> 2) == 1 {
+                let hash: u64 = t2hash[index];
+                segs.slots(hash, &mut h012);
+
+                let found: u8 = t2count[index] & 3;
+                reverse_h[stack_size] = found;
+                reverse_order[stack_size] = hash;
+                stack_size += 1;
+
+                let arity = arity as u8;
+                for k in 1..arity {
+                    let other_index: u32 = h012[(found + k) as usize];
+                    alone[q_size] = other_index;
+                    q_size += if (t2count[other_index as usize] >> 2) == 2 {
+                        1
+                    } else {
+                        0
+                    };
+
+                    t2count[other_index as usize] -= 4;
+                    t2count[other_index as usize] ^= binary_fuse_mod(found + k, arity);
+                    t2hash[other_index as usize] ^= hash;
+                }
+            }
+        }
+        stats.peel_time += start.elapsed();
+
+        if (stack_size + duplicates) == size {
+            break stack_size; // success
+        }
+
+        // Duplicates detected on the fly can still fail the peeling, like the
+        // reference implementation, fall back to an exact de-duplication pass.
+        if duplicates > 0 && unique.is_none() {
+            let start = Instant::now();
+            let mut digests = digests.to_vec();
+            size = binary_fuse_dedup(&mut digests);
+            stats.dedup_time += start.elapsed();
+            unique = Some(digests);
+            reverse_order.truncate(size + 1);
+            reverse_h.truncate(size);
+        }
+
+        reverse_order.fill(0);
+        reverse_order[size] = 1; // sentinel
+        t2count.fill(0);
+        t2hash.fill(0);
+
+        seed = binary_fuse_rng_splitmix64(rng_counter);
+    };
+
+    // entries beyond `stack_size` belong to duplicates, and carry no slot.
+    reverse_order.truncate(stack_size);
+    reverse_h.truncate(stack_size);
+
+    stats.num_digests = digests.len();
+    stats.num_unique = stack_size;
+    stats.num_duplicates = digests.len() - stack_size;
+    stats.seed_retries = attempts - 1;
+    stats.seed = seed;
+
+    Ok(Peeled {
+        seed,
+        reverse_order,
+        reverse_h,
+    })
+}
+
+/// Sort `digests` and remove duplicates in place, return the number of unique
+/// digests. With `parallel` feature, digests are sorted in parallel.
+pub(crate) fn binary_fuse_dedup(digests: &mut Vec<u64>) -> usize {
+    #[cfg(feature = "parallel")]
+    digests.par_sort_unstable();
+    #[cfg(not(feature = "parallel"))]
+    digests.sort_unstable();
+    digests.dedup();
+    digests.len()
+}
+
+/// Build fingerprints of type `F` for `digests`, on an `arity`-wise segment layout
+/// sized for exactly as many digests, and return the segment layout along with the
+/// fingerprints and statistics on the build, including the seed and number of unique
+/// digests. Used by the immutable filter types built via their builders.
+pub(crate) fn binary_fuse_build<F>(
+    digests: &[u64],
+    arity: u32,
+    rng_seed: u64,
+) -> Result<(Segments, Vec<F>, BuildStats)>
+where
+    F: Fingerprint,
+{
+    if digests.len() > (u32::MAX as usize) {
+        return Err(Error::CapacityExceeded {
+            keys: digests.len(),
+            capacity: u32::MAX as usize,
+        });
+    }
+
+    let segs = Segments::with_arity(arity, digests.len() as u32);
+    let mut stats = BuildStats::default();
+    let mut rng_counter = rng_seed;
+    let peeled = binary_fuse_peel(&segs, digests, &mut rng_counter, &mut stats)?;
+
+    let start = Instant::now();
+    let mut finger_prints = vec![F::default(); segs.array_length as usize];
+    binary_fuse_assign(&segs, &peeled, finger_prints.as_mut_slice(), F::from_hash);
+    stats.assign_time = start.elapsed();
+    stats.set_array(finger_prints.len(), F::BITS);
+
+    Ok((segs, finger_prints, stats))
+}
+
+/// Tell whether `digest` is likely part of the set, by xor-ing its fingerprint with
+/// the fingerprints in its `arity` slots, laid out as `segs`.
+#[inline]
+pub(crate) fn binary_fuse_contains<F>(
+    segs: &Segments,
+    seed: u64,
+    finger_prints: &[F],
+    digest: u64,
+) -> bool
+where
+    F: Fingerprint,
+{
+    let hash = binary_fuse_mix_split(digest, seed);
+    let mut f = F::from_hash(hash);
+    for index in 0..segs.arity {
+        f = f ^ finger_prints[segs.hash(index, hash) as usize];
+    }
+    f == F::default()
+}
+
+/// Assign a value to every slot owned by a peeled key, in reverse peeling order,
+/// such that xor-ing the `arity` slots of a key yields `value(hash)`.
+pub(crate) fn binary_fuse_assign<S, V>(
+    segs: &Segments,
+    peeled: &Peeled,
+    slots: &mut S,
+    value: V,
+) where
+    S: Slots + ?Sized,
+    V: Fn(u64) -> S::Item,
+{
+    let mut h012 = [0_u32; 7];
+
+    for i in (0_usize..peeled.reverse_h.len()).rev() {
+        // the hash of the key we insert next
+        let hash: u64 = peeled.reverse_order[i];
+        let mut val = value(hash);
+        let found: usize = peeled.reverse_h[i] as usize;
+        segs.slots(hash, &mut h012);
+        for k in 1..(segs.arity as usize) {
+            val = val ^ slots.get_slot(h012[found + k] as usize);
+        }
+        slots.set_slot(h012[found] as usize, val);
+    }
+}
+
+//------ Implement cbordata related functionalities
+
+// Intermediate types to serialize and de-serialized BinaryFuseFilter into bytes, one
+// for each fingerprint width and arity. 3-wise filters share the same layout with
+// BinaryFuse, so that either type can read the other.
+#[cfg(feature = "cbordata")]
+macro_rules! cbor_binary_fuse {
+    ($name:ident, $fp:ty, $arity:expr, $id:expr) => {
+        #[derive(Cborize)]
+        struct $name {
+            hash_builder: Vec<u8>,
+            seed: u64,
+            num_keys: Option<usize>,
+            segment_length: u32,
+            segment_length_mask: u32,
+            segment_count: u32,
+            segment_count_length: u32,
+            finger_prints: Vec<$fp>,
+        }
+
+        impl $name {
+            const ID: &'static str = $id;
+        }
+
+        impl<H> IntoCbor for BinaryFuseFilter<$fp, H, $arity>
+        where H: BuildHasher + Into<Vec<u8>>
+        {
+            fn into_cbor(self) -> cbor::Result<Cbor> {
+                let val = $name {
+                    hash_builder: self.hash_builder.into(),
+                    seed: self.seed,
+                    num_keys: self.num_keys,
+                    segment_length: self.segment_length,
+                    segment_length_mask: self.segment_length_mask,
+                    segment_count: self.segment_count,
+                    segment_count_length: self.segment_count_length,
+                    finger_prints: self.finger_prints.to_vec(),
+                };
+                val.into_cbor()
+            }
+        }
+
+        impl<H> FromCbor for BinaryFuseFilter<$fp, H, $arity>
+        where H: BuildHasher + From<Vec<u8>>
+        {
+            fn from_cbor(val: Cbor) -> cbor::Result<Self> {
+                let val = $name::from_cbor(val)?;
+
+                let filter = BinaryFuseFilter {
+                    hash_builder: val.hash_builder.into(),
+                    seed: val.seed,
+                    num_keys: val.num_keys,
+                    segment_length: val.segment_length,
+                    segment_length_mask: val.segment_length_mask,
+                    segment_count: val.segment_count,
+                    segment_count_length: val.segment_count_length,
+                    finger_prints: Arc::new(val.finger_prints),
+                };
+
+                Ok(filter)
+            }
+        }
+    };
+    ($name:ident, $fp:ty, $id:expr) => {
+        cbor_binary_fuse!($name, $fp, 3, $id);
+
+        impl<H> IntoCbor for BinaryFuse<$fp, H>
+        where H: BuildHasher + Into<Vec<u8>>
+        {
+            fn into_cbor(self) -> cbor::Result<Cbor> {
+                let val = $name {
+                    hash_builder: self.hash_builder.into(),
+                    seed: self.seed,
+                    num_keys: self.num_keys,
+                    segment_length: self.segment_length,
+                    segment_length_mask: self.segment_length_mask,
+                    segment_count: self.segment_count,
+                    segment_count_length: self.segment_count_length,
+                    finger_prints: self.finger_prints.to_vec(),
+                };
+                val.into_cbor()
+            }
+        }
+
+        impl<H> FromCbor for BinaryFuse<$fp, H>
+        where H: BuildHasher + From<Vec<u8>>
+        {
+            fn from_cbor(val: Cbor) -> cbor::Result<Self> {
+                let val = $name::from_cbor(val)?;
+
+                let filter = BinaryFuse {
+                    keys: None,
+                    hash_time: Duration::default(),
+                    // capacity is not serialized, deserialized filters are full.
+                    capacity: val.num_keys.unwrap_or(0),
+                    hash_builder: val.hash_builder.into(),
+                    seed: val.seed,
+                    rng_seed: FUSE_RNG_SEED,
+                    num_keys: val.num_keys,
+                    segment_length: val.segment_length,
+                    segment_length_mask: val.segment_length_mask,
+                    segment_count: val.segment_count,
+                    segment_count_length: val.segment_count_length,
+                    finger_prints: Arc::new(val.finger_prints),
+                };
+
+                Ok(filter)
+            }
+        }
+    };
+}
+
+#[cfg(feature = "cbordata")]
+cbor_binary_fuse!(CborFuse8, u8, "fuse8/0.0.1");
+// Fuse16 was serialized with this identifier before the generic implementation,
+// retain the same for backward compatibility.
+#[cfg(feature = "cbordata")]
+cbor_binary_fuse!(CborFuse16, u16, "fuse8/0.0.1");
+#[cfg(feature = "cbordata")]
+cbor_binary_fuse!(CborFuse32, u32, "fuse32/0.0.1");
+#[cfg(feature = "cbordata")]
+cbor_binary_fuse!(CborFuse8x4, u8, 4, "fuse8x4/0.0.1");
+#[cfg(feature = "cbordata")]
+cbor_binary_fuse!(CborFuse16x4, u16, 4, "fuse16x4/0.0.1");
+#[cfg(feature = "cbordata")]
+cbor_binary_fuse!(CborFuse32x4, u32, 4, "fuse32x4/0.0.1");
+
+#[cfg(test)]
+#[path = "fuse_test.rs"]
+mod fuse_test;
@@ -0,0 +1,36 @@
+//! Fuse16 filter, binary fuse filter with 16-bit fingerprints.
+//!
+//! Uses the same construction as [crate::fuse8], trading twice the memory for a false
+//! positive rate of about 0.0015%.
+
+use crate::BinaryFuseBuilder;
+use crate::BinaryFuseFilter;
+use crate::BuildHasherDefault;
+
+/// Type Fuse16 is a [BinaryFuseFilter] with 16-bit fingerprints.
+///
+/// This implementation has a false positive rate of about 0.0015% and a memory usage
+/// of about 18 bits per entry for sizeable sets.
+pub type Fuse16<H = BuildHasherDefault> = BinaryFuseFilter<u16, H>;
+
+/// Builds a [Fuse16] filter.
+///
+/// Example:
+/// ```
+/// # use xorfilter::fuse16::Fuse16Builder;
+///
+/// let mut b: Fuse16Builder = Fuse16Builder::new();
+///
+/// b.populate(&["foo", "bar"]);
+/// let filter = b.build().unwrap();
+///
+/// assert!(filter.contains("foo"));
+/// ```
+pub type Fuse16Builder<H = BuildHasherDefault> = BinaryFuseBuilder<u16, H>;
+
+/// Type Fuse16x4 is a [BinaryFuseFilter] with 16-bit fingerprints, mapping every key
+/// onto 4 slots instead of 3.
+pub type Fuse16x4<H = BuildHasherDefault> = BinaryFuseFilter<u16, H, 4>;
+
+/// Builds a [Fuse16x4] filter.
+pub type Fuse16x4Builder<H = BuildHasherDefault> = BinaryFuseBuilder<u16, H, 4>;
@@ -0,0 +1,13 @@
+use crate::fuse::BinaryFuse;
+use crate::BuildHasherDefault;
+
+/// Type Fuse16 is probabilistic data-structure to test membership of an element in a set.
+///
+/// Fuse16 is a [BinaryFuse] filter with 16-bit fingerprints, it has a false positive
+/// rate of about 0.0015% and a memory usage of about 18 bits per entry for sizeable
+/// sets.
+pub type Fuse16<H = BuildHasherDefault> = BinaryFuse<u16, H>;
+
+#[cfg(test)]
+#[path = "fuse16_old_test.rs"]
+mod fuse16_old_test;
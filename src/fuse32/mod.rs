@@ -0,0 +1,36 @@
+//! Fuse32 filter, binary fuse filter with 32-bit fingerprints.
+//!
+//! Uses the same construction as [crate::fuse8], trading four times the memory for a
+//! false positive rate of about 2^-32.
+
+use crate::BinaryFuseBuilder;
+use crate::BinaryFuseFilter;
+use crate::BuildHasherDefault;
+
+/// Type Fuse32 is a [BinaryFuseFilter] with 32-bit fingerprints.
+///
+/// This implementation has a false positive rate of about 2^-32 and a memory usage
+/// of about 36 bits per entry for sizeable sets.
+pub type Fuse32<H = BuildHasherDefault> = BinaryFuseFilter<u32, H>;
+
+/// Builds a [Fuse32] filter.
+///
+/// Example:
+/// ```
+/// # use xorfilter::fuse32::Fuse32Builder;
+///
+/// let mut b: Fuse32Builder = Fuse32Builder::new();
+///
+/// b.populate(&["foo", "bar"]);
+/// let filter = b.build().unwrap();
+///
+/// assert!(filter.contains("foo"));
+/// ```
+pub type Fuse32Builder<H = BuildHasherDefault> = BinaryFuseBuilder<u32, H>;
+
+/// Type Fuse32x4 is a [BinaryFuseFilter] with 32-bit fingerprints, mapping every key
+/// onto 4 slots instead of 3.
+pub type Fuse32x4<H = BuildHasherDefault> = BinaryFuseFilter<u32, H, 4>;
+
+/// Builds a [Fuse32x4] filter.
+pub type Fuse32x4Builder<H = BuildHasherDefault> = BinaryFuseBuilder<u32, H, 4>;
@@ -0,0 +1,38 @@
+//! Fuse8 filter, binary fuse filter with 8-bit fingerprints.
+//!
+//! [Fuse8Builder] collects keys and builds an immutable [Fuse8] filter, which holds
+//! nothing but the fingerprints and can be shared across threads. Both are aliases
+//! for [BinaryFuseBuilder] and [BinaryFuseFilter], generic over the
+//! [crate::Fingerprint] width.
+
+use crate::BinaryFuseBuilder;
+use crate::BinaryFuseFilter;
+use crate::BuildHasherDefault;
+
+/// Type Fuse8 is a [BinaryFuseFilter] with 8-bit fingerprints.
+///
+/// This implementation has a false positive rate of about 0.39% and a memory usage
+/// of about 9 bits per entry for sizeable sets.
+pub type Fuse8<H = BuildHasherDefault> = BinaryFuseFilter<u8, H>;
+
+/// Builds a [Fuse8] filter.
+///
+/// Example:
+/// ```
+/// # use xorfilter::fuse8::Fuse8Builder;
+///
+/// let mut b: Fuse8Builder = Fuse8Builder::new();
+///
+/// b.populate(&["foo", "bar"]);
+/// let filter = b.build().unwrap();
+///
+/// assert!(filter.contains("foo"));
+/// ```
+pub type Fuse8Builder<H = BuildHasherDefault> = BinaryFuseBuilder<u8, H>;
+
+/// Type Fuse8x4 is a [BinaryFuseFilter] with 8-bit fingerprints, mapping every key
+/// onto 4 slots instead of 3.
+pub type Fuse8x4<H = BuildHasherDefault> = BinaryFuseFilter<u8, H, 4>;
+
+/// Builds a [Fuse8x4] filter.
+pub type Fuse8x4Builder<H = BuildHasherDefault> = BinaryFuseBuilder<u8, H, 4>;
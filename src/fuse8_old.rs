@@ -0,0 +1,12 @@
+use crate::fuse::BinaryFuse;
+use crate::BuildHasherDefault;
+
+/// Type Fuse8 is probabilistic data-structure to test membership of an element in a set.
+///
+/// Fuse8 is a [BinaryFuse] filter with 8-bit fingerprints, it has a false positive
+/// rate of about 0.39% and a memory usage of about 9 bits per entry for sizeable sets.
+pub type Fuse8<H = BuildHasherDefault> = BinaryFuse<u8, H>;
+
+#[cfg(test)]
+#[path = "fuse8_old_test.rs"]
+mod fuse8_old_test;
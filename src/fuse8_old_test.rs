@@ -1,3 +1,12 @@
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::hash::Hash;
+use std::hash::Hasher;
+
+#[cfg(feature = "cbordata")]
+use cbordata::FromCbor;
+#[cfg(feature = "cbordata")]
+use cbordata::IntoCbor;
 use rand::distributions::Distribution;
 use rand::distributions::Standard;
 use rand::prelude::random;
@@ -238,6 +247,7 @@ fn test_fuse8_duplicates() {
     let mut filter = Fuse8::<RandomState>::new(keys.len() as u32);
 
     filter.build_keys(&keys).expect("build with duplicate keys failed");
+    assert_eq!(filter.len(), Some(keys.len() - 1));
 
     // contains api
     for key in keys.iter() {
@@ -245,6 +255,72 @@ fn test_fuse8_duplicates() {
     }
 }
 
+#[test]
+fn test_fuse8_errors() {
+    use crate::Error;
+
+    let digests: Vec<u64> = (0..10_000).collect();
+
+    let mut filter = Fuse8::<BuildHasherDefault>::new(10);
+    match filter.build_keys(&digests) {
+        Err(Error::CapacityExceeded { keys, capacity }) => {
+            assert_eq!((keys, capacity), (10_000, 10))
+        }
+        res => panic!("unexpected {:?}", res.err()),
+    }
+
+    // more populated keys than capacity, left after de-duplication.
+    let mut filter = Fuse8::<BuildHasherDefault>::new(10);
+    filter.populate_keys(&digests[..20]);
+    filter.populate_keys(&digests[..20]);
+    match filter.build() {
+        Err(Error::CapacityExceeded { keys, capacity }) => {
+            assert_eq!((keys, capacity), (20, 10))
+        }
+        res => panic!("unexpected {:?}", res.err()),
+    }
+
+    // empty input builds an empty filter.
+    let mut filter = Fuse8::<BuildHasherDefault>::new(0);
+    filter.build_keys(&[]).expect("failed build");
+    assert_eq!(filter.len(), Some(0));
+}
+
+#[test]
+fn test_fuse8_from_iter() {
+    let seed: u64 = random();
+    println!("test_fuse8_from_iter seed:{}", seed);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let keys: Vec<u64> = (0..100_000).map(|_| rng.gen::<u64>()).collect();
+
+    // owned keys, generated on the fly, with duplicates.
+    let iter = keys.iter().chain(keys[..1000].iter()).map(|k| k.to_string());
+    let filter =
+        Fuse8::<BuildHasherDefault>::build_from_iter(iter, BuildHasherDefault::default())
+            .expect("fail building fuse8 filter");
+    assert_eq!(filter.len(), Some(keys.len()));
+
+    // borrowed keys, shall hash the same as owned keys.
+    let strs: Vec<String> = keys.iter().map(|k| k.to_string()).collect();
+    let mut other = Fuse8::<BuildHasherDefault>::new(keys.len() as u32);
+    other.populate(&strs);
+    other.build().expect("fail building fuse8 filter");
+    assert_eq!(filter.seed, other.seed);
+    assert_eq!(filter.finger_prints, other.finger_prints);
+
+    for key in strs.iter() {
+        assert!(filter.contains(key), "key {} not present", key);
+    }
+
+    let filter = Fuse8::<BuildHasherDefault>::build_from_iter(
+        std::iter::empty::<u64>(),
+        BuildHasherDefault::default(),
+    )
+    .expect("fail building empty fuse8 filter");
+    assert_eq!(filter.len(), Some(0));
+}
+
 #[test]
 #[ignore]
 fn test_fuse8_billion() {
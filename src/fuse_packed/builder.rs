@@ -0,0 +1,218 @@
+use std::borrow::Borrow;
+use std::hash::BuildHasher;
+use std::hash::Hash;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::fuse::binary_fuse_assign;
+use crate::fuse::binary_fuse_dedup;
+use crate::fuse::binary_fuse_fingerprint;
+use crate::fuse::binary_fuse_peel;
+use crate::fuse::Segments;
+use crate::fuse::FUSE_RNG_SEED;
+use crate::fuse_packed::FusePacked;
+use crate::hasher::random_seed;
+use crate::packed::PackedArray;
+use crate::BuildHasherDefault;
+use crate::BuildStats;
+use crate::Error;
+use crate::Result;
+
+/// Builds a [FusePacked] filter, with fingerprints `bits` wide.
+///
+/// Example:
+/// ```
+/// # use xorfilter::FusePackedBuilder;
+///
+/// let mut b: FusePackedBuilder = FusePackedBuilder::new(12);
+///
+/// b.populate(&["foo", "bar"]);
+/// let filter = b.build().unwrap();
+///
+/// assert!(filter.contains("foo"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct FusePackedBuilder<H = BuildHasherDefault>
+where H: BuildHasher + Clone
+{
+    digests: Vec<u64>,
+    hash_time: Duration,
+    bits: u32,
+    pub num_digests: usize,
+    pub hash_builder: H,
+    /// Seed for the random number generator drawing the peeling seeds.
+    pub rng_seed: u64,
+}
+
+impl<H> FusePackedBuilder<H>
+where H: BuildHasher + Clone
+{
+    /// New FusePacked builder, with `bits` wide fingerprints, initialized with
+    /// [BuildHasherDefault].
+    ///
+    /// Panics if `bits` is not within 1 and 32.
+    pub fn new(bits: u32) -> Self
+    where H: Default {
+        Self::with_hasher(bits, H::default())
+    }
+
+    /// New FusePacked builder, with `bits` wide fingerprints, initialized with
+    /// supplied `hasher`.
+    ///
+    /// Panics if `bits` is not within 1 and 32.
+    pub fn with_hasher(bits: u32, hash_builder: H) -> Self {
+        assert!((1..=32).contains(&bits), "bits:{} not within 1..=32", bits);
+
+        Self {
+            digests: Vec::new(),
+            hash_time: Duration::default(),
+            bits,
+            num_digests: 0,
+            hash_builder,
+            rng_seed: FUSE_RNG_SEED,
+        }
+    }
+
+    /// Seed the builds with a random `rng_seed`, and return the same.
+    pub fn set_random_seed(&mut self) -> u64 {
+        self.rng_seed = random_seed();
+        self.rng_seed
+    }
+
+    /// Return the width of fingerprints, in bits.
+    pub fn bits(&self) -> u32 {
+        self.bits
+    }
+
+    pub fn get_hasher(&self) -> H::Hasher {
+        self.hash_builder.build_hasher()
+    }
+
+    /// Calculate hash of a key.
+    #[inline]
+    pub fn hash<K: Hash + ?Sized>(&self, key: &K) -> u64 {
+        self.hash_builder.hash_one(key)
+    }
+
+    /// Insert 64-bit digest of a single key.
+    ///
+    /// Digest for the key shall be generated using the default-hasher or via hasher
+    /// supplied via [FusePackedBuilder::with_hasher] method.
+    pub fn insert<K: ?Sized + Hash>(&mut self, key: &K) {
+        let digest = self.hash(key);
+
+        self.digests.push(digest);
+        self.num_digests += 1;
+    }
+
+    /// Populate with 64-bit digests for a collection of keys, owned or
+    /// borrowed.
+    ///
+    /// Digest for key shall be generated using the default-hasher or via hasher supplied
+    /// via [FusePackedBuilder::with_hasher] method.
+    pub fn populate<I>(&mut self, keys: I)
+    where
+        I: IntoIterator,
+        I::Item: Hash,
+    {
+        let (n, start) = (self.digests.len(), Instant::now());
+
+        let hash_builder = &self.hash_builder;
+        self.digests.extend(keys.into_iter().map(|key| hash_builder.hash_one(key)));
+
+        self.num_digests += self.digests.len() - n;
+        self.hash_time += start.elapsed();
+    }
+
+    /// Populate with pre-compute collection of 64-bit digests.
+    pub fn populate_digests<I>(&mut self, digests: I)
+    where
+        I: IntoIterator,
+        I::Item: Borrow<u64>,
+    {
+        let n = self.digests.len();
+
+        self.digests.extend(digests.into_iter().map(|digest| *digest.borrow()));
+
+        self.num_digests += self.digests.len() - n;
+    }
+
+    /// Build bitmap for keys that where previously inserted using
+    /// [FusePackedBuilder::insert], [FusePackedBuilder::populate] and
+    /// [FusePackedBuilder::populate_digests] method. Duplicate digests are removed
+    /// before building the bitmap.
+    pub fn build(&mut self) -> Result<FusePacked<H>> {
+        self.build_with_stats().map(|(filter, _)| filter)
+    }
+
+    /// Same as [FusePackedBuilder::build], and return statistics on the build.
+    pub fn build_with_stats(&mut self) -> Result<(FusePacked<H>, BuildStats)> {
+        let start = Instant::now();
+        binary_fuse_dedup(&mut self.digests);
+        let dedup_time = start.elapsed();
+
+        let digests = std::mem::take(&mut self.digests);
+        let res = self.build_from_digests_with_stats(&digests);
+        self.digests = digests;
+
+        let (filter, mut stats) = res?;
+        stats.num_digests = self.num_digests;
+        stats.num_duplicates = self.num_digests - stats.num_unique;
+        stats.hash_time = self.hash_time;
+        stats.dedup_time += dedup_time;
+        Ok((filter, stats))
+    }
+
+    /// Build a bitmap for pre-computed 64-bit digests for keys.
+    ///
+    /// If keys where previously inserted using [FusePackedBuilder::insert] or
+    /// [FusePackedBuilder::populate] or [FusePackedBuilder::populate_digests] methods,
+    /// they shall be ignored.
+    ///
+    /// Duplicate digests are tolerated, at the cost of de-duplicating a copy of
+    /// digests when they fail the build, and [FusePacked::len] reports the number of
+    /// unique digests. Filters are sized with `u32`, more than `u32::MAX` digests fail
+    /// the build with [Error::CapacityExceeded].
+    pub fn build_from_digests(&mut self, digests: &[u64]) -> Result<FusePacked<H>> {
+        self.build_from_digests_with_stats(digests).map(|(filter, _)| filter)
+    }
+
+    /// Same as [FusePackedBuilder::build_from_digests], and return statistics on the
+    /// build.
+    pub fn build_from_digests_with_stats(
+        &mut self,
+        digests: &[u64],
+    ) -> Result<(FusePacked<H>, BuildStats)> {
+        if digests.len() > (u32::MAX as usize) {
+            return Err(Error::CapacityExceeded {
+                keys: digests.len(),
+                capacity: u32::MAX as usize,
+            });
+        }
+
+        let segs = Segments::new(digests.len() as u32);
+        let mut stats = BuildStats::default();
+        let mut rng_counter = self.rng_seed;
+        let peeled = binary_fuse_peel(&segs, digests, &mut rng_counter, &mut stats)?;
+
+        let start = Instant::now();
+        let mut finger_prints = PackedArray::new(self.bits, segs.array_length as usize);
+        binary_fuse_assign(&segs, &peeled, &mut finger_prints, binary_fuse_fingerprint);
+        stats.assign_time = start.elapsed();
+        stats.set_array(finger_prints.len(), self.bits);
+
+        let filter = FusePacked {
+            hash_builder: self.hash_builder.clone(),
+            seed: peeled.seed,
+            num_keys: Some(peeled.len()),
+            segment_length: segs.segment_length,
+            segment_length_mask: segs.segment_length_mask,
+            segment_count: segs.segment_count,
+            segment_count_length: segs.segment_count_length,
+            finger_prints: Arc::new(finger_prints),
+        };
+
+        Ok((filter, stats))
+    }
+}
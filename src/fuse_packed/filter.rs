@@ -0,0 +1,261 @@
+//! Binary fuse filter with bit-packed fingerprints of arbitrary width.
+//!
+//! Refer to [FusePacked] for details.
+
+#[allow(unused_imports)]
+use std::collections::hash_map::DefaultHasher;
+#[allow(unused_imports)]
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::hash::Hash;
+use std::sync::Arc;
+
+#[cfg(feature = "cbordata")]
+use cbordata::Cbor;
+#[cfg(feature = "cbordata")]
+use cbordata::Cborize;
+#[cfg(feature = "cbordata")]
+use cbordata::FromCbor;
+#[cfg(feature = "cbordata")]
+use cbordata::IntoCbor;
+#[cfg(feature = "cbordata")]
+use cbordata::{self as cbor};
+
+use crate::fuse::binary_fuse_fingerprint;
+use crate::fuse::binary_fuse_hash_batch;
+use crate::fuse::binary_fuse_mix_split;
+use crate::fuse::BinaryHashes;
+use crate::fuse_packed::FusePackedBuilder;
+use crate::packed::PackedArray;
+use crate::BuildHasherDefault;
+use crate::Filter;
+use crate::Result;
+
+/// Type FusePacked is probabilistic data-structure to test membership of an element
+/// in a set.
+///
+/// FusePacked is a 3-wise binary fuse filter, same as [crate::BinaryFuseFilter], whose
+/// fingerprints are `bits` wide, as chosen by [FusePackedBuilder], and stored in a
+/// [PackedArray]. It has a false positive rate of about `2^-bits` and a memory usage
+/// of about `1.13 * bits` bits per entry for sizeable sets.
+///
+/// FusePacked is parametrized over type `H` which is expected to implement
+/// [BuildHasher] trait, like types [RandomState] and [BuildHasherDefault]. When not
+/// supplied, [BuildHasherDefault] is used as the default hash-builder.
+///
+/// If `RandomState` is used as BuildHasher, `std` has got this to say
+/// > _A particular instance RandomState will create the same instances
+/// > of Hasher, but the hashers created by two different RandomState
+/// > instances are unlikely to produce the same result for the same values._
+///
+/// If [DefaultHasher] is used as BuildHasher, `std` has got this to say,
+/// > _The internal algorithm is not specified, and so its hashes
+/// > should not be relied upon over releases._
+///
+/// IMPORTANT: Refer to [crate::BinaryFuse] on handling duplicate keys.
+#[derive(Clone, Debug)]
+pub struct FusePacked<H = BuildHasherDefault>
+where H: BuildHasher
+{
+    pub hash_builder: H,
+    pub seed: u64,
+    pub num_keys: Option<usize>,
+    pub segment_length: u32,
+    pub segment_length_mask: u32,
+    pub segment_count: u32,
+    pub segment_count_length: u32,
+    pub finger_prints: Arc<PackedArray>,
+}
+
+impl<H> PartialEq for FusePacked<H>
+where H: BuildHasher
+{
+    fn eq(&self, other: &Self) -> bool {
+        let num_keys = match (self.num_keys, other.num_keys) {
+            (Some(a), Some(b)) => a == b,
+            (_, _) => true,
+        };
+
+        self.seed == other.seed
+            && num_keys
+            && self.segment_length == other.segment_length
+            && self.segment_count == other.segment_count
+            && self.finger_prints == other.finger_prints
+    }
+}
+
+impl<H> FusePacked<H>
+where H: BuildHasher + Clone
+{
+    /// Build a filter from an iterator of keys, owned or borrowed, hashing them on
+    /// the fly with supplied hasher. Same as populating a [FusePackedBuilder] and
+    /// building it, so keys need not be materialized as a slice. Fingerprints are
+    /// `bits` wide.
+    ///
+    /// Panics if `bits` is not within 1 and 32.
+    pub fn build_from_iter<I>(
+        keys: I,
+        bits: u32,
+        hash_builder: H,
+    ) -> Result<FusePacked<H>>
+    where
+        I: IntoIterator,
+        I::Item: Hash,
+    {
+        let mut builder = FusePackedBuilder::with_hasher(bits, hash_builder);
+        builder.populate(keys);
+        builder.build()
+    }
+}
+
+impl<H> FusePacked<H>
+where H: BuildHasher
+{
+    /// Return the size of index.
+    #[inline]
+    pub fn size_of(&self) -> usize {
+        std::mem::size_of::<Self>() + self.finger_prints.size_of()
+    }
+
+    /// Return the width of fingerprints, in bits.
+    #[inline]
+    pub fn bits(&self) -> u32 {
+        self.finger_prints.bits()
+    }
+}
+
+impl<H> FusePacked<H>
+where H: BuildHasher
+{
+    #[allow(clippy::len_without_is_empty)]
+    /// Return the number of keys added/built into the bitmap index.
+    pub fn len(&self) -> Option<usize> {
+        self.num_keys
+    }
+
+    /// Contains tell you whether the key is likely part of the set, with false
+    /// positive rate.
+    pub fn contains<K: ?Sized + Hash>(&self, key: &K) -> bool {
+        let digest = self.hash_builder.hash_one(key);
+        self.contains_key(digest)
+    }
+
+    /// Contains tell you whether the key, as pre-computed digest form, is likely
+    /// part of the set, with false positive rate.
+    pub fn contains_key(&self, digest: u64) -> bool {
+        let hash = binary_fuse_mix_split(digest, self.seed);
+        let f = binary_fuse_fingerprint(hash) & self.finger_prints.mask();
+        let BinaryHashes { h0, h1, h2 } = binary_fuse_hash_batch(
+            hash,
+            self.segment_length,
+            self.segment_length_mask,
+            self.segment_count_length,
+        );
+        let f = f
+            ^ self.finger_prints.get(h0 as usize)
+            ^ self.finger_prints.get(h1 as usize)
+            ^ self.finger_prints.get(h2 as usize);
+        f == 0
+    }
+
+    pub fn get_hasher(&self) -> H::Hasher {
+        self.hash_builder.build_hasher()
+    }
+}
+
+impl<H> Filter for FusePacked<H>
+where H: BuildHasher
+{
+    fn contains_digest(&self, digest: u64) -> bool {
+        self.contains_key(digest)
+    }
+
+    fn len(&self) -> Option<usize> {
+        self.num_keys
+    }
+
+    fn size_of(&self) -> usize {
+        self.size_of()
+    }
+}
+
+//------ Implement cbordata related functionalities
+
+// Intermediate type to serialize and de-serialized FusePacked into bytes.
+#[cfg(feature = "cbordata")]
+#[derive(Cborize)]
+struct CborFusePacked {
+    hash_builder: Vec<u8>,
+    seed: u64,
+    num_keys: Option<usize>,
+    segment_length: u32,
+    segment_length_mask: u32,
+    segment_count: u32,
+    segment_count_length: u32,
+    bits: u32,
+    array_length: usize,
+    finger_prints: Vec<u64>,
+}
+
+#[cfg(feature = "cbordata")]
+impl CborFusePacked {
+    const ID: &'static str = "fusepacked/0.0.1";
+}
+
+#[cfg(feature = "cbordata")]
+impl<H> IntoCbor for FusePacked<H>
+where H: BuildHasher + Into<Vec<u8>>
+{
+    fn into_cbor(self) -> cbor::Result<Cbor> {
+        let val = CborFusePacked {
+            hash_builder: self.hash_builder.into(),
+            seed: self.seed,
+            num_keys: self.num_keys,
+            segment_length: self.segment_length,
+            segment_length_mask: self.segment_length_mask,
+            segment_count: self.segment_count,
+            segment_count_length: self.segment_count_length,
+            bits: self.finger_prints.bits(),
+            array_length: self.finger_prints.len(),
+            finger_prints: self.finger_prints.as_words().to_vec(),
+        };
+        val.into_cbor()
+    }
+}
+
+#[cfg(feature = "cbordata")]
+impl<H> FromCbor for FusePacked<H>
+where H: BuildHasher + From<Vec<u8>>
+{
+    fn from_cbor(val: Cbor) -> cbor::Result<Self> {
+        let val = CborFusePacked::from_cbor(val)?;
+
+        let finger_prints = match PackedArray::from_words(
+            val.bits,
+            val.array_length,
+            val.finger_prints,
+        ) {
+            Some(finger_prints) => finger_prints,
+            None => {
+                let msg = format!("invalid packed fingerprints, bits:{}", val.bits);
+                return Err(cbor::Error::FailConvert(
+                    format!("{}:{}", file!(), line!()),
+                    msg,
+                ));
+            }
+        };
+
+        let filter = FusePacked {
+            hash_builder: val.hash_builder.into(),
+            seed: val.seed,
+            num_keys: val.num_keys,
+            segment_length: val.segment_length,
+            segment_length_mask: val.segment_length_mask,
+            segment_count: val.segment_count,
+            segment_count_length: val.segment_count_length,
+            finger_prints: Arc::new(finger_prints),
+        };
+
+        Ok(filter)
+    }
+}
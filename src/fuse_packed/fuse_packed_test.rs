@@ -0,0 +1,314 @@
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::time::Duration;
+
+#[cfg(feature = "cbordata")]
+use cbordata::FromCbor;
+#[cfg(feature = "cbordata")]
+use cbordata::IntoCbor;
+use rand::prelude::random;
+use rand::rngs::StdRng;
+use rand::Rng;
+use rand::SeedableRng;
+
+use super::*;
+use crate::BuildHasherDefault;
+
+fn generate_unique_keys(rng: &mut StdRng, size: usize) -> Vec<u64> {
+    let mut keys: Vec<u64> = Vec::with_capacity(size);
+    keys.resize(size, u64::default());
+
+    for key in keys.iter_mut() {
+        *key = rng.gen();
+    }
+    keys.sort_unstable();
+    keys.dedup();
+
+    for _i in 0..(size - keys.len()) {
+        let key = rng.gen::<u64>();
+        if !keys.contains(&key) {
+            keys.push(key)
+        }
+    }
+
+    keys
+}
+
+fn test_fuse_packed_build<H>(name: &str, seed: u64, size: u32, bits: u32)
+where H: Clone + Default + BuildHasher {
+    let (x, y) = {
+        let size = size as usize;
+        (size / 3, size / 3)
+    };
+
+    println!(
+        "test_fuse_packed_build<{}> size:{} bits:{}",
+        name, size, bits
+    );
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let mut builder = FusePackedBuilder::<H>::new(bits);
+    let keys = generate_unique_keys(&mut rng, size as usize);
+    let (keys1, keys2, keys3) = (&keys[0..x], &keys[x..x + y], &keys[x + y..]);
+
+    // populate api
+    builder.populate(keys1);
+    // populate_digests api
+    let digests: Vec<u64> = keys2
+        .iter()
+        .map(|k| {
+            let mut hasher = builder.get_hasher();
+            k.hash(&mut hasher);
+            hasher.finish()
+        })
+        .collect();
+    builder.populate_digests(&digests);
+    // insert api
+    keys3.iter().for_each(|key| builder.insert(key));
+
+    let filter = builder.build().expect("failed to build fuse-packed filter");
+    assert_eq!(filter.bits(), bits);
+
+    // contains api
+    for key in keys.iter() {
+        assert!(filter.contains(key), "key {} not present", key);
+    }
+    // contains_key api
+    for key in keys.iter() {
+        let digest = {
+            let mut hasher = filter.get_hasher();
+            key.hash(&mut hasher);
+            hasher.finish()
+        };
+        assert!(filter.contains_key(digest), "key {} not present", key);
+    }
+
+    // print some statistics
+    let (falsesize, mut matches) = (1_000_000, 0_f64);
+    let bpv = ((filter.finger_prints.size_of() * 8) as f64) / (keys.len() as f64);
+    println!(
+        "test_fuse_packed_build<{}> bits per entry {} bits",
+        name, bpv
+    );
+    if size > 100000 {
+        let limit = (bits as f64) * 1.15;
+        assert!(bpv < limit, "bpv({}) >= {}", bpv, limit);
+    }
+
+    for _ in 0..falsesize {
+        if filter.contains(&rng.gen::<u64>()) {
+            matches += 1_f64;
+        }
+    }
+
+    let fpp = matches * 100.0 / (falsesize as f64);
+    println!(
+        "test_fuse_packed_build<{}> false positive rate {}%",
+        name, fpp
+    );
+    if size > 1000 {
+        // allow twice the expected rate, with some slack for very small rates.
+        let limit = (2.0 * 100.0 / ((1_u64 << bits) as f64)) + 0.001;
+        assert!(fpp < limit, "fpp({}) >= {}", fpp, limit);
+    }
+}
+
+fn test_fuse_packed_build_digests<H>(name: &str, seed: u64, size: u32, bits: u32)
+where H: Clone + Default + BuildHasher {
+    println!(
+        "test_fuse_packed_build_digests<{}> size:{} bits:{}",
+        name, size, bits
+    );
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let mut builder = FusePackedBuilder::<H>::new(bits);
+
+    // build_from_digests api
+    let keys = generate_unique_keys(&mut rng, size as usize);
+    let digests: Vec<u64> = keys
+        .iter()
+        .map(|k| {
+            let mut hasher = builder.get_hasher();
+            k.hash(&mut hasher);
+            hasher.finish()
+        })
+        .collect();
+
+    let filter =
+        builder.build_from_digests(&digests).expect("failed to build fuse-packed filter");
+
+    // contains api
+    for key in keys.iter() {
+        assert!(filter.contains(key), "key {} not present", key);
+    }
+    // contains_key api
+    for digest in digests.into_iter() {
+        assert!(filter.contains_key(digest), "digest {} not present", digest);
+    }
+}
+
+#[test]
+fn test_fuse_packed() {
+    let mut seed: u64 = random();
+    println!("test_fuse_packed seed:{}", seed);
+
+    for bits in [4, 7, 12, 20, 31].iter() {
+        for size in [0, 1, 2, 10, 1000, 10_000, 100_000, 1_000_000].iter() {
+            seed = seed.wrapping_add(*size as u64);
+            test_fuse_packed_build::<RandomState>("RandomState", seed, *size, *bits);
+            test_fuse_packed_build::<BuildHasherDefault>(
+                "BuildHasherDefault",
+                seed,
+                *size,
+                *bits,
+            );
+            test_fuse_packed_build_digests::<RandomState>(
+                "RandomState",
+                seed,
+                *size,
+                *bits,
+            );
+            test_fuse_packed_build_digests::<BuildHasherDefault>(
+                "BuildHasherDefault",
+                seed,
+                *size,
+                *bits,
+            );
+        }
+    }
+}
+
+#[test]
+fn test_fuse_packed_bits() {
+    let mut builder = FusePackedBuilder::<BuildHasherDefault>::new(13);
+    assert_eq!(builder.bits(), 13);
+
+    let keys: Vec<u64> = (0..10_000).collect();
+    builder.populate(&keys);
+    let filter = builder.build().expect("failed to build fuse-packed filter");
+    assert_eq!(filter.bits(), 13);
+
+    let fuse8 = {
+        let mut builder = crate::fuse8::Fuse8Builder::<BuildHasherDefault>::new();
+        builder.populate(&keys);
+        builder.build().expect("failed to build fuse8 filter")
+    };
+    assert!(filter.size_of() > fuse8.size_of());
+    assert!(filter.size_of() < fuse8.size_of() * 2);
+}
+
+#[test]
+fn test_fuse_packed_from_iter() {
+    let filter = FusePacked::<BuildHasherDefault>::build_from_iter(
+        0..10_000_u64,
+        13,
+        BuildHasherDefault::default(),
+    )
+    .expect("failed to build fuse-packed filter");
+    assert_eq!(filter.bits(), 13);
+    assert_eq!(filter.len(), Some(10_000));
+
+    for key in 0..10_000_u64 {
+        assert!(filter.contains(&key), "key {} not present", key);
+    }
+}
+
+#[test]
+fn test_fuse_packed_build_stats() {
+    let seed: u64 = random();
+    println!("test_fuse_packed_build_stats seed:{}", seed);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let keys = generate_unique_keys(&mut rng, 100_000);
+
+    let mut builder = FusePackedBuilder::<BuildHasherDefault>::new(12);
+    let (filter, stats) =
+        builder.build_from_digests_with_stats(&keys).expect("failed build");
+    println!("test_fuse_packed_build_stats {}", stats);
+
+    assert_eq!(stats.num_digests, keys.len());
+    assert_eq!(stats.num_unique, keys.len());
+    assert_eq!(stats.num_duplicates, 0);
+    assert_eq!(stats.seed, filter.seed);
+    assert_eq!(stats.array_length, filter.finger_prints.len());
+    assert!(stats.bits_per_key > 12.0 && stats.bits_per_key < 15.0);
+    assert_eq!(stats.hash_time, Duration::default());
+}
+
+#[test]
+fn test_fuse_packed_duplicates() {
+    let keys: Vec<u64> = (0..10_000).collect();
+
+    let mut builder = FusePackedBuilder::<BuildHasherDefault>::new(9);
+    builder.populate(&keys);
+    builder.populate(&keys);
+    let (filter, stats) = builder.build_with_stats().expect("failed build");
+    assert_eq!(stats.num_digests, keys.len() * 2);
+    assert_eq!(stats.num_duplicates, keys.len());
+    assert_eq!(filter.len(), Some(keys.len()));
+
+    // builder retains its keys, building again yields the same filter.
+    assert!(builder.build().expect("failed build") == filter);
+    for key in keys.iter() {
+        assert!(filter.contains(key), "key {} not present", key);
+    }
+}
+
+#[test]
+fn test_fuse_packed_rng_seed() {
+    let seed: u64 = random();
+    println!("test_fuse_packed_rng_seed seed:{}", seed);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let keys = generate_unique_keys(&mut rng, 10_000);
+
+    let build = |rng_seed: u64| {
+        let mut builder = FusePackedBuilder::<BuildHasherDefault>::new(10);
+        builder.rng_seed = rng_seed;
+        builder.build_from_digests(&keys).expect("failed build")
+    };
+
+    let (filter1, filter2) = (build(seed), build(seed));
+    assert!(filter1 == filter2);
+    assert!(filter1.clone() == filter2);
+
+    let filter3 = build(seed.wrapping_add(1));
+    assert_ne!(filter1.seed, filter3.seed);
+    for key in keys.iter() {
+        assert!(filter3.contains_key(*key), "key {} not present", key);
+    }
+}
+
+#[cfg(feature = "cbordata")]
+#[test]
+fn test_fuse_packed_cbor() {
+    let seed: u64 = random();
+    println!("test_fuse_packed_cbor seed:{}", seed);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let keys: Vec<u64> = (0..100_000).map(|_| rng.gen::<u64>()).collect();
+
+    let filter = {
+        let mut builder = FusePackedBuilder::<BuildHasherDefault>::new(11);
+        builder.populate(&keys);
+        builder.build().expect("fail building fuse-packed filter")
+    };
+
+    for key in keys.iter() {
+        assert!(filter.contains(key), "key {} not present", key);
+    }
+
+    let filter = {
+        let val = filter.clone().into_cbor().unwrap();
+        let other = FusePacked::<BuildHasherDefault>::from_cbor(val).unwrap();
+        assert!(filter == other);
+        other
+    };
+
+    assert_eq!(filter.bits(), 11);
+    for key in keys.iter() {
+        assert!(filter.contains(key), "key {} not present", key);
+    }
+}
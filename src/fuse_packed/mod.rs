@@ -0,0 +1,15 @@
+//! Binary fuse filter with bit-packed fingerprints of arbitrary width.
+//!
+//! [FusePackedBuilder] collects keys and builds an immutable [FusePacked] filter,
+//! fingerprint width is chosen when creating the builder, trading memory for a false
+//! positive rate of about `2^-bits`.
+
+mod builder;
+mod filter;
+
+pub use builder::FusePackedBuilder;
+pub use filter::FusePacked;
+
+#[cfg(test)]
+#[path = "fuse_packed_test.rs"]
+mod fuse_packed_test;
@@ -0,0 +1,550 @@
+//! Filter that accepts new keys after it is built, using a chain of binary fuse
+//! filters.
+//!
+//! Refer to [GrowableFilter] for details.
+
+use std::borrow::Borrow;
+#[allow(unused_imports)]
+use std::collections::hash_map::DefaultHasher;
+#[allow(unused_imports)]
+use std::collections::hash_map::RandomState;
+use std::collections::HashSet;
+use std::hash::BuildHasher;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::sync::Arc;
+use std::thread;
+
+#[cfg(feature = "cbordata")]
+use cbordata::Cbor;
+#[cfg(feature = "cbordata")]
+use cbordata::Cborize;
+#[cfg(feature = "cbordata")]
+use cbordata::FromCbor;
+#[cfg(feature = "cbordata")]
+use cbordata::IntoCbor;
+#[cfg(feature = "cbordata")]
+use cbordata::{self as cbor};
+
+use crate::fuse::FUSE_RNG_SEED;
+use crate::hasher::random_seed;
+use crate::packed::MAX_BITS;
+use crate::BuildHasherDefault;
+use crate::Error;
+use crate::Filter;
+use crate::FusePacked;
+use crate::FusePackedBuilder;
+use crate::NoHash;
+use crate::Result;
+
+/// Type GrowableFilter is probabilistic data-structure to test membership of an
+/// element in a set, that can grow as new keys are inserted.
+///
+/// Inserted keys are buffered, until the buffer fills up, and then sealed into an
+/// immutable [FusePacked] filter, called a level. Capacity of the buffer starts with
+/// `capacity` and doubles after every level, so that the number of levels grow only
+/// logarithmically with the number of keys. Lookups are exact on buffered keys, and
+/// check every level for sealed keys.
+///
+/// To keep the combined false positive rate under `fpp`, each level gets one more
+/// fingerprint bit than the previous one, halving its false positive rate, starting
+/// from `fpp / 2` for the first level. Fingerprints are capped at 32 bits.
+///
+/// As levels accumulate, lookups get slower. When [GrowableFilter::retain_digests]
+/// is set, digests of each level are retained, and levels can be merged back into a
+/// larger level, refer to [GrowableFilter::compact] and [CompactionPolicy].
+///
+/// Example:
+/// ```
+/// # use xorfilter::GrowableFilter;
+///
+/// let mut filter: GrowableFilter = GrowableFilter::new(1000, 0.01);
+///
+/// filter.insert("foo").unwrap();
+/// filter.populate(&["bar", "baz"]).unwrap();
+///
+/// assert!(filter.contains("foo"));
+/// assert!(filter.contains("baz"));
+/// ```
+///
+/// If `RandomState` is used as BuildHasher, `std` has got this to say
+/// > _A particular instance RandomState will create the same instances
+/// > of Hasher, but the hashers created by two different RandomState
+/// > instances are unlikely to produce the same result for the same values._
+///
+/// If [DefaultHasher] is used as BuildHasher, `std` has got this to say,
+/// > _The internal algorithm is not specified, and so its hashes
+/// > should not be relied upon over releases._
+#[derive(Clone)]
+pub struct GrowableFilter<H = BuildHasherDefault>
+where H: BuildHasher
+{
+    pub hash_builder: H,
+    /// Number of keys sealed into the first level.
+    pub capacity: usize,
+    /// Fingerprint width, in bits, of the first level.
+    pub bits: u32,
+    /// Retain digests of sealed levels, required for compaction.
+    pub retain_digests: bool,
+    /// Seed for sealing and compacting levels, not serialized.
+    pub rng_seed: u64,
+    buffer: HashSet<u64>,
+    levels: Vec<FusePacked<NoHash>>,
+    retained: Vec<Option<Arc<Vec<u64>>>>,
+}
+
+impl<H> GrowableFilter<H>
+where H: BuildHasher
+{
+    /// New growable filter, with an initial `capacity` and a bound on the combined
+    /// false positive rate `fpp`, initialized with [BuildHasherDefault].
+    ///
+    /// Panics if `capacity` is ZERO or if `fpp` is not within `2^-31` and `1`.
+    pub fn new(capacity: usize, fpp: f64) -> Self
+    where H: Default {
+        Self::with_hasher(capacity, fpp, H::default())
+    }
+
+    /// New growable filter, with an initial `capacity` and a bound on the combined
+    /// false positive rate `fpp`, initialized with supplied `hasher`.
+    ///
+    /// Panics if `capacity` is ZERO or if `fpp` is not within `2^-31` and `1`.
+    pub fn with_hasher(capacity: usize, fpp: f64, hash_builder: H) -> Self {
+        let bits = (-fpp.log2()).ceil() + 1.0;
+        assert!(capacity > 0, "capacity is ZERO");
+        assert!(
+            fpp > 0.0 && fpp <= 1.0 && bits <= (MAX_BITS as f64),
+            "fpp {} not within 2^-31 and 1",
+            fpp
+        );
+
+        GrowableFilter {
+            hash_builder,
+            capacity,
+            bits: bits as u32,
+            retain_digests: false,
+            rng_seed: FUSE_RNG_SEED,
+            buffer: HashSet::default(),
+            levels: Vec::default(),
+            retained: Vec::default(),
+        }
+    }
+
+    /// Seed the builds with a random `rng_seed`, and return the same.
+    pub fn set_random_seed(&mut self) -> u64 {
+        self.rng_seed = random_seed();
+        self.rng_seed
+    }
+
+    #[allow(clippy::len_without_is_empty)]
+    /// Return the number of keys added into the filter, including buffered keys.
+    pub fn len(&self) -> Option<usize> {
+        let n: usize = self.levels.iter().filter_map(|level| level.len()).sum();
+        Some(n + self.buffer.len())
+    }
+
+    /// Return the sealed levels, oldest first.
+    pub fn levels(&self) -> &[FusePacked<NoHash>] {
+        &self.levels
+    }
+
+    /// Return the number of keys buffered, yet to be sealed into a level.
+    pub fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Return the size of index.
+    #[inline]
+    pub fn size_of(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + (self.buffer.capacity() * std::mem::size_of::<u64>())
+            + self.levels.iter().map(|level| level.size_of()).sum::<usize>()
+            + self.retained.iter().flatten().map(|ds| ds.len() * 8).sum::<usize>()
+    }
+
+    /// Insert 64-bit digest of a single key. Digest for the key shall be generated
+    /// using the default-hasher or via hasher supplied via
+    /// [GrowableFilter::with_hasher] method.
+    pub fn insert<K: ?Sized + Hash>(&mut self, key: &K) -> Result<()> {
+        let digest = self.hash(key);
+        self.insert_digest(digest)
+    }
+
+    /// Insert pre-computed 64-bit digest of a single key.
+    ///
+    /// Keys that are already reported as present, including false positives, are
+    /// skipped, unless `retain_digests` is set, in which case every key is buffered
+    /// so that compaction cannot drop them. If the buffer is full, it is sealed into
+    /// a new level, which can fail.
+    pub fn insert_digest(&mut self, digest: u64) -> Result<()> {
+        let skip = !self.retain_digests
+            && self.levels.iter().any(|level| level.contains_key(digest));
+        if !skip {
+            self.buffer.insert(digest);
+        }
+        match self.buffer.len() >= self.level_capacity() {
+            true => self.seal(),
+            false => Ok(()),
+        }
+    }
+
+    /// Populate with 64-bit digests for a collection of keys, owned or borrowed.
+    /// Digest for key shall be generated using the default-hasher or via hasher
+    /// supplied via [GrowableFilter::with_hasher] method.
+    pub fn populate<I>(&mut self, keys: I) -> Result<()>
+    where
+        I: IntoIterator,
+        I::Item: Hash,
+    {
+        for key in keys.into_iter() {
+            self.insert(&key)?;
+        }
+        Ok(())
+    }
+
+    /// Populate with pre-compute collection of 64-bit digests.
+    pub fn populate_digests<I>(&mut self, digests: I) -> Result<()>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<u64>,
+    {
+        for digest in digests.into_iter() {
+            self.insert_digest(*digest.borrow())?;
+        }
+        Ok(())
+    }
+
+    /// Seal buffered keys into a new level. Inserting keys shall automatically seal
+    /// the buffer when full, use this to seal a partially filled buffer.
+    pub fn seal(&mut self) -> Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+
+        let digests: Vec<u64> = self.buffer.iter().copied().collect();
+        let bits = std::cmp::min(self.bits + (self.levels.len() as u32), MAX_BITS);
+        let mut builder = FusePackedBuilder::with_hasher(bits, NoHash);
+        builder.rng_seed = self.rng_seed;
+        let level = builder.build_from_digests(&digests)?;
+
+        self.levels.push(level);
+        self.retained.push(match self.retain_digests {
+            true => Some(Arc::new(digests)),
+            false => None,
+        });
+        self.buffer.clear();
+
+        Ok(())
+    }
+
+    /// Contains tell you whether the key is likely part of the set, with false
+    /// positive rate.
+    pub fn contains<K: ?Sized + Hash>(&self, key: &K) -> bool {
+        let digest = self.hash(key);
+        self.contains_digest(digest)
+    }
+
+    /// Contains tell you whether the key, as pre-computed digest form, is likely
+    /// part of the set, with false positive rate.
+    pub fn contains_digest(&self, digest: u64) -> bool {
+        self.buffer.contains(&digest)
+            || self.levels.iter().rev().any(|level| level.contains_key(digest))
+    }
+
+    pub fn get_hasher(&self) -> H::Hasher {
+        self.hash_builder.build_hasher()
+    }
+
+    /// Calculate hash of a key.
+    #[inline]
+    pub fn hash<K: Hash + ?Sized>(&self, key: &K) -> u64 {
+        let mut hasher = self.get_hasher();
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Plan a compaction as per `policy`, returning `None` if there is nothing to
+    /// compact. Returned [Compaction] can be run on any thread, while this filter
+    /// keeps serving lookups and inserts, and its result is applied using
+    /// [GrowableFilter::apply].
+    ///
+    /// Fails if digests of a level, picked for compaction, were not retained.
+    pub fn compaction(&self, policy: &CompactionPolicy) -> Result<Option<Compaction>> {
+        let start = match policy.pick(&self.levels) {
+            Some(start) => start,
+            None => return Ok(None),
+        };
+
+        let mut digests = vec![];
+        for (off, retained) in self.retained[start..].iter().enumerate() {
+            match retained {
+                Some(ds) => digests.push(Arc::clone(ds)),
+                None => {
+                    err_at!(Fatal, msg: "digests of level {} not retained", start + off)?
+                }
+            }
+        }
+
+        let compaction = Compaction {
+            start,
+            bits: self.levels[start].bits(),
+            rng_seed: self.rng_seed,
+            digests,
+        };
+
+        Ok(Some(compaction))
+    }
+
+    /// Compact levels as per `policy`, on the calling thread. Return whether any
+    /// levels were compacted.
+    pub fn compact(&mut self, policy: &CompactionPolicy) -> Result<bool> {
+        match self.compaction(policy)? {
+            Some(compaction) => {
+                let compacted = compaction.run()?;
+                self.apply(compacted)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Same as [GrowableFilter::compact], but run the compaction on a background
+    /// thread. Lookups and inserts can continue on this filter, apply the result
+    /// from the thread using [GrowableFilter::apply].
+    pub fn compact_background(
+        &self,
+        policy: &CompactionPolicy,
+    ) -> Result<Option<thread::JoinHandle<Result<Compacted>>>> {
+        match self.compaction(policy)? {
+            Some(compaction) => Ok(Some(thread::spawn(move || compaction.run()))),
+            None => Ok(None),
+        }
+    }
+
+    /// Replace the compacted levels with the merged level.
+    ///
+    /// Fails if the compacted levels were modified, by another compaction, since
+    /// the compaction was planned.
+    pub fn apply(&mut self, compacted: Compacted) -> Result<()> {
+        let (start, n) = (compacted.start, compacted.replaced.len());
+
+        // levels are identified by their retained digests.
+        let same = match self.retained.get(start..(start + n)) {
+            Some(retained) => {
+                let mut iter = retained.iter().zip(compacted.replaced.iter());
+                iter.all(|(ds, replaced)| match ds {
+                    Some(ds) => Arc::ptr_eq(ds, replaced),
+                    None => false,
+                })
+            }
+            None => false,
+        };
+        if !same {
+            err_at!(Fatal, msg: "levels {}..{} modified since compaction", start, start + n)?;
+        }
+
+        self.levels.splice(start..(start + n), Some(compacted.level));
+        self.retained.splice(start..(start + n), Some(Some(compacted.digests)));
+
+        Ok(())
+    }
+
+    // capacity of the next level, doubles for every sealed level.
+    fn level_capacity(&self) -> usize {
+        match 1_usize.checked_shl(self.levels.len() as u32) {
+            Some(n) => self.capacity.saturating_mul(n),
+            None => usize::MAX,
+        }
+    }
+}
+
+impl<H> Filter for GrowableFilter<H>
+where H: BuildHasher
+{
+    fn contains_digest(&self, digest: u64) -> bool {
+        self.contains_digest(digest)
+    }
+
+    fn len(&self) -> Option<usize> {
+        self.len()
+    }
+
+    fn size_of(&self) -> usize {
+        self.size_of()
+    }
+}
+
+/// Policy to pick levels of a [GrowableFilter] for compaction.
+///
+/// Starting from the newest level, older levels are picked as long as each holds
+/// fewer than `size_ratio` times the number of keys in the levels picked so far.
+/// If that leaves more than `max_levels` levels, more older levels are picked until
+/// `max_levels` are left. Picked levels are merged into a single level.
+///
+/// Since every level holds twice the keys of the previous one, a `size_ratio` of
+/// `2.0` merges all the levels, after which each new level is merged into its
+/// predecessor until the predecessor is twice its size.
+#[derive(Clone, Debug)]
+pub struct CompactionPolicy {
+    pub size_ratio: f64,
+    pub max_levels: usize,
+}
+
+impl Default for CompactionPolicy {
+    fn default() -> Self {
+        CompactionPolicy {
+            size_ratio: 2.0,
+            max_levels: 8,
+        }
+    }
+}
+
+impl CompactionPolicy {
+    // return the oldest level to compact, all levels from that upto the newest
+    // level are to be merged.
+    fn pick(&self, levels: &[FusePacked<NoHash>]) -> Option<usize> {
+        let sizes: Vec<f64> =
+            levels.iter().map(|l| l.len().unwrap_or(0) as f64).collect();
+
+        let mut start = sizes.len().checked_sub(1)?;
+        let mut acc = sizes[start];
+        while start > 0 && sizes[start - 1] < self.size_ratio * acc {
+            start -= 1;
+            acc += sizes[start];
+        }
+        if sizes.len() > self.max_levels {
+            start = std::cmp::min(start, self.max_levels.saturating_sub(1));
+        }
+
+        match sizes.len() - start {
+            0 | 1 => None,
+            _ => Some(start),
+        }
+    }
+}
+
+/// Planned compaction, merging levels of a [GrowableFilter] into one. Refer to
+/// [GrowableFilter::compaction].
+pub struct Compaction {
+    start: usize,
+    bits: u32,
+    rng_seed: u64,
+    digests: Vec<Arc<Vec<u64>>>,
+}
+
+impl Compaction {
+    /// Build the merged level, rebuilding from the retained digests.
+    pub fn run(self) -> Result<Compacted> {
+        let mut digests: Vec<u64> =
+            self.digests.iter().flat_map(|ds| ds.iter()).copied().collect();
+        digests.sort_unstable();
+        digests.dedup();
+
+        let mut builder = FusePackedBuilder::with_hasher(self.bits, NoHash);
+        builder.rng_seed = self.rng_seed;
+        let level = builder.build_from_digests(&digests)?;
+
+        let compacted = Compacted {
+            start: self.start,
+            replaced: self.digests,
+            level,
+            digests: Arc::new(digests),
+        };
+
+        Ok(compacted)
+    }
+}
+
+/// Result of a [Compaction], to be applied using [GrowableFilter::apply].
+pub struct Compacted {
+    start: usize,
+    replaced: Vec<Arc<Vec<u64>>>,
+    level: FusePacked<NoHash>,
+    digests: Arc<Vec<u64>>,
+}
+
+//------ Implement cbordata related functionalities
+
+// Intermediate type to serialize and de-serialized GrowableFilter into bytes.
+#[cfg(feature = "cbordata")]
+#[derive(Cborize)]
+struct CborGrowableFilter {
+    hash_builder: Vec<u8>,
+    capacity: usize,
+    bits: u32,
+    retain_digests: bool,
+    buffer: Vec<u64>,
+    levels: Vec<Cbor>,
+    retained: Vec<Option<Vec<u64>>>,
+}
+
+#[cfg(feature = "cbordata")]
+impl CborGrowableFilter {
+    const ID: &'static str = "growablefilter/0.0.1";
+}
+
+#[cfg(feature = "cbordata")]
+impl<H> IntoCbor for GrowableFilter<H>
+where H: BuildHasher + Into<Vec<u8>>
+{
+    fn into_cbor(self) -> cbor::Result<Cbor> {
+        let mut levels = vec![];
+        for level in self.levels.into_iter() {
+            levels.push(level.into_cbor()?);
+        }
+
+        let val = CborGrowableFilter {
+            hash_builder: self.hash_builder.into(),
+            capacity: self.capacity,
+            bits: self.bits,
+            retain_digests: self.retain_digests,
+            buffer: self.buffer.into_iter().collect(),
+            levels,
+            retained: self
+                .retained
+                .iter()
+                .map(|ds| ds.as_ref().map(|ds| ds.to_vec()))
+                .collect(),
+        };
+        val.into_cbor()
+    }
+}
+
+#[cfg(feature = "cbordata")]
+impl<H> FromCbor for GrowableFilter<H>
+where H: BuildHasher + From<Vec<u8>>
+{
+    fn from_cbor(val: Cbor) -> cbor::Result<Self> {
+        let val = CborGrowableFilter::from_cbor(val)?;
+
+        let mut levels = vec![];
+        for level in val.levels.into_iter() {
+            levels.push(FusePacked::<NoHash>::from_cbor(level)?);
+        }
+
+        if levels.len() != val.retained.len() {
+            let msg = format!("levels:{} retained:{}", levels.len(), val.retained.len());
+            return Err(cbor::Error::FailConvert(
+                format!("{}:{}", file!(), line!()),
+                msg,
+            ));
+        }
+
+        let filter = GrowableFilter {
+            hash_builder: val.hash_builder.into(),
+            capacity: val.capacity,
+            bits: val.bits,
+            retain_digests: val.retain_digests,
+            rng_seed: FUSE_RNG_SEED,
+            buffer: val.buffer.into_iter().collect(),
+            levels,
+            retained: val.retained.into_iter().map(|ds| ds.map(Arc::new)).collect(),
+        };
+
+        Ok(filter)
+    }
+}
+
+#[cfg(test)]
+#[path = "growable_test.rs"]
+mod growable_test;
@@ -0,0 +1,218 @@
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+
+#[cfg(feature = "cbordata")]
+use cbordata::FromCbor;
+#[cfg(feature = "cbordata")]
+use cbordata::IntoCbor;
+use rand::prelude::random;
+use rand::rngs::StdRng;
+use rand::Rng;
+use rand::SeedableRng;
+
+use super::*;
+
+fn test_growable_build<H>(name: &str, seed: u64, capacity: usize, size: usize, fpp: f64)
+where H: BuildHasher + Clone + Default {
+    println!(
+        "test_growable_build<{}> capacity:{} size:{} fpp:{}",
+        name, capacity, size, fpp
+    );
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let keys: Vec<u64> = (0..size).map(|_| rng.gen::<u64>()).collect();
+    let (keys1, keys2) = keys.split_at(size / 2);
+
+    let mut filter = GrowableFilter::<H>::new(capacity, fpp);
+    // populate api
+    filter.populate(keys1).expect("failed populate");
+    // keys are visible as soon as they are inserted.
+    for key in keys1.iter() {
+        assert!(filter.contains(key), "key {} not present", key);
+    }
+    // populate_digests api
+    let digests: Vec<u64> = keys2.iter().map(|k| filter.hash(k)).collect();
+    filter.populate_digests(digests.iter()).expect("failed populate_digests");
+    // insert api, duplicates are skipped.
+    if let Some(key) = keys.first() {
+        filter.insert(key).expect("failed insert");
+    }
+
+    // keys that are false positives against sealed levels are skipped.
+    let n = filter.len().unwrap();
+    let limit = (size as f64) * (1.0 - 2.0 * fpp) - 3.0;
+    assert!(n <= size && (n as f64) >= limit, "len {} size {}", n, size);
+    let mut capacity = capacity;
+    for level in filter.levels().iter() {
+        assert_eq!(level.len(), Some(capacity));
+        capacity *= 2;
+    }
+    assert!(filter.buffered() < capacity);
+
+    for key in keys.iter() {
+        assert!(filter.contains(key), "key {} not present", key);
+    }
+    for digest in digests.iter() {
+        assert!(
+            filter.contains_digest(*digest),
+            "digest {} not present",
+            digest
+        );
+    }
+
+    let (falsesize, mut matches) = (1_000_000, 0_f64);
+    for _ in 0..falsesize {
+        if filter.contains(&rng.gen::<u64>()) {
+            matches += 1_f64;
+        }
+    }
+    let rate = matches / (falsesize as f64);
+    println!(
+        "test_growable_build<{}> levels:{} false positive rate {}",
+        name,
+        filter.levels().len(),
+        rate
+    );
+    assert!(rate < (fpp * 1.2), "rate({}) >= {}", rate, fpp * 1.2);
+
+    filter.seal().expect("failed seal");
+    assert_eq!(filter.buffered(), 0);
+    assert_eq!(filter.len(), Some(n));
+    for key in keys.iter() {
+        assert!(filter.contains(key), "key {} not present", key);
+    }
+}
+
+#[test]
+fn test_growable() {
+    let mut seed: u64 = random();
+    println!("test_growable seed:{}", seed);
+
+    for (capacity, size) in
+        [(1, 0), (1, 100), (1000, 1_000_000), (100_000, 300_000)].iter()
+    {
+        for fpp in [0.1, 0.01, 0.001].iter() {
+            seed = seed.wrapping_add(*size as u64);
+            test_growable_build::<RandomState>(
+                "RandomState",
+                seed,
+                *capacity,
+                *size,
+                *fpp,
+            );
+            test_growable_build::<BuildHasherDefault>(
+                "BuildHasherDefault",
+                seed,
+                *capacity,
+                *size,
+                *fpp,
+            );
+        }
+    }
+}
+
+#[test]
+fn test_growable_compact() {
+    let seed: u64 = random();
+    println!("test_growable_compact seed:{}", seed);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let keys: Vec<u64> = (0..1_000_000).map(|_| rng.gen::<u64>()).collect();
+    let (keys1, keys2) = keys.split_at(500_000);
+
+    let mut filter = GrowableFilter::<BuildHasherDefault>::new(100, 0.01);
+    filter.populate(keys1).expect("failed populate");
+    assert!(filter.levels().len() > 8);
+    // digests are not retained, compaction shall fail.
+    let policy = CompactionPolicy::default();
+    assert!(filter.compact(&policy).is_err());
+
+    let mut filter = GrowableFilter::<BuildHasherDefault>::new(100, 0.01);
+    filter.retain_digests = true;
+    filter.populate(keys1).expect("failed populate");
+    let (n, levels) = (filter.len(), filter.levels().len());
+
+    let policy = CompactionPolicy {
+        size_ratio: 0.0,
+        max_levels: 4,
+    };
+    assert!(filter.compact(&policy).expect("failed compact"));
+    println!(
+        "test_growable_compact levels {} -> {}",
+        levels,
+        filter.levels().len()
+    );
+    assert_eq!(filter.levels().len(), 4);
+    assert_eq!(filter.len(), n);
+    assert!(!filter.compact(&policy).expect("failed compact"));
+    for key in keys1.iter() {
+        assert!(filter.contains(key), "key {} not present", key);
+    }
+
+    // compact in background, while inserting more keys.
+    let policy = CompactionPolicy::default();
+    let handle = filter.compact_background(&policy).unwrap().unwrap();
+    filter.populate(keys2).expect("failed populate");
+    let levels = filter.levels().len();
+    for key in keys.iter() {
+        assert!(filter.contains(key), "key {} not present", key);
+    }
+
+    let compacted = handle.join().unwrap().expect("failed compaction");
+    filter.apply(compacted).expect("failed apply");
+    assert_eq!(filter.levels().len(), levels - 3);
+    for key in keys.iter() {
+        assert!(filter.contains(key), "key {} not present", key);
+    }
+
+    // a stale compaction shall not be applied.
+    let compaction = filter.compaction(&policy).unwrap().unwrap();
+    let stale = filter.compaction(&policy).unwrap().unwrap().run().unwrap();
+    filter.apply(compaction.run().unwrap()).expect("failed apply");
+    assert_eq!(filter.levels().len(), 1);
+    assert!(filter.apply(stale).is_err());
+
+    let (falsesize, mut matches) = (1_000_000, 0_f64);
+    for _ in 0..falsesize {
+        if filter.contains(&rng.gen::<u64>()) {
+            matches += 1_f64;
+        }
+    }
+    let rate = matches / (falsesize as f64);
+    println!("test_growable_compact false positive rate {}", rate);
+    assert!(rate < 0.012, "rate({}) >= 0.012", rate);
+}
+
+#[test]
+#[should_panic]
+fn test_growable_fpp() {
+    GrowableFilter::<BuildHasherDefault>::new(100, 2.0_f64.powi(-32));
+}
+
+#[cfg(feature = "cbordata")]
+#[test]
+fn test_growable_cbor() {
+    let seed: u64 = random();
+    println!("test_growable_cbor seed:{}", seed);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let keys: Vec<u64> = (0..100_000).map(|_| rng.gen::<u64>()).collect();
+
+    let filter = {
+        let mut filter = GrowableFilter::<BuildHasherDefault>::new(1000, 0.001);
+        filter.populate(&keys).expect("failed populate");
+        filter
+    };
+    let (levels, buffered) = (filter.levels().len(), filter.buffered());
+
+    let filter = {
+        let val = filter.into_cbor().unwrap();
+        GrowableFilter::<BuildHasherDefault>::from_cbor(val).unwrap()
+    };
+    assert_eq!(filter.levels().len(), levels);
+    assert_eq!(filter.buffered(), buffered);
+
+    for key in keys.iter() {
+        assert!(filter.contains(key), "key {} not present", key);
+    }
+}
@@ -1,4 +1,5 @@
 use std::collections::hash_map::DefaultHasher;
+use std::collections::hash_map::RandomState;
 use std::hash::BuildHasher;
 use std::hash::Hasher;
 use std::hash::{self};
@@ -32,6 +33,12 @@ impl BuildHasher for BuildHasherDefault {
     }
 }
 
+/// Return a random 64-bit seed, keyed by the operating system's entropy source via
+/// [RandomState]. Used to seed filter builds that shall not be predictable.
+pub(crate) fn random_seed() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
+
 /// NoHash type skips hashing altogether.
 ///
 /// When a filter is constructed using NoHash as the type parameter then it is upto
@@ -1,14 +1,73 @@
-#![allow(clippy::bool_to_int_with_if)]
-
 //! Library implements xor-filter.
 //!
 //! Refer to original implementation under `github.com/FastFilter` to learn the
-//! differences between [Xor8], [Fuse8] and [Fuse16] filters. Otherwise, all the types
-//! provides similar methods.
+//! differences between [Xor8], [Fuse8], [Fuse16] and [fuse32::Fuse32] filters.
+//! Otherwise, all the types provides similar methods.
+//!
+//! [Fuse8] and [Fuse16] are type aliases for [BinaryFuse] filter, which is generic
+//! over the [Fingerprint] width.
 //!
 //! Starting from version `0.6.0` [Xor8] type is split into [xor8::Xor8] and
 //! [xor8::Xor8Builder] under module [xor8]. And [Xor8] type is now deprecated.
 //!
+//! Likewise, from version `0.7.0` [Fuse8] and [Fuse16] types, which mix the buffer
+//! of keys with the built filter, are split into builders [fuse8::Fuse8Builder] and
+//! [fuse16::Fuse16Builder] that produce immutable [fuse8::Fuse8] and [fuse16::Fuse16]
+//! filters. And [Fuse8] and [Fuse16] types are now deprecated. Filters with 32-bit
+//! fingerprints are built using [fuse32::Fuse32Builder] into [fuse32::Fuse32]. All of
+//! them are aliases for [BinaryFuseBuilder] and [BinaryFuseFilter], generic over the
+//! [Fingerprint] width. [fuse8::Fuse8x4], [fuse16::Fuse16x4] and [fuse32::Fuse32x4]
+//! are their 4-wise counterparts, that trade an extra memory access per lookup for
+//! smaller memory footprint on large sets.
+//!
+//! Xor filters with wider fingerprints are available under module [xor16] and
+//! [xor32], they follow the same builder/filter split as [xor8]. All of them are
+//! aliases for [XorFilter] and [XorBuilder], generic over the [Fingerprint] width.
+//! [xor8plus::Xor8Plus]
+//! is a compressed form of [xor8::Xor8], saving about 8% memory.
+//!
+//! When none of the fixed fingerprint widths fit, [FusePackedBuilder] and
+//! [xor_packed::XorPackedBuilder] accept any width from 1 to 32 bits, building
+//! [FusePacked] and [xor_packed::XorPacked] filters that store fingerprints in a
+//! bit-[PackedArray].
+//!
+//! To specify an error budget instead of a filter type, use
+//! [FilterBuilder::with_fpp], which picks the smallest filter meeting the target
+//! false positive rate and returns it as [AnyFilter]. All filter types implement
+//! the [Filter] trait for lookups on pre-computed digests.
+//!
+//! Filters are sized with `u32`, to index more keys, or to keep allocations
+//! small, partition them into independently built shards using [ShardedFilter].
+//! For key sets larger than memory, [ExternalBuilder] spills digests to disk and
+//! builds one shard at a time, within a memory budget.
+//!
+//! When every key to be queried is known in advance, [FilterCascade] answers
+//! membership without false positives, using a cascade of [fuse8::Fuse8] filters over the
+//! included and excluded keys.
+//!
+//! All the filters above are immutable once built. [GrowableFilter] accepts new keys
+//! at any time, sealing them into a chain of [FusePacked] filters while keeping the
+//! combined false positive rate bounded. To remove keys from an immutable filter
+//! until its next rebuild, wrap it with [Tombstoned]. For keys that shall be
+//! forgotten after a time window, use [ExpiringFilter], a ring of [fuse8::Fuse8] filters,
+//! one per time bucket.
+//!
+//! The same peeling algorithm can store small values instead of fingerprints,
+//! refer to module [map] for static key to value retrieval, optionally verified
+//! with fingerprints to reject keys that are not part of the map.
+//! [Mphf] reuses the peeling algorithm to map each key in a static set to a unique
+//! index, a minimal perfect hash function.
+//!
+//! To tune a build pipeline, the `build_with_stats` family of methods, on filter
+//! types and their builders, return [BuildStats] along with the built filter.
+//!
+//! Builds are deterministic, seeded with a fixed `rng_seed` by default. The
+//! `rng_seed` drives the random number generator that draws the seeds tried while
+//! peeling, so that builds from the same keys, hasher and `rng_seed` produce
+//! identical filters across machines. The fixed default is public knowledge though,
+//! call `set_random_seed()` on builders to defend against keys crafted to fail the
+//! build under it, and record the returned seed to reproduce the build.
+//!
 //! Provides hasher types:
 //!
 //! All filter-types are parametrised over user supplied hasher-type.
@@ -21,12 +80,15 @@
 //!
 //! **Handling duplicates**
 //!
-//! * [Fuse16] and [Xor8] implementation uses BTreeMap to make sure all the digests
-//!   generated from keys are unique, this avoids duplicates but decreases the build
-//!   performance significantly.
-//! * [Fuse8] implementation computes duplicates on the fly leading to significantly
-//!   better build performance. On the other hand, Fuse8 cannot handle more than few
-//!   duplicates.
+//! * [Xor8] implementation uses BTreeMap to make sure all the digests generated from keys
+//!   are unique, this avoids duplicates but decreases the build performance
+//!   significantly.
+//! * [BinaryFuse] filters, [Fuse8] and [Fuse16], and their builders sort and de-duplicate
+//!   the digests populated via `insert()`, `populate()` and `populate_keys()`. Digests
+//!   supplied directly to `build_keys()` are checked for duplicates on the fly, which
+//!   leads to significantly better build performance, and when duplicates fail the build,
+//!   a sorted and de-duplicated copy of digests is built instead. Either way, `len()`
+//!   reports the number of unique keys built.
 //!
 //! **Cloning**
 //!
@@ -38,11 +100,14 @@
 //! **Features**
 //!
 //! * Enable ``cbordata`` feature for serialize and deserialize [Xor8] [Fuse8] [Fuse16]
-//!   types using CBOR spec.
+//!   [fuse32::Fuse32] types using CBOR spec.
+//! * Enable ``parallel`` feature to build shards of a [ShardedFilter] in parallel, and to
+//!   sort digests in parallel while building [BinaryFuse] filters, using rayon. Filters
+//!   are identical to the ones built serially.
 //!
 //! * [Xor8] from <https://github.com/FastFilter/xorfilter>, written in golang.
-//! * [Fuse8] and [Fuse16] from <https://github.com/FastFilter/xor_singleheader>  written
-//!   in C.
+//! * [Fuse8], [Fuse16] and [fuse32::Fuse32] from <https://github.com/FastFilter/xor_singleheader>
+//!   written in C.
 
 #[allow(unused_imports)]
 use std::collections::hash_map::DefaultHasher;
@@ -96,10 +161,36 @@ macro_rules! err_at {
 
 /// Error variants that are returned by this package's API.
 ///
-/// Each variant carries a prefix, typically identifying the
-/// error location.
+/// Variants with a message carry a prefix, typically identifying the
+/// error location. Build failures carry diagnostics instead, so that callers
+/// can tell them apart.
+///
+/// Building from an empty set of keys is not an error. Such filters are valid, report
+/// a `len()` of zero and answer lookups with the same false positive rate as any other
+/// filter, so that key sets that turn out empty need no special casing by callers.
 pub enum Error {
     Fatal(String, String),
+    /// Filter could not be built after trying `attempts` number of seeds. Build
+    /// fails when there are duplicate keys, `duplicates` is the number of duplicate
+    /// digests detected while building.
+    TooManyIterations {
+        attempts: usize,
+        duplicates: usize,
+    },
+    /// Number of `keys` exceed the `capacity` of the filter.
+    CapacityExceeded {
+        keys: usize,
+        capacity: usize,
+    },
+    /// Same key, as `digest`, was supplied more than once with different values,
+    /// while building a map.
+    ConflictingValues {
+        digest: u64,
+    },
+    /// Serialized filter is malformed.
+    InvalidFormat(String, String),
+    /// Reading or writing a serialized filter failed.
+    IOError(String, String),
 }
 
 impl fmt::Display for Error {
@@ -108,6 +199,22 @@ impl fmt::Display for Error {
 
         match self {
             Fatal(p, msg) => write!(f, "{} Fatal: {}", p, msg),
+            TooManyIterations {
+                attempts,
+                duplicates,
+            } => write!(
+                f,
+                "TooManyIterations: attempts:{} duplicates:{}",
+                attempts, duplicates
+            ),
+            CapacityExceeded { keys, capacity } => {
+                write!(f, "CapacityExceeded: keys:{} capacity:{}", keys, capacity)
+            }
+            ConflictingValues { digest } => {
+                write!(f, "ConflictingValues: digest:{:#x}", digest)
+            }
+            InvalidFormat(p, msg) => write!(f, "{} InvalidFormat: {}", p, msg),
+            IOError(p, msg) => write!(f, "{} IOError: {}", p, msg),
         }
     }
 }
@@ -123,15 +230,71 @@ impl error::Error for Error {}
 /// Type alias for Result return type, used by this package.
 pub type Result<T> = result::Result<T, Error>;
 
-mod fuse16;
-mod fuse8;
+mod cascade;
+mod expiring;
+mod external;
+mod filter;
+mod fuse;
+mod fuse16_old;
+mod fuse8_old;
+mod fuse_packed;
+mod growable;
 mod hasher;
+mod mphf;
+mod packed;
+mod sharded;
+mod stats;
+mod tombstone;
 mod xor8_old;
 
+pub mod fuse16;
+pub mod fuse32;
+pub mod fuse8;
+pub mod map;
+pub mod xor16;
+pub mod xor32;
 pub mod xor8;
-pub use fuse16::Fuse16;
-pub use fuse8::Fuse8;
+pub mod xor8plus;
+pub mod xor_packed;
+pub use cascade::FilterCascade;
+pub use cascade::FilterCascadeBuilder;
+pub use expiring::ExpiringFilter;
+pub use external::ExternalBuilder;
+pub use external::BUILD_BYTES_PER_KEY;
+pub use filter::AnyFilter;
+pub use filter::Filter;
+pub use filter::FilterBuilder;
+pub use fuse::BinaryFuse;
+pub use fuse::BinaryFuseBuilder;
+pub use fuse::BinaryFuseFilter;
+pub use fuse::Fingerprint;
+#[deprecated(
+    since = "0.7.0",
+    note = "Use fuse16::Fuse16 and fuse16::Fuse16Builder types"
+)]
+pub use fuse16_old::Fuse16;
+#[deprecated(
+    since = "0.7.0",
+    note = "Use fuse8::Fuse8 and fuse8::Fuse8Builder types"
+)]
+pub use fuse8_old::Fuse8;
+pub use fuse_packed::FusePacked;
+pub use fuse_packed::FusePackedBuilder;
+pub use growable::Compacted;
+pub use growable::Compaction;
+pub use growable::CompactionPolicy;
+pub use growable::GrowableFilter;
 pub use hasher::BuildHasherDefault;
 pub use hasher::NoHash;
+pub use mphf::Mphf;
+pub use mphf::MphfBuilder;
+pub use packed::PackedArray;
+pub use sharded::ShardedFilter;
+pub use sharded::ShardedFilterBuilder;
+pub use sharded::MAX_SHARD_BITS;
+pub use stats::BuildStats;
+pub use tombstone::Tombstoned;
+pub use xor8::XorBuilder;
+pub use xor8::XorFilter;
 #[deprecated(since = "0.6.0", note = "Use xor8::Xor8 and xor8::Xor8Builder types")]
 pub use xor8_old::Xor8;
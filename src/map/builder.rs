@@ -0,0 +1,447 @@
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::fuse::binary_fuse_assign;
+use crate::fuse::binary_fuse_fingerprint;
+use crate::fuse::binary_fuse_mix_split;
+use crate::fuse::binary_fuse_peel;
+use crate::fuse::Peeled;
+use crate::fuse::Segments;
+use crate::fuse::FUSE_RNG_SEED;
+use crate::hasher::random_seed;
+use crate::map::FuseMap;
+use crate::map::MapValue;
+use crate::map::VerifiedMap;
+use crate::map::XorMap;
+use crate::packed::PackedArray;
+use crate::xor8::builder::xor_assign;
+use crate::xor8::builder::xor_block_length;
+use crate::xor8::builder::xor_peel;
+use crate::xor8::builder::XOR_MAX_KEYS;
+use crate::xor8::filter::mixsplit;
+use crate::BuildHasherDefault;
+use crate::BuildStats;
+use crate::Error;
+use crate::Result;
+
+/// Builds an [XorMap], a [FuseMap] or a [VerifiedMap], mapping keys to values `bits`
+/// wide.
+///
+/// Example:
+/// ```
+/// # use xorfilter::map::MapBuilder;
+///
+/// let mut b: MapBuilder<u8> = MapBuilder::new(3);
+///
+/// b.insert("foo", 5);
+/// b.insert("bar", 2);
+/// let map = b.build_fuse().unwrap();
+///
+/// assert_eq!(map.get("foo"), 5);
+/// assert_eq!(map.get("bar"), 2);
+/// ```
+#[derive(Clone, Debug)]
+pub struct MapBuilder<V, H = BuildHasherDefault>
+where
+    V: MapValue,
+    H: BuildHasher + Clone,
+{
+    entries: Vec<(u64, V)>,
+    bits: u32,
+    hash_time: Duration,
+    pub num_digests: usize,
+    pub hash_builder: H,
+    /// Seed for the random number generator drawing the peeling seeds.
+    pub rng_seed: u64,
+}
+
+impl<V, H> MapBuilder<V, H>
+where
+    V: MapValue,
+    H: BuildHasher + Clone,
+{
+    /// New map builder, for values `bits` wide, initialized with
+    /// [BuildHasherDefault].
+    ///
+    /// Panics if `bits` is not within 1 and 32.
+    pub fn new(bits: u32) -> Self
+    where H: Default {
+        Self::with_hasher(bits, H::default())
+    }
+
+    /// New map builder, for values `bits` wide, initialized with supplied `hasher`.
+    ///
+    /// Panics if `bits` is not within 1 and 32.
+    pub fn with_hasher(bits: u32, hash_builder: H) -> Self {
+        assert!((1..=32).contains(&bits), "bits:{} not within 1..=32", bits);
+
+        Self {
+            entries: Vec::default(),
+            bits,
+            hash_time: Duration::default(),
+            num_digests: 0,
+            hash_builder,
+            rng_seed: FUSE_RNG_SEED,
+        }
+    }
+
+    /// Seed the builds with a random `rng_seed`, and return the same.
+    pub fn set_random_seed(&mut self) -> u64 {
+        self.rng_seed = random_seed();
+        self.rng_seed
+    }
+
+    /// Return the width of values, in bits.
+    pub fn bits(&self) -> u32 {
+        self.bits
+    }
+
+    pub fn get_hasher(&self) -> H::Hasher {
+        self.hash_builder.build_hasher()
+    }
+
+    // entries populated so far, duplicates included.
+    pub(crate) fn entries(&self) -> &[(u64, V)] {
+        &self.entries
+    }
+
+    /// Calculate hash of a key.
+    #[inline]
+    pub fn hash<K: Hash + ?Sized>(&self, key: &K) -> u64 {
+        let mut hasher = self.get_hasher();
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Insert 64-bit digest of a single key, along with its value. Inserting the
+    /// same key again, with a different value, shall fail the build with
+    /// [Error::ConflictingValues], like the `*_from_digests` methods.
+    ///
+    /// Digest for the key shall be generated using the default-hasher or via hasher
+    /// supplied via [MapBuilder::with_hasher] method.
+    pub fn insert<K: ?Sized + Hash>(&mut self, key: &K, value: V) {
+        let digest = self.hash(key);
+
+        self.entries.push((digest, value));
+        self.num_digests += 1;
+    }
+
+    /// Populate with 64-bit digests for a collection of keys, owned or borrowed,
+    /// along with their values.
+    ///
+    /// Digest for key shall be generated using the default-hasher or via hasher supplied
+    /// via [MapBuilder::with_hasher] method.
+    pub fn populate<K, I>(&mut self, entries: I)
+    where
+        K: Hash,
+        I: IntoIterator<Item = (K, V)>,
+    {
+        let (mut n, start) = (0, Instant::now());
+
+        for (key, value) in entries.into_iter() {
+            n += 1;
+
+            let digest = self.hash(&key);
+            self.entries.push((digest, value));
+        }
+
+        self.num_digests += n;
+        self.hash_time += start.elapsed();
+    }
+
+    /// Populate with pre-compute collection of 64-bit digests, along with their values.
+    pub fn populate_digests<I: IntoIterator<Item = (u64, V)>>(&mut self, entries: I) {
+        let mut n = 0;
+
+        for (digest, value) in entries.into_iter() {
+            n += 1;
+            self.entries.push((digest, value));
+        }
+
+        self.num_digests += n;
+    }
+
+    /// Build [XorMap] for keys that where previously inserted using
+    /// [MapBuilder::insert], [MapBuilder::populate] and [MapBuilder::populate_digests]
+    /// method.
+    pub fn build_xor(&mut self) -> Result<XorMap<V, H>> {
+        self.build_xor_with_stats().map(|(map, _)| map)
+    }
+
+    /// Same as [MapBuilder::build_xor], and return statistics on the build.
+    pub fn build_xor_with_stats(&mut self) -> Result<(XorMap<V, H>, BuildStats)> {
+        let entries = self.entries.clone();
+        let (map, stats) = self.build_xor_from_digests_with_stats(&entries)?;
+        Ok((map, self.populated_stats(stats)))
+    }
+
+    /// Build [FuseMap] for keys that where previously inserted using
+    /// [MapBuilder::insert], [MapBuilder::populate] and [MapBuilder::populate_digests]
+    /// method.
+    pub fn build_fuse(&mut self) -> Result<FuseMap<V, H>> {
+        self.build_fuse_with_stats().map(|(map, _)| map)
+    }
+
+    /// Same as [MapBuilder::build_fuse], and return statistics on the build.
+    pub fn build_fuse_with_stats(&mut self) -> Result<(FuseMap<V, H>, BuildStats)> {
+        let entries = self.entries.clone();
+        let (map, stats) = self.build_fuse_from_digests_with_stats(&entries)?;
+        Ok((map, self.populated_stats(stats)))
+    }
+
+    /// Build [XorMap] for pre-computed 64-bit digests for keys, along with their values.
+    ///
+    /// If keys where previously inserted using [MapBuilder::insert] or
+    /// [MapBuilder::populate] or [MapBuilder::populate_digests] methods, they shall be
+    /// ignored.
+    ///
+    /// Duplicate digests are tolerated as long as they carry the same value, otherwise
+    /// the build fails with [Error::ConflictingValues]. More than about `3.49e9`
+    /// unique digests fail the build with [Error::CapacityExceeded].
+    pub fn build_xor_from_digests(
+        &mut self,
+        entries: &[(u64, V)],
+    ) -> Result<XorMap<V, H>> {
+        self.build_xor_from_digests_with_stats(entries).map(|(map, _)| map)
+    }
+
+    /// Same as [MapBuilder::build_xor_from_digests], and return statistics on the
+    /// build.
+    pub fn build_xor_from_digests_with_stats(
+        &mut self,
+        entries: &[(u64, V)],
+    ) -> Result<(XorMap<V, H>, BuildStats)> {
+        let start = Instant::now();
+        let digests = self.check_entries(entries)?;
+        let dedup_time = start.elapsed();
+
+        if digests.len() > XOR_MAX_KEYS {
+            return Err(Error::CapacityExceeded {
+                keys: digests.len(),
+                capacity: XOR_MAX_KEYS,
+            });
+        }
+        let block_length = xor_block_length(digests.len());
+        let (mut rngcounter, mut stats) = (self.rng_seed, BuildStats::default());
+        let (seed, stack) =
+            xor_peel(&digests, block_length, false, &mut rngcounter, &mut stats)?;
+        stats.num_digests = entries.len();
+        stats.num_duplicates = entries.len() - digests.len();
+        stats.dedup_time += dedup_time;
+
+        let start = Instant::now();
+        let values: HashMap<u64, u64> =
+            entries.iter().map(|(d, v)| (mixsplit(*d, seed), v.into_bits())).collect();
+
+        let mut slots = PackedArray::new(self.bits, (block_length as usize) * 3);
+        xor_assign(&stack, block_length, &mut slots, |h| values[&h]);
+        stats.assign_time = start.elapsed();
+        stats.set_array(slots.len(), self.bits);
+
+        let map = XorMap {
+            hash_builder: self.hash_builder.clone(),
+            seed,
+            num_keys: Some(digests.len()),
+            block_length,
+            values: Arc::new(slots),
+            _value: PhantomData,
+        };
+
+        Ok((map, stats))
+    }
+
+    /// Build [FuseMap] for pre-computed 64-bit digests for keys, along with their
+    /// values.
+    ///
+    /// If keys where previously inserted using [MapBuilder::insert] or
+    /// [MapBuilder::populate] or [MapBuilder::populate_digests] methods, they shall be
+    /// ignored.
+    ///
+    /// Duplicate digests are tolerated as long as they carry the same value, otherwise
+    /// the build fails with [Error::ConflictingValues].
+    pub fn build_fuse_from_digests(
+        &mut self,
+        entries: &[(u64, V)],
+    ) -> Result<FuseMap<V, H>> {
+        self.build_fuse_from_digests_with_stats(entries).map(|(map, _)| map)
+    }
+
+    /// Same as [MapBuilder::build_fuse_from_digests], and return statistics on the
+    /// build.
+    pub fn build_fuse_from_digests_with_stats(
+        &mut self,
+        entries: &[(u64, V)],
+    ) -> Result<(FuseMap<V, H>, BuildStats)> {
+        let (segs, peeled, slots, stats) = self.fuse_slots(entries, self.bits, |_| 0)?;
+
+        let map = FuseMap {
+            hash_builder: self.hash_builder.clone(),
+            seed: peeled.seed,
+            num_keys: Some(peeled.len()),
+            segment_length: segs.segment_length,
+            segment_length_mask: segs.segment_length_mask,
+            segment_count: segs.segment_count,
+            segment_count_length: segs.segment_count_length,
+            values: Arc::new(slots),
+            _value: PhantomData,
+        };
+
+        Ok((map, stats))
+    }
+
+    /// Build [VerifiedMap], with `fingerprint_bits` wide fingerprints, for keys that
+    /// where previously inserted using [MapBuilder::insert], [MapBuilder::populate]
+    /// and [MapBuilder::populate_digests] method.
+    ///
+    /// Fails if fingerprint and value together are wider than 32 bits. With ZERO
+    /// `fingerprint_bits` the map returns a value for every key, like [FuseMap].
+    pub fn build_verified(&mut self, fingerprint_bits: u32) -> Result<VerifiedMap<V, H>> {
+        self.build_verified_with_stats(fingerprint_bits).map(|(map, _)| map)
+    }
+
+    /// Same as [MapBuilder::build_verified], and return statistics on the build.
+    pub fn build_verified_with_stats(
+        &mut self,
+        fingerprint_bits: u32,
+    ) -> Result<(VerifiedMap<V, H>, BuildStats)> {
+        let entries = self.entries.clone();
+        let (map, stats) =
+            self.build_verified_from_digests_with_stats(&entries, fingerprint_bits)?;
+        Ok((map, self.populated_stats(stats)))
+    }
+
+    /// Build [VerifiedMap], with `fingerprint_bits` wide fingerprints, for
+    /// pre-computed 64-bit digests for keys, along with their values.
+    ///
+    /// If keys where previously inserted using [MapBuilder::insert] or
+    /// [MapBuilder::populate] or [MapBuilder::populate_digests] methods, they shall be
+    /// ignored.
+    ///
+    /// Duplicate digests are tolerated as long as they carry the same value, otherwise
+    /// the build fails with [Error::ConflictingValues].
+    pub fn build_verified_from_digests(
+        &mut self,
+        entries: &[(u64, V)],
+        fingerprint_bits: u32,
+    ) -> Result<VerifiedMap<V, H>> {
+        self.build_verified_from_digests_with_stats(entries, fingerprint_bits)
+            .map(|(map, _)| map)
+    }
+
+    /// Same as [MapBuilder::build_verified_from_digests], and return statistics on
+    /// the build.
+    pub fn build_verified_from_digests_with_stats(
+        &mut self,
+        entries: &[(u64, V)],
+        fingerprint_bits: u32,
+    ) -> Result<(VerifiedMap<V, H>, BuildStats)> {
+        let slot_bits = fingerprint_bits + self.bits;
+        if slot_bits > 32 {
+            err_at!(
+                Fatal,
+                msg: "fingerprint_bits:{} + bits:{} wider than 32",
+                fingerprint_bits,
+                self.bits
+            )?;
+        }
+
+        let (value_bits, mask) = (self.bits, (1_u64 << fingerprint_bits) - 1);
+        let tag = |h| (binary_fuse_fingerprint(h) & mask) << value_bits;
+        let (segs, peeled, slots, stats) = self.fuse_slots(entries, slot_bits, tag)?;
+
+        let map = VerifiedMap {
+            hash_builder: self.hash_builder.clone(),
+            seed: peeled.seed,
+            num_keys: Some(peeled.len()),
+            segment_length: segs.segment_length,
+            segment_length_mask: segs.segment_length_mask,
+            segment_count: segs.segment_count,
+            segment_count_length: segs.segment_count_length,
+            value_bits,
+            values: Arc::new(slots),
+            _value: PhantomData,
+        };
+
+        Ok((map, stats))
+    }
+
+    // account for keys populated into the builder, on statistics of a build from
+    // its entries.
+    fn populated_stats(&self, mut stats: BuildStats) -> BuildStats {
+        stats.num_digests = self.num_digests;
+        stats.num_duplicates = self.num_digests - stats.num_unique;
+        stats.hash_time = self.hash_time;
+        stats
+    }
+
+    // make sure that all values fit within the configured width, and that
+    // duplicate digests carry the same value, and return the unique digests.
+    fn check_entries(&self, entries: &[(u64, V)]) -> Result<Vec<u64>> {
+        let mut values: HashMap<u64, u64> = HashMap::with_capacity(entries.len());
+        let mut digests = Vec::with_capacity(entries.len());
+        for (digest, value) in entries.iter() {
+            let bits = value.into_bits();
+            if (bits >> self.bits) > 0 {
+                err_at!(Fatal, msg: "value {} does not fit in {} bits", bits, self.bits)?;
+            }
+            match values.insert(*digest, bits) {
+                Some(old) if old != bits => {
+                    return Err(Error::ConflictingValues { digest: *digest });
+                }
+                Some(_) => (),
+                None => digests.push(*digest),
+            }
+        }
+
+        Ok(digests)
+    }
+
+    // peel entries over binary fuse segments, and assign `slot_bits` wide slots such
+    // that xor of a key's slots yield its value or-ed with `tag(hash)`.
+    fn fuse_slots<T>(
+        &self,
+        entries: &[(u64, V)],
+        slot_bits: u32,
+        tag: T,
+    ) -> Result<(Segments, Peeled, PackedArray, BuildStats)>
+    where
+        T: Fn(u64) -> u64,
+    {
+        let start = Instant::now();
+        let digests = self.check_entries(entries)?;
+        let dedup_time = start.elapsed();
+
+        if digests.len() > (u32::MAX as usize) {
+            return Err(Error::CapacityExceeded {
+                keys: digests.len(),
+                capacity: u32::MAX as usize,
+            });
+        }
+        let segs = Segments::new(digests.len() as u32);
+        let mut stats = BuildStats::default();
+        let mut rng_counter = self.rng_seed;
+        let peeled = binary_fuse_peel(&segs, &digests, &mut rng_counter, &mut stats)?;
+        stats.num_digests = entries.len();
+        stats.num_duplicates = entries.len() - digests.len();
+        stats.dedup_time += dedup_time;
+
+        let start = Instant::now();
+        let values: HashMap<u64, u64> = entries
+            .iter()
+            .map(|(d, v)| (binary_fuse_mix_split(*d, peeled.seed), v.into_bits()))
+            .collect();
+
+        let mut slots = PackedArray::new(slot_bits, segs.array_length as usize);
+        binary_fuse_assign(&segs, &peeled, &mut slots, |h| tag(h) | values[&h]);
+        stats.assign_time = start.elapsed();
+        stats.set_array(slots.len(), slot_bits);
+
+        Ok((segs, peeled, slots, stats))
+    }
+}
@@ -0,0 +1,330 @@
+#[allow(unused_imports)]
+use std::collections::hash_map::DefaultHasher;
+#[allow(unused_imports)]
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::hash::Hash;
+
+#[cfg(feature = "cbordata")]
+use cbordata::Cbor;
+#[cfg(feature = "cbordata")]
+use cbordata::Cborize;
+#[cfg(feature = "cbordata")]
+use cbordata::FromCbor;
+#[cfg(feature = "cbordata")]
+use cbordata::IntoCbor;
+#[cfg(feature = "cbordata")]
+use cbordata::{self as cbor};
+
+use crate::fuse::FUSE_RNG_SEED;
+use crate::hasher::random_seed;
+use crate::map::MapBuilder;
+use crate::map::VerifiedMap;
+use crate::BuildHasherDefault;
+use crate::BuildStats;
+use crate::Error;
+use crate::Result;
+
+/// Identifies the class, or set, a key belongs to.
+pub type ClassId = u32;
+
+/// Builds a [Classifier] from `(key, class)` pairs.
+///
+/// Example:
+/// ```
+/// # use xorfilter::map::ClassifierBuilder;
+///
+/// let mut b: ClassifierBuilder = ClassifierBuilder::new(3, 8);
+///
+/// b.insert("foo", 0);
+/// b.insert("bar", 2);
+/// let classifier = b.build().unwrap();
+///
+/// assert_eq!(classifier.classify("foo"), Some(0));
+/// assert_eq!(classifier.classify("bar"), Some(2));
+/// ```
+#[derive(Clone, Debug)]
+pub struct ClassifierBuilder<H = BuildHasherDefault>
+where H: BuildHasher + Clone
+{
+    builder: MapBuilder<ClassId, H>,
+    num_classes: u32,
+    fingerprint_bits: u32,
+    /// Seed for the random number generator drawing the peeling seeds.
+    pub rng_seed: u64,
+}
+
+impl<H> ClassifierBuilder<H>
+where H: BuildHasher + Clone
+{
+    /// New classifier builder for classes `0..num_classes`, with `fingerprint_bits`
+    /// wide fingerprints, initialized with [BuildHasherDefault].
+    ///
+    /// Panics if `num_classes` is ZERO.
+    pub fn new(num_classes: u32, fingerprint_bits: u32) -> Self
+    where H: Default {
+        Self::with_hasher(num_classes, fingerprint_bits, H::default())
+    }
+
+    /// New classifier builder for classes `0..num_classes`, with `fingerprint_bits`
+    /// wide fingerprints, initialized with supplied `hasher`.
+    ///
+    /// Panics if `num_classes` is ZERO.
+    pub fn with_hasher(num_classes: u32, fingerprint_bits: u32, hash_builder: H) -> Self {
+        assert!(num_classes > 0, "num_classes is ZERO");
+
+        let bits = std::cmp::max(32 - (num_classes - 1).leading_zeros(), 1);
+        Self {
+            builder: MapBuilder::with_hasher(bits, hash_builder),
+            num_classes,
+            fingerprint_bits,
+            rng_seed: FUSE_RNG_SEED,
+        }
+    }
+
+    /// Seed the builds with a random `rng_seed`, and return the same.
+    pub fn set_random_seed(&mut self) -> u64 {
+        self.rng_seed = random_seed();
+        self.rng_seed
+    }
+
+    /// Return the width of class ids, in bits.
+    pub fn bits(&self) -> u32 {
+        self.builder.bits()
+    }
+
+    pub fn get_hasher(&self) -> H::Hasher {
+        self.builder.get_hasher()
+    }
+
+    /// Calculate hash of a key.
+    #[inline]
+    pub fn hash<K: Hash + ?Sized>(&self, key: &K) -> u64 {
+        self.builder.hash(key)
+    }
+
+    /// Insert 64-bit digest of a single key, along with its class. Inserting the
+    /// same key again, with a different class, shall fail the build with
+    /// [Error::ConflictingValues].
+    ///
+    /// Digest for the key shall be generated using the default-hasher or via hasher
+    /// supplied via [ClassifierBuilder::with_hasher] method.
+    pub fn insert<K: ?Sized + Hash>(&mut self, key: &K, class: ClassId) {
+        self.builder.insert(key, class)
+    }
+
+    /// Populate with 64-bit digests for a collection of keys, owned or borrowed,
+    /// along with their classes.
+    ///
+    /// Digest for key shall be generated using the default-hasher or via hasher supplied
+    /// via [ClassifierBuilder::with_hasher] method.
+    pub fn populate<K, I>(&mut self, entries: I)
+    where
+        K: Hash,
+        I: IntoIterator<Item = (K, ClassId)>,
+    {
+        self.builder.populate(entries)
+    }
+
+    /// Populate with pre-compute collection of 64-bit digests, along with their
+    /// classes.
+    pub fn populate_digests<I>(&mut self, entries: I)
+    where I: IntoIterator<Item = (u64, ClassId)> {
+        self.builder.populate_digests(entries)
+    }
+
+    /// Build [Classifier] for keys that where previously inserted using
+    /// [ClassifierBuilder::insert], [ClassifierBuilder::populate] and
+    /// [ClassifierBuilder::populate_digests] method.
+    ///
+    /// Fails if a class is not within `0..num_classes`, or if fingerprint and class
+    /// together are wider than 32 bits.
+    pub fn build(&mut self) -> Result<Classifier<H>> {
+        self.build_with_stats().map(|(classifier, _)| classifier)
+    }
+
+    /// Same as [ClassifierBuilder::build], and return statistics on the build.
+    pub fn build_with_stats(&mut self) -> Result<(Classifier<H>, BuildStats)> {
+        self.check_classes(self.builder.entries())?;
+
+        self.builder.rng_seed = self.rng_seed;
+        let (map, stats) =
+            self.builder.build_verified_with_stats(self.fingerprint_bits)?;
+        let classifier = Classifier {
+            num_classes: self.num_classes,
+            map,
+        };
+        Ok((classifier, stats))
+    }
+
+    /// Build [Classifier] for pre-computed 64-bit digests for keys, along with their
+    /// classes.
+    ///
+    /// If keys where previously inserted using [ClassifierBuilder::insert] or
+    /// [ClassifierBuilder::populate] or [ClassifierBuilder::populate_digests] methods,
+    /// they shall be ignored.
+    ///
+    /// Duplicate digests are tolerated as long as they carry the same class,
+    /// otherwise the build fails with [Error::ConflictingValues].
+    pub fn build_from_digests(
+        &mut self,
+        entries: &[(u64, ClassId)],
+    ) -> Result<Classifier<H>> {
+        self.build_from_digests_with_stats(entries).map(|(classifier, _)| classifier)
+    }
+
+    /// Same as [ClassifierBuilder::build_from_digests], and return statistics on the
+    /// build.
+    pub fn build_from_digests_with_stats(
+        &mut self,
+        entries: &[(u64, ClassId)],
+    ) -> Result<(Classifier<H>, BuildStats)> {
+        self.check_classes(entries)?;
+
+        self.builder.rng_seed = self.rng_seed;
+        let fingerprint_bits = self.fingerprint_bits;
+        let (map, stats) = self
+            .builder
+            .build_verified_from_digests_with_stats(entries, fingerprint_bits)?;
+        let classifier = Classifier {
+            num_classes: self.num_classes,
+            map,
+        };
+        Ok((classifier, stats))
+    }
+
+    fn check_classes(&self, entries: &[(u64, ClassId)]) -> Result<()> {
+        let max_class = entries.iter().map(|(_, c)| *c).max().unwrap_or(0);
+        if max_class >= self.num_classes {
+            err_at!(Fatal, msg: "class {} not within 0..{}", max_class, self.num_classes)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Type Classifier maps a set of keys to their classes, answering which of the
+/// `num_classes` sets a key belongs to, in a single lookup.
+///
+/// Classifier is a [VerifiedMap] storing the class of each key, replacing a lookup on
+/// one filter per class. Looking up a key that was not part of the input returns
+/// `None`, with a false positive rate of about `2^-fingerprint_bits`. With ZERO
+/// `fingerprint_bits` no membership test is done, and unknown keys are classified
+/// into an arbitrary class, unless the class falls outside `0..num_classes`.
+///
+/// If `RandomState` is used as BuildHasher, `std` has got this to say
+/// > _A particular instance RandomState will create the same instances
+/// > of Hasher, but the hashers created by two different RandomState
+/// > instances are unlikely to produce the same result for the same values._
+///
+/// If [DefaultHasher] is used as BuildHasher, `std` has got this to say,
+/// > _The internal algorithm is not specified, and so its hashes
+/// > should not be relied upon over releases._
+#[derive(Clone, Debug)]
+pub struct Classifier<H = BuildHasherDefault>
+where H: BuildHasher
+{
+    pub num_classes: u32,
+    pub map: VerifiedMap<ClassId, H>,
+}
+
+impl<H> Classifier<H>
+where H: BuildHasher
+{
+    #[allow(clippy::len_without_is_empty)]
+    /// Return the number of keys built into the classifier.
+    pub fn len(&self) -> Option<usize> {
+        self.map.len()
+    }
+
+    /// Return the size of index.
+    #[inline]
+    pub fn size_of(&self) -> usize {
+        std::mem::size_of::<u32>() + self.map.size_of()
+    }
+
+    /// Return the width of fingerprints, in bits.
+    #[inline]
+    pub fn fingerprint_bits(&self) -> u32 {
+        self.map.fingerprint_bits()
+    }
+
+    /// Return the class of key. If key was not part of the input, `None` is
+    /// returned, with false positive rate.
+    pub fn classify<K: ?Sized + Hash>(&self, key: &K) -> Option<ClassId> {
+        let digest = self.hash(key);
+        self.classify_digest(digest)
+    }
+
+    /// Return the class of key, as pre-computed digest form. If key was not part of
+    /// the input, `None` is returned, with false positive rate.
+    pub fn classify_digest(&self, digest: u64) -> Option<ClassId> {
+        self.map.get_digest(digest).filter(|class| *class < self.num_classes)
+    }
+
+    pub fn get_hasher(&self) -> H::Hasher {
+        self.map.get_hasher()
+    }
+
+    /// Calculate hash of a key.
+    #[inline]
+    pub fn hash<K: Hash + ?Sized>(&self, key: &K) -> u64 {
+        self.map.hash(key)
+    }
+}
+
+//------ Implement cbordata related functionalities
+
+// Intermediate type to serialize and de-serialized Classifier into bytes, the map is
+// nested as a single item list.
+#[cfg(feature = "cbordata")]
+#[derive(Cborize)]
+struct CborClassifier {
+    num_classes: u32,
+    map: Vec<Cbor>,
+}
+
+#[cfg(feature = "cbordata")]
+impl CborClassifier {
+    const ID: &'static str = "classifier/0.0.1";
+}
+
+#[cfg(feature = "cbordata")]
+impl<H> IntoCbor for Classifier<H>
+where H: BuildHasher + Into<Vec<u8>>
+{
+    fn into_cbor(self) -> cbor::Result<Cbor> {
+        let val = CborClassifier {
+            num_classes: self.num_classes,
+            map: vec![self.map.into_cbor()?],
+        };
+        val.into_cbor()
+    }
+}
+
+#[cfg(feature = "cbordata")]
+impl<H> FromCbor for Classifier<H>
+where H: BuildHasher + From<Vec<u8>>
+{
+    fn from_cbor(val: Cbor) -> cbor::Result<Self> {
+        let mut val = CborClassifier::from_cbor(val)?;
+
+        let map = match (val.map.pop(), val.map.is_empty()) {
+            (Some(map), true) => VerifiedMap::from_cbor(map)?,
+            _ => {
+                let msg = "expected a single map".to_string();
+                return Err(cbor::Error::FailConvert(
+                    format!("{}:{}", file!(), line!()),
+                    msg,
+                ));
+            }
+        };
+
+        let classifier = Classifier {
+            num_classes: val.num_classes,
+            map,
+        };
+
+        Ok(classifier)
+    }
+}
@@ -0,0 +1,206 @@
+#[allow(unused_imports)]
+use std::collections::hash_map::DefaultHasher;
+#[allow(unused_imports)]
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+#[cfg(feature = "cbordata")]
+use cbordata::Cbor;
+#[cfg(feature = "cbordata")]
+use cbordata::Cborize;
+#[cfg(feature = "cbordata")]
+use cbordata::FromCbor;
+#[cfg(feature = "cbordata")]
+use cbordata::IntoCbor;
+#[cfg(feature = "cbordata")]
+use cbordata::{self as cbor};
+
+use crate::fuse::binary_fuse_hash_batch;
+use crate::fuse::binary_fuse_mix_split;
+use crate::fuse::BinaryHashes;
+use crate::map::MapValue;
+use crate::packed::PackedArray;
+use crate::BuildHasherDefault;
+
+/// Type FuseMap is a static function mapping a set of keys to values, also known as
+/// Bloomier filter.
+///
+/// FuseMap uses the same construction as [crate::BinaryFuse], but instead of a
+/// fingerprint, xor-ing the three slots of a key yields its value. Values are `bits`
+/// wide and memory usage is about `1.13 * bits` bits per key for sizeable sets.
+/// Looking up a key that was not part of the input returns an arbitrary value.
+///
+/// FuseMap is parametrized over type `H` which is expected to implement [BuildHasher]
+/// trait, like types [RandomState] and [BuildHasherDefault]. When not supplied,
+/// [BuildHasherDefault] is used as the default hash-builder.
+///
+/// If `RandomState` is used as BuildHasher, `std` has got this to say
+/// > _A particular instance RandomState will create the same instances
+/// > of Hasher, but the hashers created by two different RandomState
+/// > instances are unlikely to produce the same result for the same values._
+///
+/// If [DefaultHasher] is used as BuildHasher, `std` has got this to say,
+/// > _The internal algorithm is not specified, and so its hashes
+/// > should not be relied upon over releases._
+#[derive(Clone, Debug)]
+pub struct FuseMap<V, H = BuildHasherDefault>
+where
+    V: MapValue,
+    H: BuildHasher,
+{
+    pub hash_builder: H,
+    pub seed: u64,
+    pub num_keys: Option<usize>,
+    pub segment_length: u32,
+    pub segment_length_mask: u32,
+    pub segment_count: u32,
+    pub segment_count_length: u32,
+    pub values: Arc<PackedArray>,
+    pub(crate) _value: PhantomData<V>,
+}
+
+impl<V, H> FuseMap<V, H>
+where
+    V: MapValue,
+    H: BuildHasher,
+{
+    #[allow(clippy::len_without_is_empty)]
+    /// Return the number of keys built into the map.
+    pub fn len(&self) -> Option<usize> {
+        self.num_keys
+    }
+
+    /// Return the size of index.
+    #[inline]
+    pub fn size_of(&self) -> usize {
+        std::mem::size_of::<Self>() + self.values.size_of()
+    }
+
+    /// Return the width of values, in bits.
+    #[inline]
+    pub fn bits(&self) -> u32 {
+        self.values.bits()
+    }
+
+    /// Return the value for key. If key was not part of the input, an arbitrary
+    /// value is returned.
+    pub fn get<K: ?Sized + Hash>(&self, key: &K) -> V {
+        let digest = self.hash(key);
+        self.get_digest(digest)
+    }
+
+    /// Return the value for key, as pre-computed digest form. If key was not part of
+    /// the input, an arbitrary value is returned.
+    pub fn get_digest(&self, digest: u64) -> V {
+        let hash = binary_fuse_mix_split(digest, self.seed);
+        let BinaryHashes { h0, h1, h2 } = binary_fuse_hash_batch(
+            hash,
+            self.segment_length,
+            self.segment_length_mask,
+            self.segment_count_length,
+        );
+        let vals = &self.values;
+        V::from_bits(
+            vals.get(h0 as usize) ^ vals.get(h1 as usize) ^ vals.get(h2 as usize),
+        )
+    }
+
+    pub fn get_hasher(&self) -> H::Hasher {
+        self.hash_builder.build_hasher()
+    }
+
+    /// Calculate hash of a key.
+    #[inline]
+    pub fn hash<K: Hash + ?Sized>(&self, key: &K) -> u64 {
+        let mut hasher = self.get_hasher();
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+//------ Implement cbordata related functionalities
+
+// Intermediate type to serialize and de-serialized FuseMap into bytes.
+#[cfg(feature = "cbordata")]
+#[derive(Cborize)]
+struct CborFuseMap {
+    hash_builder: Vec<u8>,
+    seed: u64,
+    num_keys: Option<usize>,
+    segment_length: u32,
+    segment_length_mask: u32,
+    segment_count: u32,
+    segment_count_length: u32,
+    bits: u32,
+    array_length: usize,
+    values: Vec<u64>,
+}
+
+#[cfg(feature = "cbordata")]
+impl CborFuseMap {
+    const ID: &'static str = "fusemap/0.0.1";
+}
+
+#[cfg(feature = "cbordata")]
+impl<V, H> IntoCbor for FuseMap<V, H>
+where
+    V: MapValue,
+    H: BuildHasher + Into<Vec<u8>>,
+{
+    fn into_cbor(self) -> cbor::Result<Cbor> {
+        let val = CborFuseMap {
+            hash_builder: self.hash_builder.into(),
+            seed: self.seed,
+            num_keys: self.num_keys,
+            segment_length: self.segment_length,
+            segment_length_mask: self.segment_length_mask,
+            segment_count: self.segment_count,
+            segment_count_length: self.segment_count_length,
+            bits: self.values.bits(),
+            array_length: self.values.len(),
+            values: self.values.as_words().to_vec(),
+        };
+        val.into_cbor()
+    }
+}
+
+#[cfg(feature = "cbordata")]
+impl<V, H> FromCbor for FuseMap<V, H>
+where
+    V: MapValue,
+    H: BuildHasher + From<Vec<u8>>,
+{
+    fn from_cbor(val: Cbor) -> cbor::Result<Self> {
+        let val = CborFuseMap::from_cbor(val)?;
+
+        let values = match PackedArray::from_words(val.bits, val.array_length, val.values)
+        {
+            Some(values) => values,
+            None => {
+                let msg = format!("invalid packed values, bits:{}", val.bits);
+                return Err(cbor::Error::FailConvert(
+                    format!("{}:{}", file!(), line!()),
+                    msg,
+                ));
+            }
+        };
+
+        let map = FuseMap {
+            hash_builder: val.hash_builder.into(),
+            seed: val.seed,
+            num_keys: val.num_keys,
+            segment_length: val.segment_length,
+            segment_length_mask: val.segment_length_mask,
+            segment_count: val.segment_count,
+            segment_count_length: val.segment_count_length,
+            values: Arc::new(values),
+            _value: PhantomData,
+        };
+
+        Ok(map)
+    }
+}
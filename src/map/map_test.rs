@@ -0,0 +1,458 @@
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+
+#[cfg(feature = "cbordata")]
+use cbordata::FromCbor;
+#[cfg(feature = "cbordata")]
+use cbordata::IntoCbor;
+use rand::prelude::random;
+use rand::rngs::StdRng;
+use rand::Rng;
+use rand::SeedableRng;
+
+use super::*;
+use crate::BuildHasherDefault;
+use crate::Error;
+
+fn generate_entries(rng: &mut StdRng, size: usize, bits: u32) -> Vec<(u64, u16)> {
+    let mask = (1_u64 << bits) - 1;
+    (0..size).map(|_| (rng.gen::<u64>(), (rng.gen::<u64>() & mask) as u16)).collect()
+}
+
+fn test_map_build<H>(name: &str, seed: u64, size: usize, bits: u32)
+where H: BuildHasher + Clone + Default {
+    println!("test_map_build<{}> size:{} bits:{}", name, size, bits);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let entries = generate_entries(&mut rng, size, bits);
+    let (entries1, entries2) = entries.split_at(size / 2);
+
+    let mut builder = MapBuilder::<u16, H>::new(bits);
+    // populate api
+    builder.populate(entries1.iter().map(|(k, v)| (k, *v)));
+    // populate_digests api
+    let digests: Vec<(u64, u16)> =
+        entries2.iter().map(|(k, v)| (builder.hash(k), *v)).collect();
+    builder.populate_digests(digests.iter().copied());
+    // insert api, duplicates with the same value are tolerated.
+    if let Some((key, value)) = entries.first() {
+        builder.insert(key, *value);
+    }
+
+    let xor_map = builder.build_xor().expect("failed build_xor");
+    let fuse_map = builder.build_fuse().expect("failed build_fuse");
+    assert_eq!(xor_map.len(), Some(size));
+    assert_eq!(fuse_map.len(), Some(size));
+
+    for (key, value) in entries.iter() {
+        assert_eq!(xor_map.get(key), *value, "key {}", key);
+        assert_eq!(fuse_map.get(key), *value, "key {}", key);
+    }
+    for (digest, value) in digests.iter() {
+        assert_eq!(xor_map.get_digest(*digest), *value, "digest {}", digest);
+        assert_eq!(fuse_map.get_digest(*digest), *value, "digest {}", digest);
+    }
+
+    let xbpv = ((xor_map.values.size_of() * 8) as f64) / (size as f64);
+    let fbpv = ((fuse_map.values.size_of() * 8) as f64) / (size as f64);
+    println!(
+        "test_map_build<{}> bits per entry xor:{} fuse:{}",
+        name, xbpv, fbpv
+    );
+    if size > 100_000 {
+        let limit = (bits as f64) * 1.25;
+        assert!(xbpv < limit, "xbpv({}) >= {}", xbpv, limit);
+        let limit = (bits as f64) * 1.15;
+        assert!(fbpv < limit, "fbpv({}) >= {}", fbpv, limit);
+    }
+}
+
+#[test]
+fn test_map() {
+    let mut seed: u64 = random();
+    println!("test_map seed:{}", seed);
+
+    for bits in [1, 4, 9, 16].iter() {
+        for size in [0, 1, 2, 10, 1000, 10_000, 100_000, 1_000_000].iter() {
+            seed = seed.wrapping_add(*size as u64);
+            test_map_build::<RandomState>("RandomState", seed, *size, *bits);
+            test_map_build::<BuildHasherDefault>(
+                "BuildHasherDefault",
+                seed,
+                *size,
+                *bits,
+            );
+        }
+    }
+}
+
+#[test]
+fn test_map_value_bits() {
+    let mut builder = MapBuilder::<u8, BuildHasherDefault>::new(3);
+    builder.insert(&10, 7);
+    assert!(builder.build_fuse().is_ok());
+
+    builder.insert(&20, 8);
+    assert!(builder.build_xor().is_err());
+    assert!(builder.build_fuse().is_err());
+
+    let map = {
+        let mut builder = MapBuilder::<u32, BuildHasherDefault>::new(32);
+        builder.insert("max", u32::MAX);
+        builder.build_fuse().unwrap()
+    };
+    assert_eq!(map.bits(), 32);
+    assert_eq!(map.get("max"), u32::MAX);
+}
+
+#[test]
+fn test_map_duplicates() {
+    let mut builder = MapBuilder::<u8, BuildHasherDefault>::new(3);
+    let entries = vec![(10, 1), (20, 2), (30, 3), (20, 2), (10, 1)];
+
+    let xor_map = builder.build_xor_from_digests(&entries).unwrap();
+    let fuse_map = builder.build_fuse_from_digests(&entries).unwrap();
+    assert_eq!(xor_map.len(), Some(3));
+    assert_eq!(fuse_map.len(), Some(3));
+    for (digest, value) in entries.iter() {
+        assert_eq!(xor_map.get_digest(*digest), *value, "digest {}", digest);
+        assert_eq!(fuse_map.get_digest(*digest), *value, "digest {}", digest);
+    }
+
+    let verified_map = builder.build_verified_from_digests(&entries, 8).unwrap();
+    assert_eq!(verified_map.len(), Some(3));
+    for (digest, value) in entries.iter() {
+        assert_eq!(
+            verified_map.get_digest(*digest),
+            Some(*value),
+            "digest {}",
+            digest
+        );
+    }
+
+    let entries = vec![(10, 1), (20, 2), (10, 3)];
+    match builder.build_xor_from_digests(&entries) {
+        Err(Error::ConflictingValues { digest: 10 }) => (),
+        res => panic!("unexpected {:?}", res.map(|m| m.len())),
+    }
+    match builder.build_fuse_from_digests(&entries) {
+        Err(Error::ConflictingValues { digest: 10 }) => (),
+        res => panic!("unexpected {:?}", res.map(|m| m.len())),
+    }
+    match builder.build_verified_from_digests(&entries, 8) {
+        Err(Error::ConflictingValues { digest: 10 }) => (),
+        res => panic!("unexpected {:?}", res.map(|m| m.len())),
+    }
+
+    // populated entries agree with the `*_from_digests` methods.
+    builder.populate_digests(vec![(10, 1), (20, 2), (10, 1)]);
+    assert_eq!(builder.build_fuse().unwrap().len(), Some(2));
+    builder.populate_digests(vec![(10, 3)]);
+    match builder.build_xor() {
+        Err(Error::ConflictingValues { digest: 10 }) => (),
+        res => panic!("unexpected {:?}", res.map(|m| m.len())),
+    }
+    match builder.build_fuse() {
+        Err(Error::ConflictingValues { digest: 10 }) => (),
+        res => panic!("unexpected {:?}", res.map(|m| m.len())),
+    }
+    match builder.build_verified(8) {
+        Err(Error::ConflictingValues { digest: 10 }) => (),
+        res => panic!("unexpected {:?}", res.map(|m| m.len())),
+    }
+}
+
+#[test]
+fn test_map_build_stats() {
+    let seed: u64 = random();
+    println!("test_map_build_stats seed:{}", seed);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let entries = generate_entries(&mut rng, 100_000, 8);
+
+    let mut builder = MapBuilder::<u16, BuildHasherDefault>::new(8);
+    builder.populate_digests(entries.iter().copied());
+    builder.populate_digests(entries[..1000].iter().copied());
+
+    let (xor_map, stats) = builder.build_xor_with_stats().expect("failed build");
+    println!("test_map_build_stats xor {}", stats);
+    assert_eq!(stats.num_digests, entries.len() + 1000);
+    assert_eq!(stats.num_unique, entries.len());
+    assert_eq!(stats.num_duplicates, 1000);
+    assert_eq!(stats.seed, xor_map.seed);
+    assert_eq!(stats.array_length, xor_map.values.len());
+
+    let (fuse_map, stats) = builder.build_fuse_with_stats().expect("failed build");
+    println!("test_map_build_stats fuse {}", stats);
+    assert_eq!(stats.num_unique, entries.len());
+    assert_eq!(stats.seed, fuse_map.seed);
+    assert!(stats.bits_per_key > 8.0 && stats.bits_per_key < 10.0);
+
+    let (verified_map, stats) =
+        builder.build_verified_with_stats(8).expect("failed build");
+    assert_eq!(stats.seed, verified_map.seed);
+    assert!(stats.bits_per_key > 16.0 && stats.bits_per_key < 20.0);
+
+    // duplicates are accounted on builds from digests.
+    let digests: Vec<(u64, u16)> =
+        entries.iter().chain(entries[..10].iter()).copied().collect();
+    let (_, stats) = builder.build_fuse_from_digests_with_stats(&digests).unwrap();
+    assert_eq!(stats.num_digests, digests.len());
+    assert_eq!(stats.num_unique, entries.len());
+    assert_eq!(stats.num_duplicates, 10);
+
+    let mut builder = ClassifierBuilder::<BuildHasherDefault>::new(4, 8);
+    let entries: Vec<(u64, ClassId)> =
+        entries.iter().map(|(d, v)| (*d, (*v % 4) as u32)).collect();
+    builder.populate_digests(entries.iter().copied());
+    let (classifier, stats) = builder.build_with_stats().expect("failed build");
+    assert_eq!(stats.num_unique, entries.len());
+    assert_eq!(stats.seed, classifier.map.seed);
+}
+
+#[test]
+fn test_map_rng_seed() {
+    let seed: u64 = random();
+    println!("test_map_rng_seed seed:{}", seed);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let entries = generate_entries(&mut rng, 10_000, 8);
+
+    let mut builder = MapBuilder::<u16, BuildHasherDefault>::new(8);
+    builder.rng_seed = seed;
+    let (xor_map, fuse_map) = (
+        builder.build_xor_from_digests(&entries).unwrap(),
+        builder.build_fuse_from_digests(&entries).unwrap(),
+    );
+    assert_eq!(
+        xor_map.seed,
+        builder.build_xor_from_digests(&entries).unwrap().seed
+    );
+    assert_eq!(
+        fuse_map.seed,
+        builder.build_fuse_from_digests(&entries).unwrap().seed
+    );
+
+    builder.rng_seed = seed.wrapping_add(1);
+    assert_ne!(
+        xor_map.seed,
+        builder.build_xor_from_digests(&entries).unwrap().seed
+    );
+    assert_ne!(
+        fuse_map.seed,
+        builder.build_fuse_from_digests(&entries).unwrap().seed
+    );
+
+    let rng_seed = builder.set_random_seed();
+    assert_eq!(builder.rng_seed, rng_seed);
+    assert_ne!(builder.set_random_seed(), rng_seed);
+}
+
+fn test_verified_map_build<H>(name: &str, seed: u64, size: usize, fp_bits: u32)
+where H: BuildHasher + Clone + Default {
+    println!(
+        "test_verified_map_build<{}> size:{} fp_bits:{}",
+        name, size, fp_bits
+    );
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let entries = generate_entries(&mut rng, size, 12);
+
+    let mut builder = MapBuilder::<u16, H>::new(12);
+    builder.populate(entries.iter().map(|(k, v)| (k, *v)));
+    let map = builder.build_verified(fp_bits).expect("failed build_verified");
+    assert_eq!(map.len(), Some(size));
+    assert_eq!(map.bits(), 12);
+    assert_eq!(map.fingerprint_bits(), fp_bits);
+
+    for (key, value) in entries.iter() {
+        assert_eq!(map.get(key), Some(*value), "key {}", key);
+        assert!(map.contains(key), "key {}", key);
+    }
+
+    let (falsesize, mut matches) = (10_000_000, 0_f64);
+    for _ in 0..falsesize {
+        if map.get(&rng.gen::<u64>()).is_some() {
+            matches += 1_f64;
+        }
+    }
+    let fpp = matches / (falsesize as f64);
+    // about 150 false positives are expected at 16 bits, twice as many is beyond
+    // 10 standard deviations.
+    let limit = 2.0 / ((1_u64 << fp_bits) as f64);
+    println!(
+        "test_verified_map_build<{}> false positive rate {}",
+        name, fpp
+    );
+    assert!(fpp < limit, "fpp({}) >= {}", fpp, limit);
+
+    let bpv = ((map.values.size_of() * 8) as f64) / (size as f64);
+    println!("test_verified_map_build<{}> bits per entry {}", name, bpv);
+    if size > 100_000 {
+        let limit = ((fp_bits + 12) as f64) * 1.15;
+        assert!(bpv < limit, "bpv({}) >= {}", bpv, limit);
+    }
+}
+
+#[test]
+fn test_verified_map() {
+    let mut seed: u64 = random();
+    println!("test_verified_map seed:{}", seed);
+
+    for fp_bits in [8, 16].iter() {
+        for size in [100_000, 1_000_000].iter() {
+            seed = seed.wrapping_add(*size as u64);
+            test_verified_map_build::<RandomState>("RandomState", seed, *size, *fp_bits);
+            test_verified_map_build::<BuildHasherDefault>(
+                "BuildHasherDefault",
+                seed,
+                *size,
+                *fp_bits,
+            );
+        }
+    }
+
+    let mut builder = MapBuilder::<u8, BuildHasherDefault>::new(8);
+    assert!(builder.build_verified(25).is_err());
+    builder.insert("key", 200);
+    let map = builder.build_verified(24).unwrap();
+    assert_eq!(map.get("key"), Some(200));
+    // without fingerprints every key is a member.
+    let map = builder.build_verified(0).unwrap();
+    assert_eq!(map.fingerprint_bits(), 0);
+    assert_eq!(map.get("key"), Some(200));
+    assert!(map.get("other").is_some());
+}
+
+fn test_classifier_build(seed: u64, size: usize, num_classes: u32, fp_bits: u32) {
+    println!(
+        "test_classifier_build size:{} num_classes:{} fp_bits:{}",
+        size, num_classes, fp_bits
+    );
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let entries: Vec<(u64, ClassId)> =
+        (0..size).map(|_| (rng.gen::<u64>(), rng.gen::<u32>() % num_classes)).collect();
+    let (entries1, entries2) = entries.split_at(size / 2);
+
+    let mut builder = ClassifierBuilder::<BuildHasherDefault>::new(num_classes, fp_bits);
+    // populate api
+    builder.populate(entries1.iter().map(|(k, c)| (k, *c)));
+    // populate_digests api
+    let digests: Vec<(u64, ClassId)> =
+        entries2.iter().map(|(k, c)| (builder.hash(k), *c)).collect();
+    builder.populate_digests(digests.iter().copied());
+
+    let classifier = builder.build().expect("failed build");
+    assert_eq!(classifier.len(), Some(size));
+    assert_eq!(classifier.fingerprint_bits(), fp_bits);
+
+    for (key, class) in entries.iter() {
+        assert_eq!(classifier.classify(key), Some(*class), "key {}", key);
+    }
+    for (digest, class) in digests.iter() {
+        assert_eq!(classifier.classify_digest(*digest), Some(*class));
+    }
+
+    let (falsesize, mut matches) = (1_000_000, 0_f64);
+    for _ in 0..falsesize {
+        match classifier.classify(&rng.gen::<u64>()) {
+            Some(class) if class < num_classes => matches += 1_f64,
+            Some(class) => panic!("class {} >= {}", class, num_classes),
+            None => (),
+        }
+    }
+    let fpp = matches / (falsesize as f64);
+    println!("test_classifier_build false positive rate {}", fpp);
+    if fp_bits > 0 {
+        // allow for few more matches, when fpp is too small to be sampled accurately.
+        let limit = (2.0 / ((1_u64 << fp_bits) as f64)) + (10.0 / (falsesize as f64));
+        assert!(fpp < limit, "fpp({}) >= {}", fpp, limit);
+    }
+}
+
+#[test]
+fn test_classifier() {
+    let mut seed: u64 = random();
+    println!("test_classifier seed:{}", seed);
+
+    for num_classes in [1, 3, 16, 1000].iter() {
+        for fp_bits in [0, 8, 16].iter() {
+            seed = seed.wrapping_add(*num_classes as u64);
+            test_classifier_build(seed, 100_000, *num_classes, *fp_bits);
+        }
+    }
+
+    let mut builder = ClassifierBuilder::<BuildHasherDefault>::new(3, 8);
+    assert_eq!(builder.bits(), 2);
+    builder.insert("foo", 3);
+    assert!(builder.build().is_err());
+    assert!(builder.build_from_digests(&[(10, 2)]).is_ok());
+    assert!(builder.build_from_digests(&[(10, 3)]).is_err());
+
+    // classes are checked on the final entries, at build time.
+    let mut builder = ClassifierBuilder::<BuildHasherDefault>::new(3, 8);
+    builder.insert("foo", 2);
+    builder.insert("foo", 2);
+    assert_eq!(builder.build().unwrap().classify("foo"), Some(2));
+    builder.insert("foo", 1);
+    match builder.build() {
+        Err(Error::ConflictingValues { .. }) => (),
+        res => panic!("unexpected {:?}", res.map(|c| c.len())),
+    }
+
+    let mut builder = ClassifierBuilder::<BuildHasherDefault>::new(u32::MAX, 1);
+    assert_eq!(builder.bits(), 32);
+    assert!(builder.build().is_err());
+}
+
+#[cfg(feature = "cbordata")]
+#[test]
+fn test_map_cbor() {
+    let seed: u64 = random();
+    println!("test_map_cbor seed:{}", seed);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let entries = generate_entries(&mut rng, 100_000, 11);
+
+    let (xor_map, fuse_map, verified_map) = {
+        let mut builder = MapBuilder::<u16, BuildHasherDefault>::new(11);
+        builder.populate(entries.iter().map(|(k, v)| (k, *v)));
+        (
+            builder.build_xor().unwrap(),
+            builder.build_fuse().unwrap(),
+            builder.build_verified(8).unwrap(),
+        )
+    };
+
+    let xor_map = {
+        let val = xor_map.into_cbor().unwrap();
+        XorMap::<u16, BuildHasherDefault>::from_cbor(val).unwrap()
+    };
+    let fuse_map = {
+        let val = fuse_map.into_cbor().unwrap();
+        FuseMap::<u16, BuildHasherDefault>::from_cbor(val).unwrap()
+    };
+    let verified_map = {
+        let val = verified_map.into_cbor().unwrap();
+        VerifiedMap::<u16, BuildHasherDefault>::from_cbor(val).unwrap()
+    };
+    let classifier = {
+        let mut builder = ClassifierBuilder::<BuildHasherDefault>::new(2048, 8);
+        builder.populate(entries.iter().map(|(k, v)| (k, *v as ClassId)));
+        let val = builder.build().unwrap().into_cbor().unwrap();
+        Classifier::<BuildHasherDefault>::from_cbor(val).unwrap()
+    };
+
+    for (key, value) in entries.iter() {
+        assert_eq!(xor_map.get(key), *value, "key {}", key);
+        assert_eq!(fuse_map.get(key), *value, "key {}", key);
+        assert_eq!(verified_map.get(key), Some(*value), "key {}", key);
+        assert_eq!(
+            classifier.classify(key),
+            Some(*value as ClassId),
+            "key {}",
+            key
+        );
+    }
+}
@@ -0,0 +1,77 @@
+//! Static key to value retrieval, using the peeling algorithm of xor and binary fuse
+//! filters.
+//!
+//! Instead of fingerprints, [XorMap] and [FuseMap] store a small value, few bits
+//! wide, for each key. Looking up a key that was not part of the input returns an
+//! arbitrary value. [VerifiedMap] additionally stores a fingerprint for each key, and
+//! returns `None` for keys that were not part of the input, with false positive rate.
+//! All types are built using [MapBuilder].
+//!
+//! [Classifier] is a [VerifiedMap] from keys to the class, or set, they belong to,
+//! built using [ClassifierBuilder].
+
+mod builder;
+mod classifier;
+mod fuse_map;
+mod verified_map;
+mod xor_map;
+
+pub use builder::MapBuilder;
+pub use classifier::ClassId;
+pub use classifier::Classifier;
+pub use classifier::ClassifierBuilder;
+pub use fuse_map::FuseMap;
+pub use verified_map::VerifiedMap;
+pub use xor_map::XorMap;
+
+/// Value type that can be stored in [XorMap], [FuseMap] and [VerifiedMap].
+///
+/// Implemented for `u8`, `u16` and `u32`. Values are stored using the bit-width
+/// supplied to [MapBuilder], not the width of the type.
+pub trait MapValue: Copy {
+    /// Convert value into bits, right aligned.
+    fn into_bits(self) -> u64;
+
+    /// Convert right aligned `bits` into value.
+    fn from_bits(bits: u64) -> Self;
+}
+
+impl MapValue for u8 {
+    #[inline]
+    fn into_bits(self) -> u64 {
+        self as u64
+    }
+
+    #[inline]
+    fn from_bits(bits: u64) -> u8 {
+        bits as u8
+    }
+}
+
+impl MapValue for u16 {
+    #[inline]
+    fn into_bits(self) -> u64 {
+        self as u64
+    }
+
+    #[inline]
+    fn from_bits(bits: u64) -> u16 {
+        bits as u16
+    }
+}
+
+impl MapValue for u32 {
+    #[inline]
+    fn into_bits(self) -> u64 {
+        self as u64
+    }
+
+    #[inline]
+    fn from_bits(bits: u64) -> u32 {
+        bits as u32
+    }
+}
+
+#[cfg(test)]
+#[path = "map_test.rs"]
+mod map_test;
@@ -0,0 +1,258 @@
+#[allow(unused_imports)]
+use std::collections::hash_map::DefaultHasher;
+#[allow(unused_imports)]
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+#[cfg(feature = "cbordata")]
+use cbordata::Cbor;
+#[cfg(feature = "cbordata")]
+use cbordata::Cborize;
+#[cfg(feature = "cbordata")]
+use cbordata::FromCbor;
+#[cfg(feature = "cbordata")]
+use cbordata::IntoCbor;
+#[cfg(feature = "cbordata")]
+use cbordata::{self as cbor};
+
+use crate::fuse::binary_fuse_fingerprint;
+use crate::fuse::binary_fuse_hash_batch;
+use crate::fuse::binary_fuse_mix_split;
+use crate::fuse::BinaryHashes;
+use crate::map::MapValue;
+use crate::packed::PackedArray;
+use crate::BuildHasherDefault;
+use crate::Filter;
+
+/// Type VerifiedMap is a static function mapping a set of keys to values, along with
+/// membership test for the keys.
+///
+/// VerifiedMap is a [crate::map::FuseMap] whose slots hold a fingerprint along with
+/// the value, xor-ing the three slots of a key yields both. Looking up a key that was
+/// not part of the input returns `None`, with a false positive rate of about
+/// `2^-fingerprint_bits`, same as [crate::fuse8::Fuse8] for 8-bit and
+/// [crate::fuse16::Fuse16] for 16-bit fingerprints. Memory usage is about `1.13 *
+/// (fingerprint_bits + bits)` bits per key for sizeable sets.
+///
+/// VerifiedMap is parametrized over type `H` which is expected to implement [BuildHasher]
+/// trait, like types [RandomState] and [BuildHasherDefault]. When not supplied,
+/// [BuildHasherDefault] is used as the default hash-builder.
+///
+/// If `RandomState` is used as BuildHasher, `std` has got this to say
+/// > _A particular instance RandomState will create the same instances
+/// > of Hasher, but the hashers created by two different RandomState
+/// > instances are unlikely to produce the same result for the same values._
+///
+/// If [DefaultHasher] is used as BuildHasher, `std` has got this to say,
+/// > _The internal algorithm is not specified, and so its hashes
+/// > should not be relied upon over releases._
+#[derive(Clone, Debug)]
+pub struct VerifiedMap<V, H = BuildHasherDefault>
+where
+    V: MapValue,
+    H: BuildHasher,
+{
+    pub hash_builder: H,
+    pub seed: u64,
+    pub num_keys: Option<usize>,
+    pub segment_length: u32,
+    pub segment_length_mask: u32,
+    pub segment_count: u32,
+    pub segment_count_length: u32,
+    /// Width of values, lower bits of each slot, fingerprints use the remaining bits.
+    pub value_bits: u32,
+    pub values: Arc<PackedArray>,
+    pub(crate) _value: PhantomData<V>,
+}
+
+impl<V, H> VerifiedMap<V, H>
+where
+    V: MapValue,
+    H: BuildHasher,
+{
+    #[allow(clippy::len_without_is_empty)]
+    /// Return the number of keys built into the map.
+    pub fn len(&self) -> Option<usize> {
+        self.num_keys
+    }
+
+    /// Return the size of index.
+    #[inline]
+    pub fn size_of(&self) -> usize {
+        std::mem::size_of::<Self>() + self.values.size_of()
+    }
+
+    /// Return the width of values, in bits.
+    #[inline]
+    pub fn bits(&self) -> u32 {
+        self.value_bits
+    }
+
+    /// Return the width of fingerprints, in bits.
+    #[inline]
+    pub fn fingerprint_bits(&self) -> u32 {
+        self.values.bits() - self.value_bits
+    }
+
+    /// Return the value for key. If key was not part of the input, `None` is
+    /// returned, with false positive rate.
+    pub fn get<K: ?Sized + Hash>(&self, key: &K) -> Option<V> {
+        let digest = self.hash(key);
+        self.get_digest(digest)
+    }
+
+    /// Return the value for key, as pre-computed digest form. If key was not part of
+    /// the input, `None` is returned, with false positive rate.
+    pub fn get_digest(&self, digest: u64) -> Option<V> {
+        let hash = binary_fuse_mix_split(digest, self.seed);
+        let BinaryHashes { h0, h1, h2 } = binary_fuse_hash_batch(
+            hash,
+            self.segment_length,
+            self.segment_length_mask,
+            self.segment_count_length,
+        );
+        let vals = &self.values;
+        let x = vals.get(h0 as usize) ^ vals.get(h1 as usize) ^ vals.get(h2 as usize);
+        let f = binary_fuse_fingerprint(hash) & (vals.mask() >> self.value_bits);
+
+        match (x >> self.value_bits) == f {
+            true => Some(V::from_bits(x & ((1_u64 << self.value_bits) - 1))),
+            false => None,
+        }
+    }
+
+    /// Contains tell you whether the key is likely part of the map, with false
+    /// positive rate.
+    pub fn contains<K: ?Sized + Hash>(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Contains tell you whether the key, as pre-computed digest form, is likely
+    /// part of the map, with false positive rate.
+    pub fn contains_digest(&self, digest: u64) -> bool {
+        self.get_digest(digest).is_some()
+    }
+
+    pub fn get_hasher(&self) -> H::Hasher {
+        self.hash_builder.build_hasher()
+    }
+
+    /// Calculate hash of a key.
+    #[inline]
+    pub fn hash<K: Hash + ?Sized>(&self, key: &K) -> u64 {
+        let mut hasher = self.get_hasher();
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+impl<V, H> Filter for VerifiedMap<V, H>
+where
+    V: MapValue,
+    H: BuildHasher,
+{
+    fn contains_digest(&self, digest: u64) -> bool {
+        self.contains_digest(digest)
+    }
+
+    fn len(&self) -> Option<usize> {
+        self.num_keys
+    }
+
+    fn size_of(&self) -> usize {
+        self.size_of()
+    }
+}
+
+//------ Implement cbordata related functionalities
+
+// Intermediate type to serialize and de-serialized VerifiedMap into bytes.
+#[cfg(feature = "cbordata")]
+#[derive(Cborize)]
+struct CborVerifiedMap {
+    hash_builder: Vec<u8>,
+    seed: u64,
+    num_keys: Option<usize>,
+    segment_length: u32,
+    segment_length_mask: u32,
+    segment_count: u32,
+    segment_count_length: u32,
+    value_bits: u32,
+    bits: u32,
+    array_length: usize,
+    values: Vec<u64>,
+}
+
+#[cfg(feature = "cbordata")]
+impl CborVerifiedMap {
+    const ID: &'static str = "verifiedmap/0.0.1";
+}
+
+#[cfg(feature = "cbordata")]
+impl<V, H> IntoCbor for VerifiedMap<V, H>
+where
+    V: MapValue,
+    H: BuildHasher + Into<Vec<u8>>,
+{
+    fn into_cbor(self) -> cbor::Result<Cbor> {
+        let val = CborVerifiedMap {
+            hash_builder: self.hash_builder.into(),
+            seed: self.seed,
+            num_keys: self.num_keys,
+            segment_length: self.segment_length,
+            segment_length_mask: self.segment_length_mask,
+            segment_count: self.segment_count,
+            segment_count_length: self.segment_count_length,
+            value_bits: self.value_bits,
+            bits: self.values.bits(),
+            array_length: self.values.len(),
+            values: self.values.as_words().to_vec(),
+        };
+        val.into_cbor()
+    }
+}
+
+#[cfg(feature = "cbordata")]
+impl<V, H> FromCbor for VerifiedMap<V, H>
+where
+    V: MapValue,
+    H: BuildHasher + From<Vec<u8>>,
+{
+    fn from_cbor(val: Cbor) -> cbor::Result<Self> {
+        let val = CborVerifiedMap::from_cbor(val)?;
+
+        let values = match PackedArray::from_words(val.bits, val.array_length, val.values)
+        {
+            Some(values) if val.value_bits <= val.bits => values,
+            _ => {
+                let msg = format!(
+                    "invalid packed values, bits:{} value_bits:{}",
+                    val.bits, val.value_bits
+                );
+                return Err(cbor::Error::FailConvert(
+                    format!("{}:{}", file!(), line!()),
+                    msg,
+                ));
+            }
+        };
+
+        let map = VerifiedMap {
+            hash_builder: val.hash_builder.into(),
+            seed: val.seed,
+            num_keys: val.num_keys,
+            segment_length: val.segment_length,
+            segment_length_mask: val.segment_length_mask,
+            segment_count: val.segment_count,
+            segment_count_length: val.segment_count_length,
+            value_bits: val.value_bits,
+            values: Arc::new(values),
+            _value: PhantomData,
+        };
+
+        Ok(map)
+    }
+}
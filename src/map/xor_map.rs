@@ -0,0 +1,189 @@
+#[allow(unused_imports)]
+use std::collections::hash_map::DefaultHasher;
+#[allow(unused_imports)]
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+#[cfg(feature = "cbordata")]
+use cbordata::Cbor;
+#[cfg(feature = "cbordata")]
+use cbordata::Cborize;
+#[cfg(feature = "cbordata")]
+use cbordata::FromCbor;
+#[cfg(feature = "cbordata")]
+use cbordata::IntoCbor;
+#[cfg(feature = "cbordata")]
+use cbordata::{self as cbor};
+
+use crate::map::MapValue;
+use crate::packed::PackedArray;
+use crate::xor8::filter::mixsplit;
+use crate::xor8::filter::reduce;
+use crate::BuildHasherDefault;
+
+/// Type XorMap is a static function mapping a set of keys to values, also known as
+/// Bloomier filter.
+///
+/// XorMap uses the same construction as [crate::xor8::Xor8], but instead of a
+/// fingerprint, xor-ing the three slots of a key yields its value. Values are `bits`
+/// wide and memory usage is about `1.23 * bits` bits per key for sizeable sets.
+/// Looking up a key that was not part of the input returns an arbitrary value.
+///
+/// XorMap is parametrized over type `H` which is expected to implement [BuildHasher]
+/// trait, like types [RandomState] and [BuildHasherDefault]. When not supplied,
+/// [BuildHasherDefault] is used as the default hash-builder.
+///
+/// If `RandomState` is used as BuildHasher, `std` has got this to say
+/// > _A particular instance RandomState will create the same instances
+/// > of Hasher, but the hashers created by two different RandomState_
+/// > instances are unlikely to produce the same result for the same values._
+///
+/// If [DefaultHasher] is used as BuildHasher, `std` has got this to say,
+/// > _The internal algorithm is not specified, and so its hashes
+/// > should not be relied upon over releases._
+#[derive(Clone, Debug)]
+pub struct XorMap<V, H = BuildHasherDefault>
+where
+    V: MapValue,
+    H: BuildHasher,
+{
+    pub hash_builder: H,
+    pub seed: u64,
+    pub num_keys: Option<usize>,
+    pub block_length: u32,
+    pub values: Arc<PackedArray>,
+    pub(crate) _value: PhantomData<V>,
+}
+
+impl<V, H> XorMap<V, H>
+where
+    V: MapValue,
+    H: BuildHasher,
+{
+    #[allow(clippy::len_without_is_empty)]
+    /// Return the number of keys built into the map.
+    pub fn len(&self) -> Option<usize> {
+        self.num_keys
+    }
+
+    /// Return the size of index.
+    #[inline]
+    pub fn size_of(&self) -> usize {
+        std::mem::size_of::<Self>() + self.values.size_of()
+    }
+
+    /// Return the width of values, in bits.
+    #[inline]
+    pub fn bits(&self) -> u32 {
+        self.values.bits()
+    }
+
+    /// Return the value for key. If key was not part of the input, an arbitrary
+    /// value is returned.
+    pub fn get<K: ?Sized + Hash>(&self, key: &K) -> V {
+        let digest = self.hash(key);
+        self.get_digest(digest)
+    }
+
+    /// Return the value for key, as pre-computed digest form. If key was not part of
+    /// the input, an arbitrary value is returned.
+    pub fn get_digest(&self, digest: u64) -> V {
+        let hash = mixsplit(digest, self.seed);
+        let r0 = hash as u32;
+        let r1 = hash.rotate_left(21) as u32;
+        let r2 = hash.rotate_left(42) as u32;
+        let h0 = reduce(r0, self.block_length) as usize;
+        let h1 = (reduce(r1, self.block_length) + self.block_length) as usize;
+        let h2 = (reduce(r2, self.block_length) + 2 * self.block_length) as usize;
+        let vals = &self.values;
+        V::from_bits(vals.get(h0) ^ vals.get(h1) ^ vals.get(h2))
+    }
+
+    pub fn get_hasher(&self) -> H::Hasher {
+        self.hash_builder.build_hasher()
+    }
+
+    /// Calculate hash of a key.
+    #[inline]
+    pub fn hash<K: Hash + ?Sized>(&self, key: &K) -> u64 {
+        let mut hasher = self.get_hasher();
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+//------ Implement cbordata related functionalities
+
+// Intermediate type to serialize and de-serialized XorMap into bytes.
+#[cfg(feature = "cbordata")]
+#[derive(Cborize)]
+struct CborXorMap {
+    hash_builder: Vec<u8>,
+    seed: u64,
+    num_keys: Option<usize>,
+    block_length: u32,
+    bits: u32,
+    values: Vec<u64>,
+}
+
+#[cfg(feature = "cbordata")]
+impl CborXorMap {
+    const ID: &'static str = "xormap/0.0.1";
+}
+
+#[cfg(feature = "cbordata")]
+impl<V, H> IntoCbor for XorMap<V, H>
+where
+    V: MapValue,
+    H: BuildHasher + Into<Vec<u8>>,
+{
+    fn into_cbor(self) -> cbor::Result<Cbor> {
+        let val = CborXorMap {
+            hash_builder: self.hash_builder.into(),
+            seed: self.seed,
+            num_keys: self.num_keys,
+            block_length: self.block_length,
+            bits: self.values.bits(),
+            values: self.values.as_words().to_vec(),
+        };
+        val.into_cbor()
+    }
+}
+
+#[cfg(feature = "cbordata")]
+impl<V, H> FromCbor for XorMap<V, H>
+where
+    V: MapValue,
+    H: BuildHasher + From<Vec<u8>>,
+{
+    fn from_cbor(val: Cbor) -> cbor::Result<Self> {
+        let val = CborXorMap::from_cbor(val)?;
+
+        let len = (val.block_length as usize) * 3;
+        let values = match PackedArray::from_words(val.bits, len, val.values) {
+            Some(values) => values,
+            None => {
+                let msg = format!("invalid packed values, bits:{}", val.bits);
+                return Err(cbor::Error::FailConvert(
+                    format!("{}:{}", file!(), line!()),
+                    msg,
+                ));
+            }
+        };
+
+        let map = XorMap {
+            hash_builder: val.hash_builder.into(),
+            seed: val.seed,
+            num_keys: val.num_keys,
+            block_length: val.block_length,
+            values: Arc::new(values),
+            _value: PhantomData,
+        };
+
+        Ok(map)
+    }
+}
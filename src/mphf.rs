@@ -0,0 +1,479 @@
+//! Minimal perfect hash function, using the peeling algorithm of binary fuse filters.
+//!
+//! Refer to [Mphf] for details, which is constructed using [MphfBuilder].
+
+use std::borrow::Borrow;
+#[allow(unused_imports)]
+use std::collections::hash_map::DefaultHasher;
+#[allow(unused_imports)]
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::BuildHasher;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+
+#[cfg(feature = "cbordata")]
+use cbordata::Cbor;
+#[cfg(feature = "cbordata")]
+use cbordata::Cborize;
+#[cfg(feature = "cbordata")]
+use cbordata::FromCbor;
+#[cfg(feature = "cbordata")]
+use cbordata::IntoCbor;
+#[cfg(feature = "cbordata")]
+use cbordata::{self as cbor};
+
+use crate::fuse::binary_fuse_assign;
+use crate::fuse::binary_fuse_hash_batch;
+use crate::fuse::binary_fuse_mix_split;
+use crate::fuse::binary_fuse_peel;
+use crate::fuse::BinaryHashes;
+use crate::fuse::Segments;
+use crate::fuse::FUSE_RNG_SEED;
+use crate::hasher::random_seed;
+use crate::packed::PackedArray;
+use crate::xor8plus::RANK_WORDS;
+use crate::BuildHasherDefault;
+use crate::BuildStats;
+use crate::Error;
+use crate::Result;
+
+/// Width of selectors, in bits, enough to pick one of the three slots of a key.
+const SELECTOR_BITS: u32 = 2;
+
+/// Builds a [Mphf] for a set of keys.
+///
+/// Example:
+/// ```
+/// # use xorfilter::MphfBuilder;
+///
+/// let mut b: MphfBuilder = MphfBuilder::new();
+///
+/// b.populate(&["foo", "bar", "baz"]);
+/// let mphf = b.build().unwrap();
+///
+/// let mut indexes = vec![mphf.index("foo"), mphf.index("bar"), mphf.index("baz")];
+/// indexes.sort();
+/// assert_eq!(indexes, vec![0, 1, 2]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct MphfBuilder<H = BuildHasherDefault>
+where H: BuildHasher + Clone
+{
+    digests: Vec<u64>,
+    hash_time: Duration,
+    pub num_digests: usize,
+    pub hash_builder: H,
+    /// Seed for the random number generator drawing the peeling seeds.
+    pub rng_seed: u64,
+}
+
+impl<H> Default for MphfBuilder<H>
+where H: BuildHasher + Clone + Default
+{
+    fn default() -> Self {
+        Self::with_hasher(H::default())
+    }
+}
+
+impl<H> MphfBuilder<H>
+where H: BuildHasher + Clone
+{
+    /// New builder initialized with [BuildHasherDefault].
+    pub fn new() -> Self
+    where H: Default {
+        Self::default()
+    }
+
+    /// New builder initialized with supplied `hasher`.
+    pub fn with_hasher(hash_builder: H) -> Self {
+        Self {
+            digests: Vec::default(),
+            hash_time: Duration::default(),
+            num_digests: 0,
+            hash_builder,
+            rng_seed: FUSE_RNG_SEED,
+        }
+    }
+
+    /// Seed the builds with a random `rng_seed`, and return the same.
+    pub fn set_random_seed(&mut self) -> u64 {
+        self.rng_seed = random_seed();
+        self.rng_seed
+    }
+
+    pub fn get_hasher(&self) -> H::Hasher {
+        self.hash_builder.build_hasher()
+    }
+
+    /// Calculate hash of a key.
+    #[inline]
+    pub fn hash<K: Hash + ?Sized>(&self, key: &K) -> u64 {
+        let mut hasher = self.get_hasher();
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Insert 64-bit digest of a single key.
+    ///
+    /// Digest for the key shall be generated using the default-hasher or via hasher
+    /// supplied via [MphfBuilder::with_hasher] method.
+    pub fn insert<K: ?Sized + Hash>(&mut self, key: &K) {
+        let digest = self.hash(key);
+
+        self.digests.push(digest);
+        self.num_digests += 1;
+    }
+
+    /// Populate with 64-bit digests for a collection of keys, owned or
+    /// borrowed.
+    ///
+    /// Digest for key shall be generated using the default-hasher or via hasher supplied
+    /// via [MphfBuilder::with_hasher] method.
+    pub fn populate<I>(&mut self, keys: I)
+    where
+        I: IntoIterator,
+        I::Item: Hash,
+    {
+        let (mut n, start) = (0, Instant::now());
+
+        for key in keys.into_iter() {
+            n += 1;
+
+            let digest = self.hash(&key);
+            self.digests.push(digest);
+        }
+
+        self.num_digests += n;
+        self.hash_time += start.elapsed();
+    }
+
+    /// Populate with pre-compute collection of 64-bit digests.
+    pub fn populate_digests<I>(&mut self, digests: I)
+    where
+        I: IntoIterator,
+        I::Item: Borrow<u64>,
+    {
+        let mut n = 0;
+
+        for digest in digests.into_iter() {
+            n += 1;
+            self.digests.push(*digest.borrow());
+        }
+
+        self.num_digests += n;
+    }
+
+    /// Build [Mphf] for keys that where previously inserted using
+    /// [MphfBuilder::insert], [MphfBuilder::populate] and
+    /// [MphfBuilder::populate_digests] method. Duplicate digests are removed before
+    /// building the function.
+    pub fn build(&mut self) -> Result<Mphf<H>> {
+        self.build_with_stats().map(|(mphf, _)| mphf)
+    }
+
+    /// Same as [MphfBuilder::build], and return statistics on the build.
+    pub fn build_with_stats(&mut self) -> Result<(Mphf<H>, BuildStats)> {
+        let start = Instant::now();
+        self.digests.sort_unstable();
+        self.digests.dedup();
+        let dedup_time = start.elapsed();
+
+        let digests = std::mem::take(&mut self.digests);
+        let res = self.build_from_digests_with_stats(&digests);
+        self.digests = digests;
+
+        let (mphf, mut stats) = res?;
+        stats.num_digests = self.num_digests;
+        stats.num_duplicates = self.num_digests - stats.num_unique;
+        stats.hash_time = self.hash_time;
+        stats.dedup_time += dedup_time;
+        Ok((mphf, stats))
+    }
+
+    /// Build [Mphf] for pre-computed 64-bit digests for keys.
+    ///
+    /// If keys where previously inserted using [MphfBuilder::insert] or
+    /// [MphfBuilder::populate] or [MphfBuilder::populate_digests] methods, they
+    /// shall be ignored.
+    ///
+    /// Duplicate digests are dropped while peeling, they map to the same rank.
+    /// Functions are sized with `u32`, more than `u32::MAX` digests fail the build
+    /// with [Error::CapacityExceeded].
+    pub fn build_from_digests(&mut self, digests: &[u64]) -> Result<Mphf<H>> {
+        self.build_from_digests_with_stats(digests).map(|(mphf, _)| mphf)
+    }
+
+    /// Same as [MphfBuilder::build_from_digests], and return statistics on the
+    /// build. Selectors, bitmap and ranks are accounted as assignment time.
+    pub fn build_from_digests_with_stats(
+        &mut self,
+        digests: &[u64],
+    ) -> Result<(Mphf<H>, BuildStats)> {
+        if digests.len() > (u32::MAX as usize) {
+            return Err(Error::CapacityExceeded {
+                keys: digests.len(),
+                capacity: u32::MAX as usize,
+            });
+        }
+        let segs = Segments::new(digests.len() as u32);
+        let mut stats = BuildStats::default();
+        let mut rng_counter = self.rng_seed;
+        let peeled = binary_fuse_peel(&segs, digests, &mut rng_counter, &mut stats)?;
+
+        let start = Instant::now();
+        // every key owns one of its three slots, selectors are assigned such that
+        // xor of a key's selectors locate its slot.
+        let owned: HashMap<u64, u8> = peeled
+            .reverse_order
+            .iter()
+            .copied()
+            .zip(peeled.reverse_h.iter().copied())
+            .collect();
+        let mut selectors = PackedArray::new(SELECTOR_BITS, segs.array_length as usize);
+        binary_fuse_assign(&segs, &peeled, &mut selectors, |h| owned[&h] as u64);
+
+        let mut bitmap = vec![0_u64; (segs.array_length as usize).div_ceil(64)];
+        for (hash, found) in owned.iter() {
+            let hs = binary_fuse_hash_batch(
+                *hash,
+                segs.segment_length,
+                segs.segment_length_mask,
+                segs.segment_count_length,
+            );
+            let slot = [hs.h0, hs.h1, hs.h2][*found as usize] as usize;
+            bitmap[slot / 64] |= 1 << (slot % 64);
+        }
+
+        let mut ranks = vec![0_u32; bitmap.len().div_ceil(RANK_WORDS)];
+        let mut rank = 0;
+        for (i, word) in bitmap.iter().enumerate() {
+            if i % RANK_WORDS == 0 {
+                ranks[i / RANK_WORDS] = rank;
+            }
+            rank += word.count_ones();
+        }
+
+        let mphf = Mphf {
+            hash_builder: self.hash_builder.clone(),
+            seed: peeled.seed,
+            num_keys: peeled.len(),
+            segment_length: segs.segment_length,
+            segment_length_mask: segs.segment_length_mask,
+            segment_count: segs.segment_count,
+            segment_count_length: segs.segment_count_length,
+            selectors: Arc::new(selectors),
+            bitmap: Arc::new(bitmap),
+            ranks: Arc::new(ranks),
+        };
+        stats.assign_time = start.elapsed();
+        // a selector and a bitmap bit per slot, ranks are not accounted.
+        stats.set_array(segs.array_length as usize, SELECTOR_BITS + 1);
+
+        Ok((mphf, stats))
+    }
+}
+
+/// Type Mphf is a minimal perfect hash function, mapping each key in a set of `n`
+/// keys to a unique index within `0..n`.
+///
+/// While peeling, binary fuse filters assign each key to one of its three slots, no
+/// two keys owning the same slot. Mphf stores a 2-bit selector in each slot, such
+/// that xor of a key's three selectors locate the slot owned by the key. Owned slots
+/// are marked in a bitmap, and the rank of the key's slot in the bitmap is its index.
+/// Memory usage is about 3.5 bits per key for sizeable sets.
+///
+/// Looking up a key that was not part of the input returns an arbitrary index within
+/// `0..n`.
+///
+/// Mphf is parametrized over type `H` which is expected to implement [BuildHasher]
+/// trait, like types [RandomState] and [BuildHasherDefault]. When not supplied,
+/// [BuildHasherDefault] is used as the default hash-builder.
+///
+/// If `RandomState` is used as BuildHasher, `std` has got this to say
+/// > _A particular instance RandomState will create the same instances
+/// > of Hasher, but the hashers created by two different RandomState
+/// > instances are unlikely to produce the same result for the same values._
+///
+/// If [DefaultHasher] is used as BuildHasher, `std` has got this to say,
+/// > _The internal algorithm is not specified, and so its hashes
+/// > should not be relied upon over releases._
+#[derive(Clone, Debug)]
+pub struct Mphf<H = BuildHasherDefault>
+where H: BuildHasher
+{
+    pub hash_builder: H,
+    pub seed: u64,
+    pub num_keys: usize,
+    pub segment_length: u32,
+    pub segment_length_mask: u32,
+    pub segment_count: u32,
+    pub segment_count_length: u32,
+    /// 2-bit selector for each slot.
+    pub selectors: Arc<PackedArray>,
+    /// One bit for each slot, set if the slot is owned by a key.
+    pub bitmap: Arc<Vec<u64>>,
+    /// Number of owned slots, preceding every [RANK_WORDS] words of bitmap.
+    pub ranks: Arc<Vec<u32>>,
+}
+
+impl<H> Mphf<H>
+where H: BuildHasher
+{
+    /// Return the number of keys built into the function, indexes are within
+    /// `0..len`.
+    pub fn len(&self) -> usize {
+        self.num_keys
+    }
+
+    /// Return whether the function was built for an empty set of keys.
+    pub fn is_empty(&self) -> bool {
+        self.num_keys == 0
+    }
+
+    /// Return the size of index.
+    #[inline]
+    pub fn size_of(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.selectors.size_of()
+            + (self.bitmap.len() * 8)
+            + (self.ranks.len() * 4)
+    }
+
+    /// Return the unique index for key, within `0..len`.
+    pub fn index<K: ?Sized + Hash>(&self, key: &K) -> usize {
+        let digest = self.hash(key);
+        self.index_digest(digest)
+    }
+
+    /// Return the unique index for key, as pre-computed digest form, within
+    /// `0..len`.
+    pub fn index_digest(&self, digest: u64) -> usize {
+        let hash = binary_fuse_mix_split(digest, self.seed);
+        let BinaryHashes { h0, h1, h2 } = binary_fuse_hash_batch(
+            hash,
+            self.segment_length,
+            self.segment_length_mask,
+            self.segment_count_length,
+        );
+        let sels = &self.selectors;
+        let found = sels.get(h0 as usize) ^ sels.get(h1 as usize) ^ sels.get(h2 as usize);
+        let slot = match found {
+            0 => h0,
+            1 => h1,
+            _ => h2,
+        } as usize;
+
+        let (w, bit) = (slot / 64, slot % 64);
+        let mut rank = self.ranks[w / RANK_WORDS];
+        for word in self.bitmap[(w - (w % RANK_WORDS))..w].iter() {
+            rank += word.count_ones();
+        }
+        let rank = (rank + (self.bitmap[w] & ((1 << bit) - 1)).count_ones()) as usize;
+
+        // keys not part of the input can land after the last owned slot.
+        std::cmp::min(rank, self.num_keys.saturating_sub(1))
+    }
+
+    pub fn get_hasher(&self) -> H::Hasher {
+        self.hash_builder.build_hasher()
+    }
+
+    /// Calculate hash of a key.
+    #[inline]
+    pub fn hash<K: Hash + ?Sized>(&self, key: &K) -> u64 {
+        let mut hasher = self.get_hasher();
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+//------ Implement cbordata related functionalities
+
+// Intermediate type to serialize and de-serialized Mphf into bytes.
+#[cfg(feature = "cbordata")]
+#[derive(Cborize)]
+struct CborMphf {
+    hash_builder: Vec<u8>,
+    seed: u64,
+    num_keys: usize,
+    segment_length: u32,
+    segment_length_mask: u32,
+    segment_count: u32,
+    segment_count_length: u32,
+    array_length: usize,
+    selectors: Vec<u64>,
+    bitmap: Vec<u64>,
+    ranks: Vec<u32>,
+}
+
+#[cfg(feature = "cbordata")]
+impl CborMphf {
+    const ID: &'static str = "mphf/0.0.1";
+}
+
+#[cfg(feature = "cbordata")]
+impl<H> IntoCbor for Mphf<H>
+where H: BuildHasher + Into<Vec<u8>>
+{
+    fn into_cbor(self) -> cbor::Result<Cbor> {
+        let val = CborMphf {
+            hash_builder: self.hash_builder.into(),
+            seed: self.seed,
+            num_keys: self.num_keys,
+            segment_length: self.segment_length,
+            segment_length_mask: self.segment_length_mask,
+            segment_count: self.segment_count,
+            segment_count_length: self.segment_count_length,
+            array_length: self.selectors.len(),
+            selectors: self.selectors.as_words().to_vec(),
+            bitmap: self.bitmap.to_vec(),
+            ranks: self.ranks.to_vec(),
+        };
+        val.into_cbor()
+    }
+}
+
+#[cfg(feature = "cbordata")]
+impl<H> FromCbor for Mphf<H>
+where H: BuildHasher + From<Vec<u8>>
+{
+    fn from_cbor(val: Cbor) -> cbor::Result<Self> {
+        let val = CborMphf::from_cbor(val)?;
+
+        let words = val.selectors;
+        let selectors =
+            match PackedArray::from_words(SELECTOR_BITS, val.array_length, words) {
+                Some(selectors) => selectors,
+                None => {
+                    let msg = format!("invalid selectors, length:{}", val.array_length);
+                    return Err(cbor::Error::FailConvert(
+                        format!("{}:{}", file!(), line!()),
+                        msg,
+                    ));
+                }
+            };
+
+        let mphf = Mphf {
+            hash_builder: val.hash_builder.into(),
+            seed: val.seed,
+            num_keys: val.num_keys,
+            segment_length: val.segment_length,
+            segment_length_mask: val.segment_length_mask,
+            segment_count: val.segment_count,
+            segment_count_length: val.segment_count_length,
+            selectors: Arc::new(selectors),
+            bitmap: Arc::new(val.bitmap),
+            ranks: Arc::new(val.ranks),
+        };
+
+        Ok(mphf)
+    }
+}
+
+#[cfg(test)]
+#[path = "mphf_test.rs"]
+mod mphf_test;
@@ -0,0 +1,169 @@
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+
+#[cfg(feature = "cbordata")]
+use cbordata::FromCbor;
+#[cfg(feature = "cbordata")]
+use cbordata::IntoCbor;
+use rand::prelude::random;
+use rand::rngs::StdRng;
+use rand::Rng;
+use rand::SeedableRng;
+
+use super::*;
+
+fn test_mphf_build<H>(name: &str, seed: u64, size: usize)
+where H: BuildHasher + Clone + Default {
+    println!("test_mphf_build<{}> size:{}", name, size);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let keys: Vec<u64> = (0..size).map(|_| rng.gen::<u64>()).collect();
+    let (keys1, keys2) = keys.split_at(size / 2);
+
+    let mut builder = MphfBuilder::<H>::new();
+    // populate api
+    builder.populate(keys1);
+    // populate_digests api
+    let digests: Vec<u64> = keys2.iter().map(|k| builder.hash(k)).collect();
+    builder.populate_digests(digests.iter());
+    // insert api, duplicates are removed before build.
+    if let Some(key) = keys.first() {
+        builder.insert(key);
+    }
+
+    let mphf = builder.build().expect("failed build");
+    assert_eq!(mphf.len(), size);
+    assert_eq!(mphf.is_empty(), size == 0);
+
+    // index api, every key shall map to a unique index within 0..size.
+    let mut seen = vec![false; size];
+    for key in keys.iter() {
+        let index = mphf.index(key);
+        assert!(index < size, "key {} index {}", key, index);
+        assert!(!seen[index], "key {} duplicate index {}", key, index);
+        seen[index] = true;
+    }
+    // index_digest api
+    for (key, digest) in keys2.iter().zip(digests.iter()) {
+        assert_eq!(mphf.index_digest(*digest), mphf.index(key), "key {}", key);
+    }
+
+    if size > 0 {
+        for _ in 0..100_000 {
+            let index = mphf.index(&rng.gen::<u64>());
+            assert!(index < size, "index {} >= {}", index, size);
+        }
+    }
+
+    let bpv = ((mphf.size_of() * 8) as f64) / (size as f64);
+    println!("test_mphf_build<{}> bits per entry {}", name, bpv);
+    if size > 100_000 {
+        assert!(bpv < 3.6, "bpv({}) >= 3.6", bpv);
+    }
+}
+
+#[test]
+fn test_mphf() {
+    let mut seed: u64 = random();
+    println!("test_mphf seed:{}", seed);
+
+    for size in [0, 1, 2, 10, 1000, 10_000, 100_000, 1_000_000].iter() {
+        seed = seed.wrapping_add(*size as u64);
+        test_mphf_build::<RandomState>("RandomState", seed, *size);
+        test_mphf_build::<BuildHasherDefault>("BuildHasherDefault", seed, *size);
+    }
+}
+
+#[test]
+fn test_mphf_build_stats() {
+    let seed: u64 = random();
+    println!("test_mphf_build_stats seed:{}", seed);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let keys: Vec<u64> = (0..100_000).map(|_| rng.gen::<u64>()).collect();
+
+    let mut builder = MphfBuilder::<BuildHasherDefault>::new();
+    builder.populate(&keys);
+    builder.populate(&keys[..1000]);
+    let (mphf, stats) = builder.build_with_stats().expect("failed build");
+    println!("test_mphf_build_stats {}", stats);
+
+    assert_eq!(stats.num_digests, keys.len() + 1000);
+    assert_eq!(stats.num_unique, keys.len());
+    assert_eq!(stats.num_duplicates, 1000);
+    assert_eq!(stats.seed, mphf.seed);
+    assert_eq!(stats.array_length, mphf.selectors.len());
+    assert!(stats.bits_per_key > 3.0 && stats.bits_per_key < 4.0);
+    assert!(stats.total_time() >= stats.assign_time);
+}
+
+#[test]
+fn test_mphf_duplicates() {
+    let digests: Vec<u64> = (0..1000_u64).chain(0..100).collect();
+
+    let mut builder = MphfBuilder::<BuildHasherDefault>::new();
+    let mphf = builder.build_from_digests(&digests).expect("failed build");
+    assert_eq!(mphf.len(), 1000);
+
+    let mut seen = vec![false; 1000];
+    for digest in 0..1000_u64 {
+        let index = mphf.index_digest(digest);
+        assert!(index < 1000, "digest {} index {}", digest, index);
+        assert!(!seen[index], "digest {} duplicate index {}", digest, index);
+        seen[index] = true;
+    }
+}
+
+#[test]
+fn test_mphf_rng_seed() {
+    let seed: u64 = random();
+    println!("test_mphf_rng_seed seed:{}", seed);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let keys: Vec<u64> = (0..10_000).map(|_| rng.gen::<u64>()).collect();
+
+    let build = |rng_seed: u64| {
+        let mut builder = MphfBuilder::<BuildHasherDefault>::new();
+        builder.rng_seed = rng_seed;
+        builder.populate(&keys);
+        builder.build().expect("failed build")
+    };
+
+    let (mphf1, mphf2) = (build(seed), build(seed));
+    assert_eq!(mphf1.seed, mphf2.seed);
+    for key in keys.iter() {
+        assert_eq!(mphf1.index(key), mphf2.index(key), "key {}", key);
+    }
+    assert_ne!(mphf1.seed, build(seed.wrapping_add(1)).seed);
+
+    let mut builder = MphfBuilder::<BuildHasherDefault>::new();
+    let rng_seed = builder.set_random_seed();
+    assert_eq!(builder.rng_seed, rng_seed);
+    assert_ne!(builder.set_random_seed(), rng_seed);
+}
+
+#[cfg(feature = "cbordata")]
+#[test]
+fn test_mphf_cbor() {
+    let seed: u64 = random();
+    println!("test_mphf_cbor seed:{}", seed);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let keys: Vec<u64> = (0..100_000).map(|_| rng.gen::<u64>()).collect();
+
+    let mphf = {
+        let mut builder = MphfBuilder::<BuildHasherDefault>::new();
+        builder.populate(&keys);
+        builder.build().expect("failed build")
+    };
+    let indexes: Vec<usize> = keys.iter().map(|k| mphf.index(k)).collect();
+
+    let mphf = {
+        let val = mphf.into_cbor().unwrap();
+        Mphf::<BuildHasherDefault>::from_cbor(val).unwrap()
+    };
+
+    for (key, index) in keys.iter().zip(indexes.iter()) {
+        assert_eq!(mphf.index(key), *index, "key {}", key);
+    }
+}
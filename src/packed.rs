@@ -0,0 +1,162 @@
+//! Bit-packed array of fixed width values.
+//!
+//! Refer to [PackedArray] for details. Packed fingerprints are used by the
+//! [crate::FusePacked] and [crate::xor_packed::XorPacked] filters to trade false
+//! positive rate for memory, one bit at a time.
+
+use std::ops::BitXor;
+
+/// Minimum width, in bits, of a value in [PackedArray].
+pub(crate) const MIN_BITS: u32 = 1;
+/// Maximum width, in bits, of a value in [PackedArray].
+pub(crate) const MAX_BITS: u32 = 32;
+
+/// Type PackedArray holds `len` number of values, each `bits` wide, back to back
+/// in a vector of 64-bit words.
+///
+/// A value can straddle two words. An extra word is allocated at the end so that
+/// reading and writing a value always touches exactly two words, without
+/// branching on the value's position.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PackedArray {
+    bits: u32,
+    len: usize,
+    words: Vec<u64>,
+}
+
+impl PackedArray {
+    /// New array of `len` zero values, each `bits` wide.
+    ///
+    /// Panics if `bits` is not within 1 and 32.
+    pub fn new(bits: u32, len: usize) -> PackedArray {
+        assert!(
+            (MIN_BITS..=MAX_BITS).contains(&bits),
+            "bits:{} not within {}..={}",
+            bits,
+            MIN_BITS,
+            MAX_BITS
+        );
+
+        let n = (len * (bits as usize)).div_ceil(64);
+        PackedArray {
+            bits,
+            len,
+            words: vec![0; n + 1],
+        }
+    }
+
+    /// Re-construct an array from its words, refer to [PackedArray::as_words].
+    /// Return None if `words` cannot hold `len` values.
+    pub fn from_words(bits: u32, len: usize, words: Vec<u64>) -> Option<PackedArray> {
+        let n = (len * (bits as usize)).div_ceil(64);
+        match (MIN_BITS..=MAX_BITS).contains(&bits) && words.len() == (n + 1) {
+            true => Some(PackedArray { bits, len, words }),
+            false => None,
+        }
+    }
+
+    /// Return the width of each value in bits.
+    #[inline]
+    pub fn bits(&self) -> u32 {
+        self.bits
+    }
+
+    /// Return the mask to truncate a value to [PackedArray::bits] width.
+    #[inline]
+    pub fn mask(&self) -> u64 {
+        (1_u64 << self.bits) - 1
+    }
+
+    /// Return the number of values in this array.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Return whether array is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Return the underlying words, including the trailing padding word.
+    pub fn as_words(&self) -> &[u64] {
+        &self.words
+    }
+
+    /// Return the memory footprint of the packed values, in bytes.
+    pub fn size_of(&self) -> usize {
+        self.words.len() * 8
+    }
+
+    /// Return the value at `index`.
+    #[inline]
+    pub fn get(&self, index: usize) -> u64 {
+        let pos = index * (self.bits as usize);
+        let (w, off) = (pos >> 6, (pos & 63) as u32);
+
+        // shifting by `1` and then by `63 - off` avoids an overflowing shift,
+        // when `off` is ZERO, without branching.
+        let lo = self.words[w] >> off;
+        let hi = (self.words[w + 1] << 1) << (63 - off);
+        (lo | hi) & self.mask()
+    }
+
+    /// Set the value at `index`, `value` is truncated to [PackedArray::bits].
+    #[inline]
+    pub fn set(&mut self, index: usize, value: u64) {
+        let mask = self.mask();
+        let value = value & mask;
+
+        let pos = index * (self.bits as usize);
+        let (w, off) = (pos >> 6, (pos & 63) as u32);
+
+        self.words[w] = (self.words[w] & !(mask << off)) | (value << off);
+        let (hi_mask, hi) = ((mask >> 1) >> (63 - off), (value >> 1) >> (63 - off));
+        self.words[w + 1] = (self.words[w + 1] & !hi_mask) | hi;
+    }
+}
+
+/// Slot storage that can be assigned by the peeling algorithms, implemented for
+/// slices of plain fingerprints and for [PackedArray].
+pub(crate) trait Slots {
+    type Item: Copy + BitXor<Output = Self::Item>;
+
+    fn get_slot(&self, index: usize) -> Self::Item;
+
+    fn set_slot(&mut self, index: usize, value: Self::Item);
+}
+
+impl<T> Slots for [T]
+where T: Copy + BitXor<Output = T>
+{
+    type Item = T;
+
+    #[inline]
+    fn get_slot(&self, index: usize) -> T {
+        self[index]
+    }
+
+    #[inline]
+    fn set_slot(&mut self, index: usize, value: T) {
+        self[index] = value
+    }
+}
+
+impl Slots for PackedArray {
+    type Item = u64;
+
+    #[inline]
+    fn get_slot(&self, index: usize) -> u64 {
+        self.get(index)
+    }
+
+    #[inline]
+    fn set_slot(&mut self, index: usize, value: u64) {
+        self.set(index, value)
+    }
+}
+
+#[cfg(test)]
+#[path = "packed_test.rs"]
+mod packed_test;
@@ -0,0 +1,49 @@
+use rand::prelude::random;
+use rand::rngs::StdRng;
+use rand::Rng;
+use rand::SeedableRng;
+
+use super::*;
+
+#[test]
+fn test_packed_array() {
+    let seed: u64 = random();
+    println!("test_packed_array seed:{}", seed);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    for bits in MIN_BITS..=MAX_BITS {
+        for len in [0, 1, 2, 63, 64, 65, 1000].iter() {
+            let mut arr = PackedArray::new(bits, *len);
+            assert_eq!(arr.bits(), bits);
+            assert_eq!(arr.len(), *len);
+
+            let vals: Vec<u64> = (0..*len).map(|_| rng.gen::<u64>()).collect();
+            for (i, val) in vals.iter().enumerate() {
+                arr.set(i, *val);
+            }
+            for (i, val) in vals.iter().enumerate() {
+                assert_eq!(arr.get(i), val & arr.mask(), "bits:{} index:{}", bits, i);
+            }
+
+            // overwrite in reverse order, neighbours shall not be disturbed.
+            for (i, val) in vals.iter().enumerate().rev() {
+                arr.set(i, !val);
+            }
+            for (i, val) in vals.iter().enumerate() {
+                assert_eq!(arr.get(i), !val & arr.mask(), "bits:{} index:{}", bits, i);
+            }
+
+            let words = arr.as_words().to_vec();
+            assert_eq!(PackedArray::from_words(bits, *len, words), Some(arr));
+        }
+    }
+
+    assert_eq!(PackedArray::from_words(8, 100, vec![0; 3]), None);
+    assert_eq!(PackedArray::from_words(33, 0, vec![0; 1]), None);
+}
+
+#[test]
+#[should_panic]
+fn test_packed_array_bits() {
+    PackedArray::new(33, 10);
+}
@@ -0,0 +1,538 @@
+//! Filters for key sets beyond `u32` capacity, partitioned into shards.
+//!
+//! Refer to [ShardedFilter] for details, which is constructed using
+//! [ShardedFilterBuilder].
+
+use std::borrow::Borrow;
+#[allow(unused_imports)]
+use std::collections::hash_map::DefaultHasher;
+#[allow(unused_imports)]
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::time::Duration;
+use std::time::Instant;
+
+#[cfg(feature = "cbordata")]
+use cbordata::Cbor;
+#[cfg(feature = "cbordata")]
+use cbordata::Cborize;
+#[cfg(feature = "cbordata")]
+use cbordata::FromCbor;
+#[cfg(feature = "cbordata")]
+use cbordata::IntoCbor;
+#[cfg(feature = "cbordata")]
+use cbordata::{self as cbor};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::fuse::FUSE_RNG_SEED;
+use crate::hasher::random_seed;
+use crate::BuildHasherDefault;
+use crate::BuildStats;
+use crate::Error;
+use crate::Filter;
+use crate::Result;
+
+/// Maximum number of high hash bits used to pick a shard, that is, upto 65536
+/// shards.
+pub const MAX_SHARD_BITS: u32 = 16;
+
+/// Builds a [ShardedFilter], partitioning digests into `2^shard_bits` shards as
+/// they are inserted, so that each shard is built independently.
+///
+/// Example:
+/// ```
+/// # use xorfilter::{fuse8::Fuse8Builder, NoHash, ShardedFilterBuilder};
+///
+/// let mut b: ShardedFilterBuilder = ShardedFilterBuilder::new(4);
+///
+/// b.populate(&["foo", "bar", "baz"]);
+/// let filter = b
+///     .build(|digests, rng_seed| {
+///         let mut shard = Fuse8Builder::with_hasher(NoHash);
+///         shard.rng_seed = rng_seed;
+///         shard.build_from_digests(digests)
+///     })
+///     .unwrap();
+///
+/// assert!(filter.contains("foo"));
+/// assert_eq!(filter.len(), Some(3));
+/// ```
+#[derive(Clone, Debug)]
+pub struct ShardedFilterBuilder<H = BuildHasherDefault>
+where H: BuildHasher + Clone
+{
+    shards: Vec<Vec<u64>>,
+    hash_time: Duration,
+    pub shard_bits: u32,
+    pub hash_builder: H,
+    /// Seed passed to `build` for every shard.
+    pub rng_seed: u64,
+}
+
+impl<H> ShardedFilterBuilder<H>
+where H: BuildHasher + Clone
+{
+    /// New builder with `2^shard_bits` shards, initialized with
+    /// [BuildHasherDefault].
+    ///
+    /// Panics if `shard_bits` is more than [MAX_SHARD_BITS].
+    pub fn new(shard_bits: u32) -> Self
+    where H: Default {
+        Self::with_hasher(shard_bits, H::default())
+    }
+
+    /// New builder with `2^shard_bits` shards, initialized with supplied `hasher`.
+    ///
+    /// Panics if `shard_bits` is more than [MAX_SHARD_BITS].
+    pub fn with_hasher(shard_bits: u32, hash_builder: H) -> Self {
+        assert!(
+            shard_bits <= MAX_SHARD_BITS,
+            "shard_bits {} > {}",
+            shard_bits,
+            MAX_SHARD_BITS
+        );
+
+        ShardedFilterBuilder {
+            shards: vec![Vec::default(); 1 << shard_bits],
+            hash_time: Duration::default(),
+            shard_bits,
+            hash_builder,
+            rng_seed: FUSE_RNG_SEED,
+        }
+    }
+
+    /// Seed the builds with a random `rng_seed`, and return the same.
+    pub fn set_random_seed(&mut self) -> u64 {
+        self.rng_seed = random_seed();
+        self.rng_seed
+    }
+
+    pub fn get_hasher(&self) -> H::Hasher {
+        self.hash_builder.build_hasher()
+    }
+
+    /// Calculate hash of a key.
+    #[inline]
+    pub fn hash<K: Hash + ?Sized>(&self, key: &K) -> u64 {
+        let mut hasher = self.get_hasher();
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Insert 64-bit digest of a single key. Digest for the key shall be generated
+    /// using the default-hasher or via hasher supplied via
+    /// [ShardedFilterBuilder::with_hasher] method.
+    pub fn insert<K: ?Sized + Hash>(&mut self, key: &K) {
+        let digest = self.hash(key);
+        self.insert_digest(digest)
+    }
+
+    /// Insert pre-computed 64-bit digest of a single key.
+    pub fn insert_digest(&mut self, digest: u64) {
+        let shard = shard_of(digest, self.shard_bits);
+        self.shards[shard].push(digest)
+    }
+
+    /// Populate with 64-bit digests for a collection of keys, owned or borrowed.
+    /// Digest for key shall be generated using the default-hasher or via hasher
+    /// supplied via [ShardedFilterBuilder::with_hasher] method.
+    pub fn populate<I>(&mut self, keys: I)
+    where
+        I: IntoIterator,
+        I::Item: Hash,
+    {
+        let start = Instant::now();
+        for key in keys.into_iter() {
+            self.insert(&key)
+        }
+        self.hash_time += start.elapsed();
+    }
+
+    /// Populate with pre-compute collection of 64-bit digests.
+    pub fn populate_digests<I>(&mut self, digests: I)
+    where
+        I: IntoIterator,
+        I::Item: Borrow<u64>,
+    {
+        for digest in digests.into_iter() {
+            self.insert_digest(*digest.borrow())
+        }
+    }
+
+    /// Build [ShardedFilter] for keys that where previously inserted, calling
+    /// `build` once per shard, with sorted and de-duplicated digests for that shard
+    /// and the `rng_seed` to build the shard with.
+    /// Digests are released only after every shard is built, so that a failed
+    /// build can be retried, say after [ShardedFilterBuilder::set_random_seed].
+    ///
+    /// Shards shall not hash digests again, build them with [crate::NoHash].
+    ///
+    /// Fails if `build` fails, or if a shard has more than `u32::MAX` keys.
+    pub fn build<F, B>(&mut self, build: B) -> Result<ShardedFilter<F, H>>
+    where
+        F: Filter,
+        B: Fn(&[u64], u64) -> Result<F>,
+    {
+        let build = |digests: &[u64], rng_seed| {
+            Ok((build(digests, rng_seed)?, BuildStats::default()))
+        };
+        self.build_with_stats(build).map(|(filter, _)| filter)
+    }
+
+    /// Same as [ShardedFilterBuilder::build], except that `build` also returns
+    /// statistics on building the shard, like the `build_from_digests_with_stats`
+    /// methods of filter builders. Return statistics merged across shards.
+    pub fn build_with_stats<F, B>(
+        &mut self,
+        build: B,
+    ) -> Result<(ShardedFilter<F, H>, BuildStats)>
+    where
+        F: Filter,
+        B: Fn(&[u64], u64) -> Result<(F, BuildStats)>,
+    {
+        let n = self.shards.len();
+        let (mut shards, mut stats) = (Vec::with_capacity(n), Vec::with_capacity(n));
+        for digests in self.shards.iter_mut() {
+            let (shard, shard_stats) = build_dedup_shard(digests, self.rng_seed, &build)?;
+            shards.push(shard);
+            stats.push(shard_stats);
+        }
+        self.shards = vec![Vec::default(); n];
+
+        let filter = ShardedFilter {
+            hash_builder: self.hash_builder.clone(),
+            shard_bits: self.shard_bits,
+            shards,
+        };
+        let mut stats = BuildStats::merge(&stats);
+        stats.hash_time = self.hash_time;
+
+        Ok((filter, stats))
+    }
+
+    /// Same as [ShardedFilterBuilder::build], except that shards are built in
+    /// parallel, using rayon's global thread pool. Since each shard is built from
+    /// the same digests, shards are identical to the ones built by
+    /// [ShardedFilterBuilder::build].
+    #[cfg(feature = "parallel")]
+    pub fn build_parallel<F, B>(&mut self, build: B) -> Result<ShardedFilter<F, H>>
+    where
+        F: Filter + Send,
+        B: Fn(&[u64], u64) -> Result<F> + Sync,
+    {
+        let build = |digests: &[u64], rng_seed| {
+            Ok((build(digests, rng_seed)?, BuildStats::default()))
+        };
+        self.build_parallel_with_stats(build).map(|(filter, _)| filter)
+    }
+
+    /// Same as [ShardedFilterBuilder::build_with_stats], except that shards are
+    /// built in parallel. Time spent on each shard is summed in the merged
+    /// statistics, and can exceed the wall-clock time of the build.
+    #[cfg(feature = "parallel")]
+    pub fn build_parallel_with_stats<F, B>(
+        &mut self,
+        build: B,
+    ) -> Result<(ShardedFilter<F, H>, BuildStats)>
+    where
+        F: Filter + Send,
+        B: Fn(&[u64], u64) -> Result<(F, BuildStats)> + Sync,
+    {
+        let rng_seed = self.rng_seed;
+        let (shards, stats): (Vec<F>, Vec<BuildStats>) = self
+            .shards
+            .par_iter_mut()
+            .map(|digests| build_dedup_shard(digests, rng_seed, &build))
+            .collect::<Result<Vec<(F, BuildStats)>>>()?
+            .into_iter()
+            .unzip();
+        self.shards = vec![Vec::default(); shards.len()];
+
+        let filter = ShardedFilter {
+            hash_builder: self.hash_builder.clone(),
+            shard_bits: self.shard_bits,
+            shards,
+        };
+        let mut stats = BuildStats::merge(&stats);
+        stats.hash_time = self.hash_time;
+
+        Ok((filter, stats))
+    }
+
+    /// Build [ShardedFilter] for pre-computed 64-bit digests for keys, calling
+    /// `build` once per shard.
+    ///
+    /// If keys where previously inserted using [ShardedFilterBuilder::insert] or
+    /// [ShardedFilterBuilder::populate] or [ShardedFilterBuilder::populate_digests]
+    /// methods, they shall be ignored.
+    ///
+    /// It is upto the caller to ensure that digests are unique, that there no
+    /// duplicates.
+    pub fn build_from_digests<F, B>(
+        &mut self,
+        digests: &[u64],
+        build: B,
+    ) -> Result<ShardedFilter<F, H>>
+    where
+        F: Filter,
+        B: Fn(&[u64], u64) -> Result<F>,
+    {
+        let build = |digests: &[u64], rng_seed| {
+            Ok((build(digests, rng_seed)?, BuildStats::default()))
+        };
+        self.build_from_digests_with_stats(digests, build).map(|(filter, _)| filter)
+    }
+
+    /// Same as [ShardedFilterBuilder::build_from_digests], except that `build` also
+    /// returns statistics on building the shard. Return statistics merged across
+    /// shards.
+    pub fn build_from_digests_with_stats<F, B>(
+        &mut self,
+        digests: &[u64],
+        build: B,
+    ) -> Result<(ShardedFilter<F, H>, BuildStats)>
+    where
+        F: Filter,
+        B: Fn(&[u64], u64) -> Result<(F, BuildStats)>,
+    {
+        let mut shards: Vec<Vec<u64>> = vec![Vec::default(); 1 << self.shard_bits];
+        for digest in digests.iter() {
+            shards[shard_of(*digest, self.shard_bits)].push(*digest);
+        }
+
+        let n = shards.len();
+        let (mut filters, mut stats) = (Vec::with_capacity(n), Vec::with_capacity(n));
+        for digests in shards.into_iter() {
+            let (filter, shard_stats) = build_shard(&digests, self.rng_seed, &build)?;
+            filters.push(filter);
+            stats.push(shard_stats);
+        }
+
+        let filter = ShardedFilter {
+            hash_builder: self.hash_builder.clone(),
+            shard_bits: self.shard_bits,
+            shards: filters,
+        };
+
+        Ok((filter, BuildStats::merge(&stats)))
+    }
+}
+
+/// Type ShardedFilter partitions a key set into `2^shard_bits` independent filters,
+/// called shards, to index more than `u32::MAX` keys.
+///
+/// Fixed width filters, like [crate::fuse8::Fuse8], are sized with `u32` and are built in
+/// a single allocation. Digests are routed to a shard by their high `shard_bits`
+/// bits, so each shard indexes about `1/2^shard_bits` of the keys, and the false
+/// positive rate is that of the shard filter type `F`. Shards are looked up using
+/// pre-computed digests, refer to [Filter].
+///
+/// If `RandomState` is used as BuildHasher, `std` has got this to say
+/// > _A particular instance RandomState will create the same instances
+/// > of Hasher, but the hashers created by two different RandomState
+/// > instances are unlikely to produce the same result for the same values._
+///
+/// If [DefaultHasher] is used as BuildHasher, `std` has got this to say,
+/// > _The internal algorithm is not specified, and so its hashes
+/// > should not be relied upon over releases._
+#[derive(Clone)]
+pub struct ShardedFilter<F, H = BuildHasherDefault>
+where
+    F: Filter,
+    H: BuildHasher,
+{
+    pub hash_builder: H,
+    pub shard_bits: u32,
+    pub shards: Vec<F>,
+}
+
+impl<F, H> ShardedFilter<F, H>
+where
+    F: Filter,
+    H: BuildHasher,
+{
+    #[allow(clippy::len_without_is_empty)]
+    /// Return the number of keys built into all the shards.
+    pub fn len(&self) -> Option<usize> {
+        self.shards.iter().map(|shard| shard.len()).sum()
+    }
+
+    /// Return the size of index.
+    #[inline]
+    pub fn size_of(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.shards.iter().map(|shard| shard.size_of()).sum::<usize>()
+    }
+
+    /// Return the shard that indexes `digest`.
+    #[inline]
+    pub fn shard_of(&self, digest: u64) -> usize {
+        shard_of(digest, self.shard_bits)
+    }
+
+    /// Contains tell you whether the key is likely part of the set, with false
+    /// positive rate.
+    pub fn contains<K: ?Sized + Hash>(&self, key: &K) -> bool {
+        let digest = self.hash(key);
+        self.contains_key(digest)
+    }
+
+    /// Contains tell you whether the key, as pre-computed digest form, is likely
+    /// part of the set, with false positive rate.
+    pub fn contains_key(&self, digest: u64) -> bool {
+        self.shards[self.shard_of(digest)].contains_digest(digest)
+    }
+
+    pub fn get_hasher(&self) -> H::Hasher {
+        self.hash_builder.build_hasher()
+    }
+
+    /// Calculate hash of a key.
+    #[inline]
+    pub fn hash<K: Hash + ?Sized>(&self, key: &K) -> u64 {
+        let mut hasher = self.get_hasher();
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+impl<F, H> Filter for ShardedFilter<F, H>
+where
+    F: Filter,
+    H: BuildHasher,
+{
+    fn contains_digest(&self, digest: u64) -> bool {
+        self.contains_key(digest)
+    }
+
+    fn len(&self) -> Option<usize> {
+        self.len()
+    }
+
+    fn size_of(&self) -> usize {
+        self.size_of()
+    }
+}
+
+#[inline]
+pub(crate) fn shard_of(digest: u64, shard_bits: u32) -> usize {
+    digest.checked_shr(64 - shard_bits).unwrap_or(0) as usize
+}
+
+// sort and de-duplicate digests of a shard, in place, before building it, and
+// account the duplicates in its statistics.
+pub(crate) fn build_dedup_shard<F, B>(
+    digests: &mut Vec<u64>,
+    rng_seed: u64,
+    build: &B,
+) -> Result<(F, BuildStats)>
+where
+    B: Fn(&[u64], u64) -> Result<(F, BuildStats)>,
+{
+    let (num_digests, start) = (digests.len(), Instant::now());
+    digests.sort_unstable();
+    digests.dedup();
+    let dedup_time = start.elapsed();
+
+    let (filter, mut stats) = build_shard(digests, rng_seed, build)?;
+    let num_duplicates = num_digests - digests.len();
+    stats.num_digests += num_duplicates;
+    stats.num_duplicates += num_duplicates;
+    stats.dedup_time += dedup_time;
+    Ok((filter, stats))
+}
+
+fn build_shard<F, B>(
+    digests: &[u64],
+    rng_seed: u64,
+    build: &B,
+) -> Result<(F, BuildStats)>
+where
+    B: Fn(&[u64], u64) -> Result<(F, BuildStats)>,
+{
+    if digests.len() > (u32::MAX as usize) {
+        Err(Error::CapacityExceeded {
+            keys: digests.len(),
+            capacity: u32::MAX as usize,
+        })
+    } else {
+        build(digests, rng_seed)
+    }
+}
+
+//------ Implement cbordata related functionalities
+
+// Intermediate type to serialize and de-serialized ShardedFilter into bytes.
+#[cfg(feature = "cbordata")]
+#[derive(Cborize)]
+struct CborShardedFilter {
+    hash_builder: Vec<u8>,
+    shard_bits: u32,
+    shards: Vec<Cbor>,
+}
+
+#[cfg(feature = "cbordata")]
+impl CborShardedFilter {
+    const ID: &'static str = "shardedfilter/0.0.1";
+}
+
+#[cfg(feature = "cbordata")]
+impl<F, H> IntoCbor for ShardedFilter<F, H>
+where
+    F: Filter + IntoCbor,
+    H: BuildHasher + Into<Vec<u8>>,
+{
+    fn into_cbor(self) -> cbor::Result<Cbor> {
+        let mut shards = vec![];
+        for shard in self.shards.into_iter() {
+            shards.push(shard.into_cbor()?);
+        }
+
+        let val = CborShardedFilter {
+            hash_builder: self.hash_builder.into(),
+            shard_bits: self.shard_bits,
+            shards,
+        };
+        val.into_cbor()
+    }
+}
+
+#[cfg(feature = "cbordata")]
+impl<F, H> FromCbor for ShardedFilter<F, H>
+where
+    F: Filter + FromCbor,
+    H: BuildHasher + From<Vec<u8>>,
+{
+    fn from_cbor(val: Cbor) -> cbor::Result<Self> {
+        let val = CborShardedFilter::from_cbor(val)?;
+
+        if val.shard_bits > MAX_SHARD_BITS || val.shards.len() != (1 << val.shard_bits) {
+            let msg =
+                format!("shard_bits:{} shards:{}", val.shard_bits, val.shards.len());
+            return Err(cbor::Error::FailConvert(
+                format!("{}:{}", file!(), line!()),
+                msg,
+            ));
+        }
+
+        let mut shards = vec![];
+        for shard in val.shards.into_iter() {
+            shards.push(F::from_cbor(shard)?);
+        }
+
+        let filter = ShardedFilter {
+            hash_builder: val.hash_builder.into(),
+            shard_bits: val.shard_bits,
+            shards,
+        };
+
+        Ok(filter)
+    }
+}
+
+#[cfg(test)]
+#[path = "sharded_test.rs"]
+mod sharded_test;
@@ -0,0 +1,302 @@
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+
+#[cfg(feature = "cbordata")]
+use cbordata::FromCbor;
+#[cfg(feature = "cbordata")]
+use cbordata::IntoCbor;
+use rand::prelude::random;
+use rand::rngs::StdRng;
+use rand::Rng;
+use rand::SeedableRng;
+
+use super::*;
+use crate::fuse16::Fuse16;
+use crate::fuse16::Fuse16Builder;
+use crate::fuse8::Fuse8;
+use crate::fuse8::Fuse8Builder;
+use crate::NoHash;
+
+fn build_fuse8(digests: &[u64], rng_seed: u64) -> Result<Fuse8<NoHash>> {
+    let mut shard = Fuse8Builder::with_hasher(NoHash);
+    shard.rng_seed = rng_seed;
+    shard.build_from_digests(digests)
+}
+
+fn build_fuse16(digests: &[u64], rng_seed: u64) -> Result<Fuse16<NoHash>> {
+    let mut shard = Fuse16Builder::with_hasher(NoHash);
+    shard.rng_seed = rng_seed;
+    shard.build_from_digests(digests)
+}
+
+fn test_sharded_build<H, F, B>(
+    name: &str,
+    seed: u64,
+    shard_bits: u32,
+    size: usize,
+    fpp: f64,
+    build: B,
+) where
+    H: BuildHasher + Clone + Default,
+    F: Filter,
+    B: Fn(&[u64], u64) -> Result<F> + Copy,
+{
+    println!(
+        "test_sharded_build<{}> shard_bits:{} size:{}",
+        name, shard_bits, size
+    );
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let keys: Vec<u64> = (0..size).map(|_| rng.gen::<u64>()).collect();
+    let (keys1, keys2) = keys.split_at(size / 2);
+
+    let mut builder = ShardedFilterBuilder::<H>::new(shard_bits);
+    // populate api
+    builder.populate(keys1);
+    // populate_digests api
+    let digests: Vec<u64> = keys2.iter().map(|k| builder.hash(k)).collect();
+    builder.populate_digests(digests.iter());
+    // insert api, duplicates are removed before build.
+    if let Some(key) = keys.first() {
+        builder.insert(key);
+    }
+
+    let filter = builder.build(build).expect("failed build");
+    assert_eq!(filter.shards.len(), 1 << shard_bits);
+    assert_eq!(filter.len(), Some(size));
+    println!(
+        "test_sharded_build<{}> bits per entry {}",
+        name,
+        ((filter.size_of() * 8) as f64) / (size as f64),
+    );
+
+    for key in keys.iter() {
+        assert!(filter.contains(key), "key {} not present", key);
+        let digest = filter.hash(key);
+        let shard = &filter.shards[filter.shard_of(digest)];
+        assert!(
+            shard.contains_digest(digest),
+            "digest {} not in shard",
+            digest
+        );
+    }
+
+    let (falsesize, mut matches) = (1_000_000, 0_f64);
+    for _ in 0..falsesize {
+        if filter.contains(&rng.gen::<u64>()) {
+            matches += 1_f64;
+        }
+    }
+    let rate = matches / (falsesize as f64);
+    println!("test_sharded_build<{}> false positive rate {}", name, rate);
+    // allow for few more matches, when fpp is too small to be sampled accurately.
+    let limit = (fpp * 1.5) + (10.0 / (falsesize as f64));
+    assert!(rate < limit, "rate({}) >= {}", rate, limit);
+
+    // build_from_digests api, routes digests the same way.
+    let digests: Vec<u64> = keys.iter().map(|k| filter.hash(k)).collect();
+    let filter = builder.build_from_digests(&digests, build).expect("failed build");
+    assert_eq!(filter.len(), Some(size));
+    for digest in digests.iter() {
+        assert!(
+            filter.contains_key(*digest),
+            "digest {} not present",
+            digest
+        );
+    }
+}
+
+#[test]
+fn test_sharded() {
+    let mut seed: u64 = random();
+    println!("test_sharded seed:{}", seed);
+
+    for (shard_bits, size) in [(0, 100_000), (4, 1_000), (8, 1_000_000)].iter() {
+        seed = seed.wrapping_add(*size as u64);
+        test_sharded_build::<RandomState, _, _>(
+            "RandomState-Fuse8",
+            seed,
+            *shard_bits,
+            *size,
+            1.0 / 256.0,
+            build_fuse8,
+        );
+        test_sharded_build::<BuildHasherDefault, _, _>(
+            "BuildHasherDefault-Fuse16",
+            seed,
+            *shard_bits,
+            *size,
+            1.0 / 65536.0,
+            build_fuse16,
+        );
+    }
+}
+
+#[test]
+#[should_panic]
+fn test_sharded_bits() {
+    ShardedFilterBuilder::<BuildHasherDefault>::new(MAX_SHARD_BITS + 1);
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn test_sharded_parallel() {
+    let seed: u64 = random();
+    println!("test_sharded_parallel seed:{}", seed);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let keys: Vec<u64> = (0..1_000_000).map(|_| rng.gen::<u64>()).collect();
+
+    let mut builder = ShardedFilterBuilder::<BuildHasherDefault>::new(6);
+    builder.populate(&keys);
+    let serial = builder.clone().build(build_fuse8).expect("failed build");
+    let parallel = builder.build_parallel(build_fuse8).expect("failed build");
+
+    assert_eq!(parallel.len(), Some(keys.len()));
+    for (a, b) in serial.shards.iter().zip(parallel.shards.iter()) {
+        assert_eq!(a.seed, b.seed);
+        assert_eq!(a.finger_prints, b.finger_prints);
+    }
+    for key in keys.iter() {
+        assert!(parallel.contains(key), "key {} not present", key);
+    }
+}
+
+#[test]
+fn test_sharded_rebuild() {
+    let seed: u64 = random();
+    println!("test_sharded_rebuild seed:{}", seed);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let keys: Vec<u64> = (0..100_000).map(|_| rng.gen::<u64>()).collect();
+
+    // fail a shard after the shards before it are built.
+    let failing = |digests: &[u64], rng_seed| match digests.first() {
+        Some(digest) if shard_of(*digest, 4) == 10 => Err(Error::TooManyIterations {
+            attempts: 100,
+            duplicates: 0,
+        }),
+        _ => build_fuse8(digests, rng_seed),
+    };
+
+    let mut builder = ShardedFilterBuilder::<BuildHasherDefault>::new(4);
+    builder.populate(&keys);
+    assert!(builder.build(failing).is_err());
+    #[cfg(feature = "parallel")]
+    assert!(builder.build_parallel(failing).is_err());
+
+    builder.set_random_seed();
+    let filter = builder.build(build_fuse8).expect("failed build");
+    assert_eq!(filter.len(), Some(keys.len()));
+    for key in keys.iter() {
+        assert!(filter.contains(key), "key {} not present", key);
+    }
+
+    // digests are released after a successful build.
+    let filter = builder.build(build_fuse8).expect("failed build");
+    assert_eq!(filter.len(), Some(0));
+}
+
+#[test]
+fn test_sharded_build_stats() {
+    let seed: u64 = random();
+    println!("test_sharded_build_stats seed:{}", seed);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let keys: Vec<u64> = (0..100_000).map(|_| rng.gen::<u64>()).collect();
+
+    let build = |digests: &[u64], rng_seed| {
+        let mut shard = Fuse8Builder::with_hasher(NoHash);
+        shard.rng_seed = rng_seed;
+        shard.build_from_digests_with_stats(digests)
+    };
+
+    let mut builder = ShardedFilterBuilder::<BuildHasherDefault>::new(4);
+    builder.populate(&keys);
+    builder.populate(&keys[..1000]);
+    #[cfg(feature = "parallel")]
+    let (parallel, parallel_stats) =
+        builder.clone().build_parallel_with_stats(build).expect("failed build");
+    let (filter, stats) = builder.build_with_stats(build).expect("failed build");
+    println!("test_sharded_build_stats {}", stats);
+
+    let array_length: usize = filter.shards.iter().map(|s| s.finger_prints.len()).sum();
+    assert_eq!(stats.num_digests, keys.len() + 1000);
+    assert_eq!(stats.num_unique, keys.len());
+    assert_eq!(stats.num_duplicates, 1000);
+    assert_eq!(stats.seed, filter.shards[0].seed);
+    assert_eq!(stats.array_length, array_length);
+    assert!(stats.bits_per_key > 8.0 && stats.bits_per_key < 12.0);
+
+    #[cfg(feature = "parallel")]
+    {
+        assert_eq!(parallel.len(), filter.len());
+        assert_eq!(parallel_stats.num_digests, stats.num_digests);
+        assert_eq!(parallel_stats.num_duplicates, stats.num_duplicates);
+        assert_eq!(parallel_stats.array_length, stats.array_length);
+    }
+
+    let digests: Vec<u64> = keys.iter().map(|k| builder.hash(k)).collect();
+    let (_, stats) =
+        builder.build_from_digests_with_stats(&digests, build).expect("failed build");
+    assert_eq!(stats.num_digests, keys.len());
+    assert_eq!(stats.num_unique, keys.len());
+}
+
+#[test]
+fn test_sharded_rng_seed() {
+    let seed: u64 = random();
+    println!("test_sharded_rng_seed seed:{}", seed);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let keys: Vec<u64> = (0..100_000).map(|_| rng.gen::<u64>()).collect();
+
+    let build = |rng_seed: u64| {
+        let mut builder = ShardedFilterBuilder::<BuildHasherDefault>::new(4);
+        builder.rng_seed = rng_seed;
+        builder.populate(&keys);
+        builder.build(build_fuse8).expect("failed build")
+    };
+
+    let (filter1, filter2) = (build(seed), build(seed));
+    for (a, b) in filter1.shards.iter().zip(filter2.shards.iter()) {
+        assert!(a == b);
+    }
+
+    let filter3 = build(seed.wrapping_add(1));
+    for (a, b) in filter1.shards.iter().zip(filter3.shards.iter()) {
+        assert_ne!(a.seed, b.seed);
+    }
+
+    let mut builder = ShardedFilterBuilder::<BuildHasherDefault>::new(4);
+    let rng_seed = builder.set_random_seed();
+    assert_eq!(builder.rng_seed, rng_seed);
+    assert_ne!(builder.set_random_seed(), rng_seed);
+}
+
+#[cfg(feature = "cbordata")]
+#[test]
+fn test_sharded_cbor() {
+    let seed: u64 = random();
+    println!("test_sharded_cbor seed:{}", seed);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let keys: Vec<u64> = (0..100_000).map(|_| rng.gen::<u64>()).collect();
+
+    let filter = {
+        let mut builder = ShardedFilterBuilder::<BuildHasherDefault>::new(6);
+        builder.populate(&keys);
+        builder.build(build_fuse8).expect("failed build")
+    };
+
+    let filter = {
+        let val = filter.into_cbor().unwrap();
+        ShardedFilter::<Fuse8<NoHash>>::from_cbor(val).unwrap()
+    };
+    assert_eq!(filter.shard_bits, 6);
+    assert_eq!(filter.len(), Some(keys.len()));
+
+    for key in keys.iter() {
+        assert!(filter.contains(key), "key {} not present", key);
+    }
+}
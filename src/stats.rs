@@ -0,0 +1,124 @@
+//! Statistics on building a filter.
+//!
+//! Refer to [BuildStats] for details.
+
+use std::fmt;
+use std::time::Duration;
+
+/// Type BuildStats report on a single build of a filter, returned by the
+/// `build_with_stats` family of methods on filter types and their builders.
+/// Builders of cascades and sharded filters report statistics merged across their
+/// levels and shards.
+///
+/// Time spent in each phase is measured with wall-clock time. Counting and
+/// peeling time accumulate across seed retries.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BuildStats {
+    /// Number of input digests, duplicates included.
+    pub num_digests: usize,
+    /// Number of unique digests built into the filter.
+    pub num_unique: usize,
+    /// Number of duplicate digests dropped while building.
+    pub num_duplicates: usize,
+    /// Number of times peeling was retried with a fresh seed.
+    pub seed_retries: usize,
+    /// Seed of the built filter. For builds merged across shards or levels, seed of
+    /// the first shard or level.
+    pub seed: u64,
+    /// Number of fingerprint slots in the built filter.
+    pub array_length: usize,
+    /// Number of fingerprint bits per unique key.
+    pub bits_per_key: f64,
+    /// Time spent hashing keys into digests, by the `populate` method. Keys supplied
+    /// as pre-computed digests, or one at a time via `insert`, are not accounted.
+    /// Xor filter builders de-duplicate digests while populating, and account that
+    /// as hashing time.
+    pub hash_time: Duration,
+    /// Time spent sorting and de-duplicating digests.
+    pub dedup_time: Duration,
+    /// Time spent mapping digests onto slots and counting the keys per slot.
+    pub count_time: Duration,
+    /// Time spent peeling keys off the slots.
+    pub peel_time: Duration,
+    /// Time spent assigning fingerprints to slots.
+    pub assign_time: Duration,
+}
+
+impl fmt::Display for BuildStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "digests:{} unique:{} duplicates:{} seed_retries:{} seed:{:#x}",
+            self.num_digests,
+            self.num_unique,
+            self.num_duplicates,
+            self.seed_retries,
+            self.seed
+        )?;
+        writeln!(
+            f,
+            "array_length:{} bits_per_key:{:.3}",
+            self.array_length, self.bits_per_key
+        )?;
+        write!(
+            f,
+            "hash:{:?} dedup:{:?} count:{:?} peel:{:?} assign:{:?} total:{:?}",
+            self.hash_time,
+            self.dedup_time,
+            self.count_time,
+            self.peel_time,
+            self.assign_time,
+            self.total_time()
+        )
+    }
+}
+
+impl BuildStats {
+    /// Return the time spent across all phases of the build.
+    pub fn total_time(&self) -> Duration {
+        self.hash_time
+            + self.dedup_time
+            + self.count_time
+            + self.peel_time
+            + self.assign_time
+    }
+
+    /// Merge statistics of builds composed into a single index, like shards and
+    /// levels. Counts, array lengths and times are summed, `bits_per_key` is
+    /// averaged over the unique keys, and `seed` is that of the first build. Time
+    /// of builds done in parallel is summed as well, exceeding the wall-clock time.
+    pub(crate) fn merge(stats: &[BuildStats]) -> BuildStats {
+        let mut merged = BuildStats {
+            seed: stats.first().map(|s| s.seed).unwrap_or(0),
+            ..BuildStats::default()
+        };
+        let mut bits = 0.0;
+        for s in stats.iter() {
+            merged.num_digests += s.num_digests;
+            merged.num_unique += s.num_unique;
+            merged.num_duplicates += s.num_duplicates;
+            merged.seed_retries += s.seed_retries;
+            merged.array_length += s.array_length;
+            merged.hash_time += s.hash_time;
+            merged.dedup_time += s.dedup_time;
+            merged.count_time += s.count_time;
+            merged.peel_time += s.peel_time;
+            merged.assign_time += s.assign_time;
+            bits += s.bits_per_key * (s.num_unique as f64);
+        }
+        merged.bits_per_key = match merged.num_unique {
+            0 => 0.0,
+            n => bits / (n as f64),
+        };
+        merged
+    }
+
+    /// Record the size of the built filter, `array_length` slots each `bits` wide.
+    pub(crate) fn set_array(&mut self, array_length: usize, bits: u32) {
+        self.array_length = array_length;
+        self.bits_per_key = match self.num_unique {
+            0 => 0.0,
+            n => ((array_length as f64) * (bits as f64)) / (n as f64),
+        };
+    }
+}
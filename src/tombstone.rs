@@ -0,0 +1,303 @@
+//! Remove keys from an immutable filter, using a tombstone filter.
+//!
+//! Refer to [Tombstoned] for details.
+
+#[allow(unused_imports)]
+use std::collections::hash_map::DefaultHasher;
+#[allow(unused_imports)]
+use std::collections::hash_map::RandomState;
+use std::collections::HashSet;
+use std::hash::BuildHasher;
+use std::hash::Hash;
+use std::hash::Hasher;
+
+#[cfg(feature = "cbordata")]
+use cbordata::Cbor;
+#[cfg(feature = "cbordata")]
+use cbordata::Cborize;
+#[cfg(feature = "cbordata")]
+use cbordata::FromCbor;
+#[cfg(feature = "cbordata")]
+use cbordata::IntoCbor;
+#[cfg(feature = "cbordata")]
+use cbordata::{self as cbor};
+
+use crate::BuildHasherDefault;
+use crate::Filter;
+use crate::GrowableFilter;
+use crate::NoHash;
+use crate::Result;
+
+/// Number of removed keys sealed into the first level of tombstone filter.
+const TOMBSTONE_CAPACITY: usize = 1024;
+
+/// Type Tombstoned wraps an immutable `base` filter, so that keys can be removed
+/// from it until the next rebuild.
+///
+/// Removed keys are inserted into a tombstone filter, a [GrowableFilter], and
+/// lookups report a key as present only if it is present in `base` and not present
+/// in the tombstone filter. Keys that are not present in `base` are not added to the
+/// tombstone filter, since lookups already report them as absent.
+///
+/// Implications on accuracy:
+///
+/// * False positive rate is at most that of `base`, removed keys are reported as absent.
+/// * Without an exact tombstone set, a key that was never removed is reported as absent
+///   when it is a false positive in the tombstone filter, that is, false negatives are
+///   introduced with a rate of at most `fpp`, the false positive rate of the tombstone
+///   filter.
+/// * With an exact tombstone set, every hit on the tombstone filter is confirmed against
+///   the set of removed digests, and there are no false negatives. This costs 8 bytes per
+///   removed key, in addition to the tombstone filter.
+///
+/// Once removed, a key cannot be added back, rebuild `base` instead.
+///
+/// Example:
+/// ```
+/// # use xorfilter::{Fuse8, Tombstoned};
+///
+/// let mut base: Fuse8 = Fuse8::new(3);
+/// base.populate(&["foo", "bar", "baz"]);
+/// base.build().unwrap();
+///
+/// let mut filter: Tombstoned<Fuse8> = Tombstoned::new(base, 0.001, true);
+/// filter.remove("bar").unwrap();
+///
+/// assert!(filter.contains("foo"));
+/// assert!(!filter.contains("bar"));
+/// ```
+///
+/// Digests are computed using `hash_builder`, which shall generate the same digests
+/// as the hasher used to build `base`.
+///
+/// If `RandomState` is used as BuildHasher, `std` has got this to say
+/// > _A particular instance RandomState will create the same instances
+/// > of Hasher, but the hashers created by two different RandomState
+/// > instances are unlikely to produce the same result for the same values._
+///
+/// If [DefaultHasher] is used as BuildHasher, `std` has got this to say,
+/// > _The internal algorithm is not specified, and so its hashes
+/// > should not be relied upon over releases._
+#[derive(Clone)]
+pub struct Tombstoned<F, H = BuildHasherDefault>
+where
+    F: Filter,
+    H: BuildHasher,
+{
+    pub hash_builder: H,
+    pub base: F,
+    tombstones: GrowableFilter<NoHash>,
+    exact: Option<HashSet<u64>>,
+}
+
+impl<F, H> Tombstoned<F, H>
+where
+    F: Filter,
+    H: BuildHasher,
+{
+    /// Wrap `base` filter, with tombstone filter's false positive rate bounded by
+    /// `fpp`, initialized with [BuildHasherDefault]. If `exact` is true, digests of
+    /// removed keys are also held in an exact set.
+    ///
+    /// Panics if `fpp` is not within `2^-31` and `1`.
+    pub fn new(base: F, fpp: f64, exact: bool) -> Self
+    where H: Default {
+        Self::with_hasher(base, fpp, exact, H::default())
+    }
+
+    /// Wrap `base` filter, with tombstone filter's false positive rate bounded by
+    /// `fpp`, initialized with supplied `hasher`. If `exact` is true, digests of
+    /// removed keys are also held in an exact set.
+    ///
+    /// Panics if `fpp` is not within `2^-31` and `1`.
+    pub fn with_hasher(base: F, fpp: f64, exact: bool, hash_builder: H) -> Self {
+        Tombstoned {
+            hash_builder,
+            base,
+            tombstones: GrowableFilter::with_hasher(TOMBSTONE_CAPACITY, fpp, NoHash),
+            exact: if exact {
+                Some(HashSet::default())
+            } else {
+                None
+            },
+        }
+    }
+
+    #[allow(clippy::len_without_is_empty)]
+    /// Return the number of keys in `base`, less the number of removed keys.
+    pub fn len(&self) -> Option<usize> {
+        self.base.len().map(|n| n.saturating_sub(self.removed()))
+    }
+
+    /// Return the number of removed keys. Without an exact tombstone set, removing
+    /// a key more than once might count it more than once.
+    pub fn removed(&self) -> usize {
+        match &self.exact {
+            Some(exact) => exact.len(),
+            None => self.tombstones.len().unwrap_or(0),
+        }
+    }
+
+    /// Return whether removed digests are held in an exact set.
+    pub fn is_exact(&self) -> bool {
+        self.exact.is_some()
+    }
+
+    /// Return the size of index, including `base`.
+    #[inline]
+    pub fn size_of(&self) -> usize {
+        let exact = match &self.exact {
+            Some(exact) => exact.capacity() * std::mem::size_of::<u64>(),
+            None => 0,
+        };
+        std::mem::size_of::<Self>()
+            + self.base.size_of()
+            + self.tombstones.size_of()
+            + exact
+    }
+
+    /// Remove a key, digest for the key shall be generated using the default-hasher
+    /// or via hasher supplied via [Tombstoned::with_hasher] method.
+    pub fn remove<K: ?Sized + Hash>(&mut self, key: &K) -> Result<()> {
+        let digest = self.hash(key);
+        self.remove_digest(digest)
+    }
+
+    /// Remove a key, as pre-computed digest form. Sealing the tombstone filter can
+    /// fail.
+    pub fn remove_digest(&mut self, digest: u64) -> Result<()> {
+        if !self.base.contains_digest(digest) {
+            return Ok(());
+        }
+        if let Some(exact) = &mut self.exact {
+            if !exact.insert(digest) {
+                return Ok(());
+            }
+        }
+        self.tombstones.insert_digest(digest)
+    }
+
+    /// Contains tell you whether the key is likely part of the set, and not removed.
+    /// Refer to [Tombstoned] for false positive and false negative rates.
+    pub fn contains<K: ?Sized + Hash>(&self, key: &K) -> bool {
+        let digest = self.hash(key);
+        self.contains_digest(digest)
+    }
+
+    /// Contains tell you whether the key, as pre-computed digest form, is likely
+    /// part of the set, and not removed.
+    pub fn contains_digest(&self, digest: u64) -> bool {
+        self.base.contains_digest(digest) && !self.is_removed(digest)
+    }
+
+    pub fn get_hasher(&self) -> H::Hasher {
+        self.hash_builder.build_hasher()
+    }
+
+    /// Calculate hash of a key.
+    #[inline]
+    pub fn hash<K: Hash + ?Sized>(&self, key: &K) -> u64 {
+        let mut hasher = self.get_hasher();
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn is_removed(&self, digest: u64) -> bool {
+        if !self.tombstones.contains_digest(digest) {
+            return false;
+        }
+        match &self.exact {
+            Some(exact) => exact.contains(&digest),
+            None => true,
+        }
+    }
+}
+
+impl<F, H> Filter for Tombstoned<F, H>
+where
+    F: Filter,
+    H: BuildHasher,
+{
+    fn contains_digest(&self, digest: u64) -> bool {
+        self.contains_digest(digest)
+    }
+
+    fn len(&self) -> Option<usize> {
+        self.len()
+    }
+
+    fn size_of(&self) -> usize {
+        self.size_of()
+    }
+}
+
+//------ Implement cbordata related functionalities
+
+// Intermediate type to serialize and de-serialized Tombstoned into bytes, base and
+// tombstone filters are nested as a two item list.
+#[cfg(feature = "cbordata")]
+#[derive(Cborize)]
+struct CborTombstoned {
+    hash_builder: Vec<u8>,
+    filters: Vec<Cbor>,
+    exact: Option<Vec<u64>>,
+}
+
+#[cfg(feature = "cbordata")]
+impl CborTombstoned {
+    const ID: &'static str = "tombstoned/0.0.1";
+}
+
+#[cfg(feature = "cbordata")]
+impl<F, H> IntoCbor for Tombstoned<F, H>
+where
+    F: Filter + IntoCbor,
+    H: BuildHasher + Into<Vec<u8>>,
+{
+    fn into_cbor(self) -> cbor::Result<Cbor> {
+        let val = CborTombstoned {
+            hash_builder: self.hash_builder.into(),
+            filters: vec![self.base.into_cbor()?, self.tombstones.into_cbor()?],
+            exact: self.exact.map(|exact| exact.into_iter().collect()),
+        };
+        val.into_cbor()
+    }
+}
+
+#[cfg(feature = "cbordata")]
+impl<F, H> FromCbor for Tombstoned<F, H>
+where
+    F: Filter + FromCbor,
+    H: BuildHasher + From<Vec<u8>>,
+{
+    fn from_cbor(val: Cbor) -> cbor::Result<Self> {
+        let val = CborTombstoned::from_cbor(val)?;
+
+        let mut filters = val.filters.into_iter();
+        let (base, tombstones) = match (filters.next(), filters.next(), filters.next()) {
+            (Some(base), Some(tombstones), None) => {
+                (F::from_cbor(base)?, GrowableFilter::from_cbor(tombstones)?)
+            }
+            _ => {
+                let msg = "expected base and tombstone filters".to_string();
+                return Err(cbor::Error::FailConvert(
+                    format!("{}:{}", file!(), line!()),
+                    msg,
+                ));
+            }
+        };
+
+        let filter = Tombstoned {
+            hash_builder: val.hash_builder.into(),
+            base,
+            tombstones,
+            exact: val.exact.map(|exact| exact.into_iter().collect()),
+        };
+
+        Ok(filter)
+    }
+}
+
+#[cfg(test)]
+#[path = "tombstone_test.rs"]
+mod tombstone_test;
@@ -0,0 +1,148 @@
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+
+#[cfg(feature = "cbordata")]
+use cbordata::FromCbor;
+#[cfg(feature = "cbordata")]
+use cbordata::IntoCbor;
+use rand::prelude::random;
+use rand::rngs::StdRng;
+use rand::Rng;
+use rand::SeedableRng;
+
+use super::*;
+use crate::fuse8::Fuse8Builder;
+
+fn test_tombstone_remove<H>(
+    name: &str,
+    seed: u64,
+    size: usize,
+    removes: usize,
+    exact: bool,
+) where
+    H: BuildHasher + Clone + Default,
+{
+    println!(
+        "test_tombstone_remove<{}> size:{} removes:{} exact:{}",
+        name, size, removes, exact
+    );
+    let mut rng = StdRng::seed_from_u64(seed);
+    let fpp = 0.001;
+
+    let keys: Vec<u64> = (0..size).map(|_| rng.gen::<u64>()).collect();
+    let (removed, kept) = keys.split_at(removes);
+
+    let mut builder = Fuse8Builder::<H>::new();
+    builder.populate(&keys);
+    let base = builder.build().expect("failed build");
+
+    // tombstones shall hash keys the same way as base filter.
+    let hash_builder = base.hash_builder.clone();
+    let mut filter = Tombstoned::with_hasher(base, fpp, exact, hash_builder);
+    assert_eq!(filter.is_exact(), exact);
+    // remove api
+    for key in removed.iter().take(removes / 2) {
+        filter.remove(key).expect("failed remove");
+    }
+    // remove_digest api, removing a key again is harmless.
+    for key in removed.iter() {
+        let digest = filter.hash(key);
+        filter.remove_digest(digest).expect("failed remove_digest");
+    }
+    if exact {
+        assert_eq!(filter.removed(), removes);
+    }
+    assert_eq!(filter.len(), Some(size - filter.removed()));
+
+    for key in removed.iter() {
+        assert!(!filter.contains(key), "removed key {} present", key);
+    }
+
+    let mut misses = 0_f64;
+    for key in kept.iter() {
+        if !filter.contains(key) {
+            misses += 1_f64;
+        }
+    }
+    let rate = misses / (kept.len() as f64);
+    println!(
+        "test_tombstone_remove<{}> false negative rate {} bits per entry {}",
+        name,
+        rate,
+        ((filter.size_of() * 8) as f64) / (size as f64),
+    );
+    if exact {
+        assert!(rate == 0.0, "rate({}) for exact tombstones", rate);
+    } else {
+        assert!(rate < (fpp * 2.0), "rate({}) >= {}", rate, fpp * 2.0);
+    }
+}
+
+#[test]
+fn test_tombstone() {
+    let mut seed: u64 = random();
+    println!("test_tombstone seed:{}", seed);
+
+    for (size, removes) in [(1_000, 10), (100_000, 1_000), (1_000_000, 100_000)].iter() {
+        for exact in [true, false].iter() {
+            seed = seed.wrapping_add(*size as u64);
+            test_tombstone_remove::<RandomState>(
+                "RandomState",
+                seed,
+                *size,
+                *removes,
+                *exact,
+            );
+            test_tombstone_remove::<BuildHasherDefault>(
+                "BuildHasherDefault",
+                seed,
+                *size,
+                *removes,
+                *exact,
+            );
+        }
+    }
+}
+
+#[cfg(feature = "cbordata")]
+#[test]
+fn test_tombstone_cbor() {
+    let seed: u64 = random();
+    println!("test_tombstone_cbor seed:{}", seed);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let keys: Vec<u64> = (0..100_000).map(|_| rng.gen::<u64>()).collect();
+    let (removed, kept) = keys.split_at(5_000);
+
+    for exact in [true, false].iter() {
+        let filter = {
+            let mut builder = Fuse8Builder::<BuildHasherDefault>::new();
+            builder.populate(&keys);
+            let base = builder.build().expect("failed build");
+
+            let mut filter = Tombstoned::<crate::fuse8::Fuse8>::new(base, 0.001, *exact);
+            for key in removed.iter() {
+                filter.remove(key).expect("failed remove");
+            }
+            filter
+        };
+        let (n, contains): (Option<usize>, Vec<bool>) = (
+            filter.len(),
+            kept.iter().map(|k| filter.contains(k)).collect(),
+        );
+
+        let filter = {
+            let val = filter.into_cbor().unwrap();
+            Tombstoned::<crate::fuse8::Fuse8>::from_cbor(val).unwrap()
+        };
+        assert_eq!(filter.is_exact(), *exact);
+        assert_eq!(filter.len(), n);
+
+        for key in removed.iter() {
+            assert!(!filter.contains(key), "removed key {} present", key);
+        }
+        for (key, present) in kept.iter().zip(contains) {
+            assert_eq!(filter.contains(key), present, "key {}", key);
+        }
+    }
+}
@@ -0,0 +1,33 @@
+//! Xor16 filter, 3-wise xor filter with 16-bit fingerprints.
+//!
+//! Uses the same construction as [crate::xor8], trading twice the memory for a false
+//! positive rate of about 0.0015%.
+
+use crate::xor8::XorBuilder;
+use crate::xor8::XorFilter;
+use crate::BuildHasherDefault;
+
+/// Type Xor16 is an [XorFilter] with 16-bit fingerprints.
+///
+/// This implementation has a false positive rate of about 0.0015% and a memory usage
+/// of less than 20 bits per entry for sizeable sets.
+pub type Xor16<H = BuildHasherDefault> = XorFilter<u16, H>;
+
+/// Builds an [Xor16] filter.
+///
+/// Example:
+/// ```
+/// # use xorfilter::xor16::Xor16Builder;
+///
+/// let mut b: Xor16Builder = Xor16Builder::new();
+///
+/// b.populate(&["foo", "bar"]);
+/// let filter = b.build().unwrap();
+///
+/// assert!(filter.contains("foo"));
+/// ```
+pub type Xor16Builder<H = BuildHasherDefault> = XorBuilder<u16, H>;
+
+#[cfg(test)]
+#[path = "xor16_test.rs"]
+mod xor16_test;
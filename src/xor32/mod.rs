@@ -0,0 +1,34 @@
+//! Xor32 filter, 3-wise xor filter with 32-bit fingerprints.
+//!
+//! Uses the same construction as [crate::xor8], trading four times the memory for a
+//! false positive rate of about 2^-32, for applications that can barely tolerate
+//! false positives.
+
+use crate::xor8::XorBuilder;
+use crate::xor8::XorFilter;
+use crate::BuildHasherDefault;
+
+/// Type Xor32 is an [XorFilter] with 32-bit fingerprints.
+///
+/// This implementation has a false positive rate of about 2^-32 and a memory usage
+/// of less than 40 bits per entry for sizeable sets.
+pub type Xor32<H = BuildHasherDefault> = XorFilter<u32, H>;
+
+/// Builds an [Xor32] filter.
+///
+/// Example:
+/// ```
+/// # use xorfilter::xor32::Xor32Builder;
+///
+/// let mut b: Xor32Builder = Xor32Builder::new();
+///
+/// b.populate(&["foo", "bar"]);
+/// let filter = b.build().unwrap();
+///
+/// assert!(filter.contains("foo"));
+/// ```
+pub type Xor32Builder<H = BuildHasherDefault> = XorBuilder<u32, H>;
+
+#[cfg(test)]
+#[path = "xor32_test.rs"]
+mod xor32_test;
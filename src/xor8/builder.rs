@@ -1,22 +1,48 @@
+use std::borrow::Borrow;
 use std::collections::HashSet;
 use std::hash::BuildHasher;
 use std::hash::Hash;
 use std::hash::Hasher;
+use std::marker::PhantomData;
 use std::sync::Arc;
-
-use crate::xor8::filter::fingerprint;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::hasher::random_seed;
+use crate::packed::Slots;
+use crate::xor8::filter::get_h0;
+use crate::xor8::filter::get_h0h1h2;
+use crate::xor8::filter::get_h1;
+use crate::xor8::filter::get_h2;
 use crate::xor8::filter::splitmix64;
+use crate::xor8::filter::XorFilter;
 use crate::xor8::filter::XorSet;
-use crate::xor8::Xor8;
+use crate::xor8plus::Xor8Plus;
 use crate::BuildHasherDefault;
+use crate::BuildStats;
+use crate::Error;
+use crate::Fingerprint;
+
+// Maximum number of seeds to try, before giving up on peeling.
+const XOR_MAX_ITERATIONS: usize = 100;
+
+/// Default seed for the random number generator drawing seeds while building xor
+/// filters.
+pub(crate) const XOR_RNG_SEED: u64 = 1;
+
+/// Maximum number of keys in a xor filter, such that its `1.23` slots per key, plus
+/// 32, can be indexed with `u32`.
+pub(crate) const XOR_MAX_KEYS: usize = ((u32::MAX as u64 - 33) * 100 / 123) as usize;
 
 #[derive(Clone, Copy, Default)]
-struct KeyIndex {
-    hash: u64,
-    index: u32,
+pub(crate) struct KeyIndex {
+    pub(crate) hash: u64,
+    pub(crate) index: u32,
 }
 
-/// Builds an Xor8 filter.
+/// Builds an [XorFilter], parametrized over fingerprint type `F`, refer to
+/// [Fingerprint]. Use the aliases [Xor8Builder], [crate::xor16::Xor16Builder] and
+/// [crate::xor32::Xor32Builder] for 8, 16 and 32 bit fingerprints.
 ///
 /// Example:
 /// ```
@@ -30,44 +56,69 @@ struct KeyIndex {
 /// assert!(filter.contains("foo"));
 /// ```
 #[derive(Clone, Debug)]
-pub struct Xor8Builder<H = BuildHasherDefault>
-where H: BuildHasher + Clone
+pub struct XorBuilder<F, H = BuildHasherDefault>
+where
+    F: Fingerprint,
+    H: BuildHasher + Clone,
 {
     digests: HashSet<u64>,
+    hash_time: Duration,
     pub num_digests: usize,
     pub hash_builder: H,
+    /// Seed for the random number generator drawing the peeling seeds.
+    pub rng_seed: u64,
+    _fp: PhantomData<F>,
 }
 
-impl<H> Default for Xor8Builder<H>
-where H: BuildHasher + Clone + Default
+/// Builds an [crate::xor8::Xor8] filter.
+pub type Xor8Builder<H = BuildHasherDefault> = XorBuilder<u8, H>;
+
+impl<F, H> Default for XorBuilder<F, H>
+where
+    F: Fingerprint,
+    H: BuildHasher + Clone + Default,
 {
     fn default() -> Self {
         Self {
             digests: Default::default(),
+            hash_time: Duration::default(),
             num_digests: 0,
             hash_builder: H::default(),
+            rng_seed: XOR_RNG_SEED,
+            _fp: PhantomData,
         }
     }
 }
 
-impl<H> Xor8Builder<H>
-where H: BuildHasher + Clone
+impl<F, H> XorBuilder<F, H>
+where
+    F: Fingerprint,
+    H: BuildHasher + Clone,
 {
-    /// New Xor8 builder initialized with [BuildHasherDefault].
+    /// New xor filter builder initialized with [BuildHasherDefault].
     pub fn new() -> Self
     where H: Default {
         Self::default()
     }
 
-    /// New Xor8 builder initialized with supplied `hasher`.
+    /// New xor filter builder initialized with supplied `hasher`.
     pub fn with_hasher(hash_builder: H) -> Self {
         Self {
             digests: HashSet::new(),
+            hash_time: Duration::default(),
             num_digests: 0,
             hash_builder,
+            rng_seed: XOR_RNG_SEED,
+            _fp: PhantomData,
         }
     }
 
+    /// Seed the builds with a random `rng_seed`, and return the same.
+    pub fn set_random_seed(&mut self) -> u64 {
+        self.rng_seed = random_seed();
+        self.rng_seed
+    }
+
     pub fn get_hasher(&self) -> H::Hasher {
         self.hash_builder.build_hasher()
     }
@@ -83,7 +134,7 @@ where H: BuildHasher + Clone
     /// Insert 64-bit digest of a single key.
     ///
     /// Digest for the key shall be generated using the default-hasher or via hasher
-    /// supplied via [Xor8Builder::with_hasher] method.
+    /// supplied via [XorBuilder::with_hasher] method.
     pub fn insert<K: ?Sized + Hash>(&mut self, key: &K) {
         let digest = self.hash(key);
 
@@ -91,245 +142,407 @@ where H: BuildHasher + Clone
         self.num_digests += 1;
     }
 
-    /// Populate with 64-bit digests for a collection of keys of type `K`.
+    /// Populate with 64-bit digests for a collection of keys, owned or
+    /// borrowed.
     ///
     /// Digest for key shall be generated using the default-hasher or via hasher supplied
-    /// via [Xor8Builder::with_hasher] method.
-    pub fn populate<'i, K: Hash + 'i, I: IntoIterator<Item = &'i K>>(&mut self, keys: I) {
-        let mut n = 0;
+    /// via [XorBuilder::with_hasher] method.
+    pub fn populate<I>(&mut self, keys: I)
+    where
+        I: IntoIterator,
+        I::Item: Hash,
+    {
+        let (mut n, start) = (0, Instant::now());
 
         for key in keys.into_iter() {
             n += 1;
 
-            let digest = self.hash(key);
+            let digest = self.hash(&key);
             self.digests.insert(digest);
         }
 
         self.num_digests += n;
+        self.hash_time += start.elapsed();
     }
 
     /// Populate with pre-compute collection of 64-bit digests.
-    pub fn populate_digests<'i, I: IntoIterator<Item = &'i u64>>(&mut self, digests: I) {
+    pub fn populate_digests<I>(&mut self, digests: I)
+    where
+        I: IntoIterator,
+        I::Item: Borrow<u64>,
+    {
         let mut n = 0;
 
         for digest in digests.into_iter() {
             n += 1;
-            self.digests.insert(*digest);
+            self.digests.insert(*digest.borrow());
         }
 
         self.num_digests += n;
     }
 
-    /// Build bitmap for keys that where previously inserted using [Xor8Builder::insert],
-    /// [Xor8Builder::populate] and [Xor8Builder::populate_digests] method.
-    pub fn build(&mut self) -> Result<Xor8<H>, crate::Error> {
+    /// Build bitmap for keys that where previously inserted using [XorBuilder::insert],
+    /// [XorBuilder::populate] and [XorBuilder::populate_digests] method.
+    pub fn build(&mut self) -> Result<XorFilter<F, H>, crate::Error> {
+        self.build_with_stats().map(|(filter, _)| filter)
+    }
+
+    /// Same as [XorBuilder::build], and return statistics on the build.
+    pub fn build_with_stats(
+        &mut self,
+    ) -> Result<(XorFilter<F, H>, BuildStats), crate::Error> {
         let digests = self.digests.iter().copied().collect::<Vec<u64>>();
-        self.build_from_digests(&digests)
+
+        let (filter, mut stats) = self.build_from_digests_with_stats(&digests)?;
+        stats.num_digests = self.num_digests;
+        stats.num_duplicates = self.num_digests - stats.num_unique;
+        stats.hash_time = self.hash_time;
+        Ok((filter, stats))
     }
 
     /// Build a bitmap for pre-computed 64-bit digests for keys.
     ///
-    /// If keys where previously inserted using [Xor8Builder::insert] or
-    /// [Xor8Builder::populate] or [Xor8Builder::populate_digests] methods, they shall be
+    /// If keys where previously inserted using [XorBuilder::insert] or
+    /// [XorBuilder::populate] or [XorBuilder::populate_digests] methods, they shall be
     /// ignored.
     ///
-    /// It is upto the caller to ensure that digests are unique, that there no duplicates.
+    /// It is upto the caller to ensure that digests are unique, that there no duplicates,
+    /// duplicates fail the build with [crate::Error::TooManyIterations]. Filters are
+    /// sized with `u32`, at `1.23` slots per key more than about `3.49e9` digests
+    /// fail the build with [crate::Error::CapacityExceeded].
     pub fn build_from_digests(
         &mut self,
         digests: &[u64],
-    ) -> Result<Xor8<H>, crate::Error> {
-        let mut ff = Xor8::<H>::new(self.hash_builder.clone());
+    ) -> Result<XorFilter<F, H>, crate::Error> {
+        self.build_from_digests_with_stats(digests).map(|(filter, _)| filter)
+    }
+
+    /// Same as [XorBuilder::build_from_digests], and return statistics on the build.
+    pub fn build_from_digests_with_stats(
+        &mut self,
+        digests: &[u64],
+    ) -> Result<(XorFilter<F, H>, BuildStats), crate::Error> {
+        self.build_peeled(digests, false)
+    }
+
+    fn build_peeled(
+        &mut self,
+        digests: &[u64],
+        lower_first: bool,
+    ) -> Result<(XorFilter<F, H>, BuildStats), crate::Error> {
+        if digests.len() > XOR_MAX_KEYS {
+            return Err(Error::CapacityExceeded {
+                keys: digests.len(),
+                capacity: XOR_MAX_KEYS,
+            });
+        }
+
+        let mut ff = XorFilter::<F, H>::new(self.hash_builder.clone());
 
         ff.num_keys = Some(digests.len());
-        let (size, mut rngcounter) = (digests.len(), 1_u64);
-        let capacity = {
-            let capacity = 32 + ((1.23 * (size as f64)).ceil() as u32);
-            capacity / 3 * 3 // round it down to a multiple of 3
-        };
-        ff.seed = splitmix64(&mut rngcounter);
-        ff.block_length = capacity / 3;
-        ff.finger_prints = Arc::new(vec![u8::default(); capacity as usize]);
-
-        let block_length = ff.block_length as usize;
-        let mut q0: Vec<KeyIndex> = Vec::with_capacity(block_length);
-        let mut q1: Vec<KeyIndex> = Vec::with_capacity(block_length);
-        let mut q2: Vec<KeyIndex> = Vec::with_capacity(block_length);
-        let mut stack: Vec<KeyIndex> = Vec::with_capacity(size);
-        let mut sets0: Vec<XorSet> = vec![XorSet::default(); block_length];
-        let mut sets1: Vec<XorSet> = vec![XorSet::default(); block_length];
-        let mut sets2: Vec<XorSet> = vec![XorSet::default(); block_length];
-
-        loop {
-            for key in digests.iter() {
-                let hs = ff.get_h0h1h2(*key);
-                sets0[hs.h0 as usize].xor_mask ^= hs.h;
-                sets0[hs.h0 as usize].count += 1;
-                sets1[hs.h1 as usize].xor_mask ^= hs.h;
-                sets1[hs.h1 as usize].count += 1;
-                sets2[hs.h2 as usize].xor_mask ^= hs.h;
-                sets2[hs.h2 as usize].count += 1;
+        ff.block_length = xor_block_length(digests.len());
+
+        let mut stats = BuildStats::default();
+        let mut rngcounter = self.rng_seed;
+        let (seed, stack) = xor_peel(
+            digests,
+            ff.block_length,
+            lower_first,
+            &mut rngcounter,
+            &mut stats,
+        )?;
+        ff.seed = seed;
+
+        let start = Instant::now();
+        let mut finger_prints = vec![F::default(); (ff.block_length as usize) * 3];
+        xor_assign(
+            &stack,
+            ff.block_length,
+            finger_prints.as_mut_slice(),
+            F::from_hash,
+        );
+        stats.assign_time = start.elapsed();
+        stats.set_array(finger_prints.len(), F::BITS);
+        ff.finger_prints = Arc::new(finger_prints);
+
+        Ok((ff, stats))
+    }
+}
+
+impl<H> XorBuilder<u8, H>
+where H: BuildHasher + Clone
+{
+    /// Build an [Xor8Plus] filter for keys that where previously inserted using
+    /// [XorBuilder::insert], [XorBuilder::populate] and [XorBuilder::populate_digests]
+    /// method. Keys are peeled from lower blocks in preference, leaving the third block
+    /// mostly empty for [Xor8Plus] to compress.
+    pub fn build_plus(&mut self) -> Result<Xor8Plus<H>, crate::Error> {
+        let digests = self.digests.iter().copied().collect::<Vec<u64>>();
+        self.build_plus_from_digests(&digests)
+    }
+
+    /// Same as [XorBuilder::build_plus], for pre-computed 64-bit digests for keys.
+    ///
+    /// It is upto the caller to ensure that digests are unique, that there no duplicates,
+    /// duplicates fail the build with [crate::Error::TooManyIterations].
+    pub fn build_plus_from_digests(
+        &mut self,
+        digests: &[u64],
+    ) -> Result<Xor8Plus<H>, crate::Error> {
+        let (filter, _) = self.build_peeled(digests, true)?;
+        Ok(Xor8Plus::from(filter))
+    }
+}
+
+/// Length of each of the three blocks for a 3-wise xor filter holding `size` keys,
+/// `size` shall not exceed [XOR_MAX_KEYS].
+pub(crate) fn xor_block_length(size: usize) -> u32 {
+    let capacity = 32 + ((1.23 * (size as f64)).ceil() as u32);
+    let capacity = capacity / 3 * 3; // round it down to a multiple of 3
+    capacity / 3
+}
+
+/// Peel the 3-partite hypergraph formed by `digests`, retrying with a fresh seed
+/// drawn from `rngcounter` until every key is peeled. Return the successful seed
+/// and the peeled keys in peeling order, where `index` locates the slot, across
+/// all three blocks, that is owned by the key.
+///
+/// When `lower_first` is true, keys are peeled from the first block in preference to
+/// the second, and from the second in preference to the third, leaving the third
+/// block mostly empty, refer to [Xor8Plus].
+///
+/// It is upto the caller to ensure that digests are unique, that there no duplicates.
+/// Duplicates fail the peeling with [Error::TooManyIterations]. Counts and timings of
+/// the peeling are recorded in `stats`.
+pub(crate) fn xor_peel(
+    digests: &[u64],
+    block_length: u32,
+    lower_first: bool,
+    rngcounter: &mut u64,
+    stats: &mut BuildStats,
+) -> Result<(u64, Vec<KeyIndex>), Error> {
+    let size = digests.len();
+    let mut seed = splitmix64(rngcounter);
+
+    let bl = block_length as usize;
+    let mut q0: Vec<KeyIndex> = Vec::with_capacity(bl);
+    let mut q1: Vec<KeyIndex> = Vec::with_capacity(bl);
+    let mut q2: Vec<KeyIndex> = Vec::with_capacity(bl);
+    let mut stack: Vec<KeyIndex> = Vec::with_capacity(size);
+    let mut sets0: Vec<XorSet> = vec![XorSet::default(); bl];
+    let mut sets1: Vec<XorSet> = vec![XorSet::default(); bl];
+    let mut sets2: Vec<XorSet> = vec![XorSet::default(); bl];
+
+    let mut attempts = 0;
+    loop {
+        if attempts > XOR_MAX_ITERATIONS {
+            let mut unique = digests.to_vec();
+            unique.sort_unstable();
+            unique.dedup();
+            return Err(Error::TooManyIterations {
+                attempts,
+                duplicates: size - unique.len(),
+            });
+        }
+        attempts += 1;
+
+        let start = Instant::now();
+        for key in digests.iter() {
+            let hs = get_h0h1h2(*key, seed, block_length);
+            sets0[hs.h0 as usize].xor_mask ^= hs.h;
+            sets0[hs.h0 as usize].count += 1;
+            sets1[hs.h1 as usize].xor_mask ^= hs.h;
+            sets1[hs.h1 as usize].count += 1;
+            sets2[hs.h2 as usize].xor_mask ^= hs.h;
+            sets2[hs.h2 as usize].count += 1;
+        }
+        stats.count_time += start.elapsed();
+
+        let start = Instant::now();
+        q0.clear();
+        q1.clear();
+        q2.clear();
+
+        let iter = sets0.iter().enumerate().take(bl);
+        for (i, item) in iter {
+            if item.count == 1 {
+                q0.push(KeyIndex {
+                    index: i as u32,
+                    hash: item.xor_mask,
+                });
             }
+        }
+        let iter = sets1.iter().enumerate().take(bl);
+        for (i, item) in iter {
+            if item.count == 1 {
+                q1.push(KeyIndex {
+                    index: i as u32,
+                    hash: item.xor_mask,
+                });
+            }
+        }
+        let iter = sets2.iter().enumerate().take(bl);
+        for (i, item) in iter {
+            if item.count == 1 {
+                q2.push(KeyIndex {
+                    index: i as u32,
+                    hash: item.xor_mask,
+                });
+            }
+        }
 
-            q0.clear();
-            q1.clear();
-            q2.clear();
+        stack.clear();
 
-            let iter = sets0.iter().enumerate().take(ff.block_length as usize);
-            for (i, item) in iter {
-                if item.count == 1 {
-                    q0.push(KeyIndex {
-                        index: i as u32,
-                        hash: item.xor_mask,
-                    });
+        'peel: while !q0.is_empty() || !q1.is_empty() || !q2.is_empty() {
+            while let Some(keyindexvar) = q0.pop() {
+                if sets0[keyindexvar.index as usize].count == 0 {
+                    // not actually possible after the initial scan.
+                    continue;
                 }
-            }
-            let iter = sets1.iter().enumerate().take(ff.block_length as usize);
-            for (i, item) in iter {
-                if item.count == 1 {
+                let hash = keyindexvar.hash;
+                let h1 = get_h1(hash, block_length);
+                let h2 = get_h2(hash, block_length);
+                stack.push(keyindexvar);
+
+                let s = unsafe { sets1.get_unchecked_mut(h1 as usize) };
+                s.xor_mask ^= hash;
+                s.count -= 1;
+                if s.count == 1 {
                     q1.push(KeyIndex {
-                        index: i as u32,
-                        hash: item.xor_mask,
-                    });
+                        index: h1,
+                        hash: s.xor_mask,
+                    })
                 }
-            }
-            let iter = sets2.iter().enumerate().take(ff.block_length as usize);
-            for (i, item) in iter {
-                if item.count == 1 {
+
+                let s = unsafe { sets2.get_unchecked_mut(h2 as usize) };
+                s.xor_mask ^= hash;
+                s.count -= 1;
+                if s.count == 1 {
                     q2.push(KeyIndex {
-                        index: i as u32,
-                        hash: item.xor_mask,
-                    });
+                        index: h2,
+                        hash: s.xor_mask,
+                    })
                 }
             }
+            while let Some(mut keyindexvar) = q1.pop() {
+                if sets1[keyindexvar.index as usize].count == 0 {
+                    continue;
+                }
+                let hash = keyindexvar.hash;
+                let h0 = get_h0(hash, block_length);
+                let h2 = get_h2(hash, block_length);
+                keyindexvar.index += block_length;
+                stack.push(keyindexvar);
+
+                let s = unsafe { sets0.get_unchecked_mut(h0 as usize) };
+                s.xor_mask ^= hash;
+                s.count -= 1;
+                if s.count == 1 {
+                    q0.push(KeyIndex {
+                        index: h0,
+                        hash: s.xor_mask,
+                    })
+                }
 
-            stack.clear();
-
-            while !q0.is_empty() || !q1.is_empty() || !q2.is_empty() {
-                while let Some(keyindexvar) = q0.pop() {
-                    if sets0[keyindexvar.index as usize].count == 0 {
-                        // not actually possible after the initial scan.
-                        continue;
-                    }
-                    let hash = keyindexvar.hash;
-                    let h1 = ff.get_h1(hash);
-                    let h2 = ff.get_h2(hash);
-                    stack.push(keyindexvar);
-
-                    let mut s = unsafe { sets1.get_unchecked_mut(h1 as usize) };
-                    s.xor_mask ^= hash;
-                    s.count -= 1;
-                    if s.count == 1 {
-                        q1.push(KeyIndex {
-                            index: h1,
-                            hash: s.xor_mask,
-                        })
-                    }
-
-                    let mut s = unsafe { sets2.get_unchecked_mut(h2 as usize) };
-                    s.xor_mask ^= hash;
-                    s.count -= 1;
-                    if s.count == 1 {
-                        q2.push(KeyIndex {
-                            index: h2,
-                            hash: s.xor_mask,
-                        })
-                    }
+                let s = unsafe { sets2.get_unchecked_mut(h2 as usize) };
+                s.xor_mask ^= hash;
+                s.count -= 1;
+                if s.count == 1 {
+                    q2.push(KeyIndex {
+                        index: h2,
+                        hash: s.xor_mask,
+                    })
+                }
+                if lower_first {
+                    continue 'peel;
                 }
-                while let Some(mut keyindexvar) = q1.pop() {
-                    if sets1[keyindexvar.index as usize].count == 0 {
-                        continue;
-                    }
-                    let hash = keyindexvar.hash;
-                    let h0 = ff.get_h0(hash);
-                    let h2 = ff.get_h2(hash);
-                    keyindexvar.index += ff.block_length;
-                    stack.push(keyindexvar);
-
-                    let mut s = unsafe { sets0.get_unchecked_mut(h0 as usize) };
-                    s.xor_mask ^= hash;
-                    s.count -= 1;
-                    if s.count == 1 {
-                        q0.push(KeyIndex {
-                            index: h0,
-                            hash: s.xor_mask,
-                        })
-                    }
-
-                    let mut s = unsafe { sets2.get_unchecked_mut(h2 as usize) };
-                    s.xor_mask ^= hash;
-                    s.count -= 1;
-                    if s.count == 1 {
-                        q2.push(KeyIndex {
-                            index: h2,
-                            hash: s.xor_mask,
-                        })
-                    }
+            }
+            while let Some(mut keyindexvar) = q2.pop() {
+                if sets2[keyindexvar.index as usize].count == 0 {
+                    continue;
+                }
+                let hash = keyindexvar.hash;
+                let h0 = get_h0(hash, block_length);
+                let h1 = get_h1(hash, block_length);
+                keyindexvar.index += 2 * block_length;
+                stack.push(keyindexvar);
+
+                let s = unsafe { sets0.get_unchecked_mut(h0 as usize) };
+                s.xor_mask ^= hash;
+                s.count -= 1;
+                if s.count == 1 {
+                    q0.push(KeyIndex {
+                        index: h0,
+                        hash: s.xor_mask,
+                    })
+                }
+                let s = unsafe { sets1.get_unchecked_mut(h1 as usize) };
+                s.xor_mask ^= hash;
+                s.count -= 1;
+                if s.count == 1 {
+                    q1.push(KeyIndex {
+                        index: h1,
+                        hash: s.xor_mask,
+                    })
                 }
-                while let Some(mut keyindexvar) = q2.pop() {
-                    if sets2[keyindexvar.index as usize].count == 0 {
-                        continue;
-                    }
-                    let hash = keyindexvar.hash;
-                    let h0 = ff.get_h0(hash);
-                    let h1 = ff.get_h1(hash);
-                    keyindexvar.index += 2 * ff.block_length;
-                    stack.push(keyindexvar);
-
-                    let mut s = unsafe { sets0.get_unchecked_mut(h0 as usize) };
-                    s.xor_mask ^= hash;
-                    s.count -= 1;
-                    if s.count == 1 {
-                        q0.push(KeyIndex {
-                            index: h0,
-                            hash: s.xor_mask,
-                        })
-                    }
-                    let mut s = unsafe { sets1.get_unchecked_mut(h1 as usize) };
-                    s.xor_mask ^= hash;
-                    s.count -= 1;
-                    if s.count == 1 {
-                        q1.push(KeyIndex {
-                            index: h1,
-                            hash: s.xor_mask,
-                        })
-                    }
+                if lower_first {
+                    continue 'peel;
                 }
             }
+        }
 
-            if stack.len() == size {
-                break;
-            }
+        stats.peel_time += start.elapsed();
 
-            for item in sets0.iter_mut() {
-                *item = XorSet::default();
-            }
-            for item in sets1.iter_mut() {
-                *item = XorSet::default();
-            }
-            for item in sets2.iter_mut() {
-                *item = XorSet::default();
-            }
-            ff.seed = splitmix64(&mut rngcounter)
+        if stack.len() == size {
+            break;
         }
 
-        while let Some(ki) = stack.pop() {
-            let mut val = fingerprint(ki.hash) as u8;
-            if ki.index < ff.block_length {
-                let h1 = (ff.get_h1(ki.hash) + ff.block_length) as usize;
-                let h2 = (ff.get_h2(ki.hash) + 2 * ff.block_length) as usize;
-                val ^= ff.finger_prints[h1] ^ ff.finger_prints[h2];
-            } else if ki.index < 2 * ff.block_length {
-                let h0 = ff.get_h0(ki.hash) as usize;
-                let h2 = (ff.get_h2(ki.hash) + 2 * ff.block_length) as usize;
-                val ^= ff.finger_prints[h0] ^ ff.finger_prints[h2];
-            } else {
-                let h0 = ff.get_h0(ki.hash) as usize;
-                let h1 = (ff.get_h1(ki.hash) + ff.block_length) as usize;
-                val ^= ff.finger_prints[h0] ^ ff.finger_prints[h1]
-            }
-            Arc::get_mut(&mut ff.finger_prints).unwrap()[ki.index as usize] = val;
+        for item in sets0.iter_mut() {
+            *item = XorSet::default();
+        }
+        for item in sets1.iter_mut() {
+            *item = XorSet::default();
+        }
+        for item in sets2.iter_mut() {
+            *item = XorSet::default();
         }
+        seed = splitmix64(rngcounter)
+    }
+
+    stats.num_digests = size;
+    stats.num_unique = size;
+    stats.seed_retries = attempts - 1;
+    stats.seed = seed;
 
-        Ok(ff)
+    Ok((seed, stack))
+}
+
+/// Assign a value to every slot owned by a peeled key, in reverse peeling order,
+/// such that xor-ing the three slots of a key yields `value(hash)`.
+pub(crate) fn xor_assign<S, F>(
+    stack: &[KeyIndex],
+    block_length: u32,
+    slots: &mut S,
+    value: F,
+) where
+    S: Slots + ?Sized,
+    F: Fn(u64) -> S::Item,
+{
+    for ki in stack.iter().rev() {
+        let mut val = value(ki.hash);
+        if ki.index < block_length {
+            let h1 = (get_h1(ki.hash, block_length) + block_length) as usize;
+            let h2 = (get_h2(ki.hash, block_length) + 2 * block_length) as usize;
+            val = val ^ slots.get_slot(h1) ^ slots.get_slot(h2);
+        } else if ki.index < 2 * block_length {
+            let h0 = get_h0(ki.hash, block_length) as usize;
+            let h2 = (get_h2(ki.hash, block_length) + 2 * block_length) as usize;
+            val = val ^ slots.get_slot(h0) ^ slots.get_slot(h2);
+        } else {
+            let h0 = get_h0(ki.hash, block_length) as usize;
+            let h1 = (get_h1(ki.hash, block_length) + block_length) as usize;
+            val = val ^ slots.get_slot(h0) ^ slots.get_slot(h1);
+        }
+        slots.set_slot(ki.index as usize, val);
     }
 }
@@ -14,10 +14,8 @@ use std::fs;
 use std::hash::BuildHasher;
 use std::hash::Hash;
 use std::hash::Hasher;
-use std::io::ErrorKind;
 use std::io::Read;
 use std::io::Write;
-use std::io::{self};
 use std::sync::Arc;
 
 #[cfg(feature = "cbordata")]
@@ -32,8 +30,11 @@ use cbordata::IntoCbor;
 use cbordata::{self as cbor};
 
 use crate::BuildHasherDefault;
+use crate::Error;
+use crate::Filter;
+use crate::Fingerprint;
 
-pub(in crate::xor8) fn murmur64(mut h: u64) -> u64 {
+pub(crate) fn murmur64(mut h: u64) -> u64 {
     h ^= h >> 33;
     h = h.wrapping_mul(0xff51_afd7_ed55_8ccd);
     h ^= h >> 33;
@@ -43,7 +44,7 @@ pub(in crate::xor8) fn murmur64(mut h: u64) -> u64 {
 }
 
 // returns random number, modifies the seed
-pub(in crate::xor8) fn splitmix64(seed: &mut u64) -> u64 {
+pub(crate) fn splitmix64(seed: &mut u64) -> u64 {
     *seed = (*seed).wrapping_add(0x9E37_79B9_7F4A_7C15);
     let mut z = *seed;
     z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
@@ -51,41 +52,67 @@ pub(in crate::xor8) fn splitmix64(seed: &mut u64) -> u64 {
     z ^ (z >> 31)
 }
 
-pub(in crate::xor8) fn mixsplit(key: u64, seed: u64) -> u64 {
+pub(crate) fn mixsplit(key: u64, seed: u64) -> u64 {
     murmur64(key.wrapping_add(seed))
 }
 
-pub(in crate::xor8) fn reduce(hash: u32, n: u32) -> u32 {
+pub(crate) fn reduce(hash: u32, n: u32) -> u32 {
     // http://lemire.me/blog/2016/06/27/a-fast-alternative-to-the-modulo-reduction/
     (((hash as u64) * (n as u64)) >> 32) as u32
 }
 
-pub(in crate::xor8) fn fingerprint(hash: u64) -> u64 {
+pub(crate) fn fingerprint(hash: u64) -> u64 {
     hash ^ (hash >> 32)
 }
 
+pub(crate) fn get_h0h1h2(k: u64, seed: u64, block_length: u32) -> Hashes {
+    let h = mixsplit(k, seed);
+    Hashes {
+        h,
+        h0: reduce(h as u32, block_length),
+        h1: reduce(h.rotate_left(21) as u32, block_length),
+        h2: reduce(h.rotate_left(42) as u32, block_length),
+    }
+}
+
+pub(crate) fn get_h0(hash: u64, block_length: u32) -> u32 {
+    let r0 = hash as u32;
+    reduce(r0, block_length)
+}
+
+pub(crate) fn get_h1(hash: u64, block_length: u32) -> u32 {
+    let r1 = hash.rotate_left(21) as u32;
+    reduce(r1, block_length)
+}
+
+pub(crate) fn get_h2(hash: u64, block_length: u32) -> u32 {
+    let r2 = hash.rotate_left(42) as u32;
+    reduce(r2, block_length)
+}
+
 #[derive(Clone, Default)]
-pub(in crate::xor8) struct XorSet {
-    pub(in crate::xor8) xor_mask: u64,
-    pub(in crate::xor8) count: u32,
+pub(crate) struct XorSet {
+    pub(crate) xor_mask: u64,
+    pub(crate) count: u32,
 }
 
 #[derive(Default)]
-pub(in crate::xor8) struct Hashes {
-    pub(in crate::xor8) h: u64,
-    pub(in crate::xor8) h0: u32,
-    pub(in crate::xor8) h1: u32,
-    pub(in crate::xor8) h2: u32,
+pub(crate) struct Hashes {
+    pub(crate) h: u64,
+    pub(crate) h0: u32,
+    pub(crate) h1: u32,
+    pub(crate) h2: u32,
 }
 
-/// Type Xor8 is probabilistic data-structure to test membership of an element in a set.
-///
-/// This implementation has a false positive rate of about 0.3% and a memory usage of
-/// less than 9 bits per entry for sizeable sets.
+/// Type XorFilter is probabilistic data-structure to test membership of an element in
+/// a set.
 ///
-/// Xor8 is parametrized over type `H` which is expected to implement [BuildHasher]
-/// trait, like types [RandomState] and [BuildHasherDefault]. When not supplied,
-/// [BuildHasherDefault] is used as the default hash-builder.
+/// XorFilter is parametrized over fingerprint type `F`, refer to [Fingerprint], and
+/// over type `H` which is expected to implement [BuildHasher] trait, like types
+/// [RandomState] and [BuildHasherDefault]. When not supplied, [BuildHasherDefault] is
+/// used as the default hash-builder. Use the aliases [Xor8],
+/// [crate::xor16::Xor16] and [crate::xor32::Xor32] for 8, 16 and 32 bit
+/// fingerprints.
 ///
 /// If `RandomState` is used as BuildHasher, `std` has got this to say
 /// > _A particular instance RandomState will create the same instances
@@ -99,20 +126,30 @@ pub(in crate::xor8) struct Hashes {
 /// The default type for parameter `H` might change when a reliable and commonly used
 /// BuildHasher type is available.
 #[derive(Clone, Debug, Default)]
-pub struct Xor8<H = BuildHasherDefault>
-where H: BuildHasher
+pub struct XorFilter<F, H = BuildHasherDefault>
+where
+    F: Fingerprint,
+    H: BuildHasher,
 {
     pub hash_builder: H,
     pub seed: u64,
     // TODO: Keep `Option` for the compatibility with Cbor format.
-    //       It is always `Some` since we have moved out Xor8Builder to another struct.
+    //       It is always `Some` since we have moved out XorBuilder to another struct.
     pub num_keys: Option<usize>,
     pub block_length: u32,
-    pub finger_prints: Arc<Vec<u8>>,
+    pub finger_prints: Arc<Vec<F>>,
 }
 
-impl<H> PartialEq for Xor8<H>
-where H: BuildHasher
+/// Type Xor8 is an [XorFilter] with 8-bit fingerprints.
+///
+/// This implementation has a false positive rate of about 0.3% and a memory usage of
+/// less than 9 bits per entry for sizeable sets.
+pub type Xor8<H = BuildHasherDefault> = XorFilter<u8, H>;
+
+impl<F, H> PartialEq for XorFilter<F, H>
+where
+    F: Fingerprint,
+    H: BuildHasher,
 {
     fn eq(&self, other: &Self) -> bool {
         let num_keys = match (self.num_keys, other.num_keys) {
@@ -127,8 +164,10 @@ where H: BuildHasher
     }
 }
 
-impl<H> Xor8<H>
-where H: BuildHasher
+impl<F, H> XorFilter<F, H>
+where
+    F: Fingerprint,
+    H: BuildHasher,
 {
     pub(crate) fn new(hash_builder: H) -> Self {
         Self {
@@ -141,8 +180,10 @@ where H: BuildHasher
     }
 }
 
-impl<H> Xor8<H>
-where H: BuildHasher
+impl<F, H> XorFilter<F, H>
+where
+    F: Fingerprint,
+    H: BuildHasher,
 {
     #[allow(clippy::len_without_is_empty)]
     /// Return the number of keys added/built into the bitmap index.
@@ -150,14 +191,17 @@ where H: BuildHasher
         self.num_keys
     }
 
+    /// Return the size of index.
+    #[inline]
+    pub fn size_of(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + (self.finger_prints.len() * std::mem::size_of::<F>())
+    }
+
     /// Contains tell you whether the key is likely part of the set, with false
     /// positive rate.
     pub fn contains<K: ?Sized + Hash>(&self, key: &K) -> bool {
-        let hashed_key = {
-            let mut hasher = self.hash_builder.build_hasher();
-            key.hash(&mut hasher);
-            hasher.finish()
-        };
+        let hashed_key = self.hash_builder.hash_one(key);
         self.contains_digest(hashed_key)
     }
 
@@ -165,7 +209,7 @@ where H: BuildHasher
     /// part of the set, with false positive rate.
     pub fn contains_digest(&self, digest: u64) -> bool {
         let hash = mixsplit(digest, self.seed);
-        let f = fingerprint(hash) as u8;
+        let f = F::from_hash(hash);
         let r0 = hash as u32;
         let r1 = hash.rotate_left(21) as u32;
         let r2 = hash.rotate_left(42) as u32;
@@ -188,41 +232,12 @@ where H: BuildHasher
     }
 }
 
-impl<H> Xor8<H>
-where H: BuildHasher
-{
-    pub(in crate::xor8) fn get_h0h1h2(&self, k: u64) -> Hashes {
-        let h = mixsplit(k, self.seed);
-        Hashes {
-            h,
-            h0: reduce(h as u32, self.block_length),
-            h1: reduce(h.rotate_left(21) as u32, self.block_length),
-            h2: reduce(h.rotate_left(42) as u32, self.block_length),
-        }
-    }
-
-    pub(in crate::xor8) fn get_h0(&self, hash: u64) -> u32 {
-        let r0 = hash as u32;
-        reduce(r0, self.block_length)
-    }
-
-    pub(in crate::xor8) fn get_h1(&self, hash: u64) -> u32 {
-        let r1 = hash.rotate_left(21) as u32;
-        reduce(r1, self.block_length)
-    }
-
-    pub(in crate::xor8) fn get_h2(&self, hash: u64) -> u32 {
-        let r2 = hash.rotate_left(42) as u32;
-        reduce(r2, self.block_length)
-    }
-}
-
 /// Implements serialization and de-serialization logic for Xor8. This is still work
 /// in progress, refer to issue: <https://github.com/bnclabs/xorfilter/issues/1>
 /// in github.
 ///
 /// TODO: <https://github.com/bnclabs/xorfilter/issues/1>
-impl<H> Xor8<H>
+impl<H> XorFilter<u8, H>
 where H: Into<Vec<u8>> + From<Vec<u8>> + BuildHasher
 {
     /// File signature write on first 4 bytes of file.
@@ -242,20 +257,20 @@ where H: Into<Vec<u8>> + From<Vec<u8>> + BuildHasher
 
     /// Write to file in binary format
     /// TODO Add chechsum of finger_prints into file headers
-    pub fn write_file(&self, path: &ffi::OsStr) -> io::Result<usize>
+    pub fn write_file(&self, path: &ffi::OsStr) -> Result<usize, Error>
     where H: Clone {
-        let mut f = fs::File::create(path)?;
+        let mut f = err_at!(IOError, fs::File::create(path))?;
         let buf = self.to_bytes();
-        f.write_all(&buf)?;
+        err_at!(IOError, f.write_all(&buf))?;
         Ok(buf.len())
     }
 
     /// Read from file in binary format
-    pub fn read_file(path: &ffi::OsStr) -> io::Result<Self>
+    pub fn read_file(path: &ffi::OsStr) -> Result<Self, Error>
     where H: Default {
-        let mut f = fs::File::open(path)?;
+        let mut f = err_at!(IOError, fs::File::open(path))?;
         let mut data = Vec::new();
-        f.read_to_end(&mut data)?;
+        err_at!(IOError, f.read_to_end(&mut data))?;
         Self::from_bytes(data)
     }
 
@@ -276,25 +291,20 @@ where H: Into<Vec<u8>> + From<Vec<u8>> + BuildHasher
         buf
     }
 
-    pub fn from_bytes(buf: Vec<u8>) -> io::Result<Self>
+    pub fn from_bytes(buf: Vec<u8>) -> Result<Self, Error>
     where H: Default {
-        use std::io::Error;
-
         let mut n = 0;
 
         // validate the buf first.
         if Self::METADATA_LENGTH > buf.len() {
-            return Err(Error::new(ErrorKind::InvalidData, "invalid byte slice"));
+            return err_at!(InvalidFormat, msg: "invalid byte slice");
         }
 
         // check the signature
         if buf[n..4] == Xor8::<H>::SIGNATURE_V1 {
             return Self::from_bytes_v1(buf);
         } else if buf[n..4] != Xor8::<H>::SIGNATURE_V2 {
-            return Err(Error::new(
-                ErrorKind::InvalidData,
-                "File signature incorrect",
-            ));
+            return err_at!(InvalidFormat, msg: "File signature incorrect");
         }
 
         n += 4;
@@ -312,7 +322,7 @@ where H: Into<Vec<u8>> + From<Vec<u8>> + BuildHasher
         n += 4;
 
         if buf[n..].len() < (fp_len + hb_len) {
-            return Err(Error::new(ErrorKind::InvalidData, "invalid byte slice"));
+            return err_at!(InvalidFormat, msg: "invalid byte slice");
         }
 
         // fetch the finger print
@@ -330,13 +340,11 @@ where H: Into<Vec<u8>> + From<Vec<u8>> + BuildHasher
         })
     }
 
-    fn from_bytes_v1(buf: Vec<u8>) -> io::Result<Self>
+    fn from_bytes_v1(buf: Vec<u8>) -> Result<Self, Error>
     where H: Default {
-        use std::io::Error;
-
         let fp_len = u32::from_be_bytes(buf[16..20].try_into().unwrap()) as usize;
         if buf[20..].len() < fp_len {
-            return Err(Error::new(ErrorKind::InvalidData, "invalid byte slice"));
+            return err_at!(InvalidFormat, msg: "invalid byte slice");
         }
         Ok(Xor8 {
             hash_builder: H::default(),
@@ -348,55 +356,82 @@ where H: Into<Vec<u8>> + From<Vec<u8>> + BuildHasher
     }
 }
 
-//------ Implement cbordata related functionalities
-
-// Intermediate type to serialize and de-serialized Xor8 into bytes.
-#[cfg(feature = "cbordata")]
-#[derive(Cborize)]
-struct CborXor8 {
-    hash_builder: Vec<u8>,
-    seed: u64,
-    num_keys: Option<usize>,
-    block_length: u32,
-    finger_prints: Vec<u8>,
-}
+impl<F, H> Filter for XorFilter<F, H>
+where
+    F: Fingerprint,
+    H: BuildHasher,
+{
+    fn contains_digest(&self, digest: u64) -> bool {
+        self.contains_digest(digest)
+    }
 
-#[cfg(feature = "cbordata")]
-impl CborXor8 {
-    const ID: &'static str = "xor8/0.0.1";
-}
+    fn len(&self) -> Option<usize> {
+        self.num_keys
+    }
 
-#[cfg(feature = "cbordata")]
-impl<H> IntoCbor for Xor8<H>
-where H: BuildHasher + Into<Vec<u8>>
-{
-    fn into_cbor(self) -> cbor::Result<Cbor> {
-        let val = CborXor8 {
-            hash_builder: self.hash_builder.into(),
-            seed: self.seed,
-            num_keys: self.num_keys,
-            block_length: self.block_length,
-            finger_prints: self.finger_prints.to_vec(),
-        };
-        val.into_cbor()
+    fn size_of(&self) -> usize {
+        self.size_of()
     }
 }
 
+//------ Implement cbordata related functionalities
+
+// Intermediate types to serialize and de-serialized XorFilter into bytes, one for
+// each fingerprint width.
 #[cfg(feature = "cbordata")]
-impl<H> FromCbor for Xor8<H>
-where H: BuildHasher + From<Vec<u8>>
-{
-    fn from_cbor(val: Cbor) -> cbor::Result<Self> {
-        let val = CborXor8::from_cbor(val)?;
-
-        let filter = Xor8 {
-            hash_builder: val.hash_builder.into(),
-            seed: val.seed,
-            num_keys: val.num_keys,
-            block_length: val.block_length,
-            finger_prints: Arc::new(val.finger_prints),
-        };
+macro_rules! cbor_xor_filter {
+    ($name:ident, $fp:ty, $id:expr) => {
+        #[derive(Cborize)]
+        struct $name {
+            hash_builder: Vec<u8>,
+            seed: u64,
+            num_keys: Option<usize>,
+            block_length: u32,
+            finger_prints: Vec<$fp>,
+        }
 
-        Ok(filter)
-    }
+        impl $name {
+            const ID: &'static str = $id;
+        }
+
+        impl<H> IntoCbor for XorFilter<$fp, H>
+        where H: BuildHasher + Into<Vec<u8>>
+        {
+            fn into_cbor(self) -> cbor::Result<Cbor> {
+                let val = $name {
+                    hash_builder: self.hash_builder.into(),
+                    seed: self.seed,
+                    num_keys: self.num_keys,
+                    block_length: self.block_length,
+                    finger_prints: self.finger_prints.to_vec(),
+                };
+                val.into_cbor()
+            }
+        }
+
+        impl<H> FromCbor for XorFilter<$fp, H>
+        where H: BuildHasher + From<Vec<u8>>
+        {
+            fn from_cbor(val: Cbor) -> cbor::Result<Self> {
+                let val = $name::from_cbor(val)?;
+
+                let filter = XorFilter {
+                    hash_builder: val.hash_builder.into(),
+                    seed: val.seed,
+                    num_keys: val.num_keys,
+                    block_length: val.block_length,
+                    finger_prints: Arc::new(val.finger_prints),
+                };
+
+                Ok(filter)
+            }
+        }
+    };
 }
+
+#[cfg(feature = "cbordata")]
+cbor_xor_filter!(CborXor8, u8, "xor8/0.0.1");
+#[cfg(feature = "cbordata")]
+cbor_xor_filter!(CborXor16, u16, "xor16/0.0.1");
+#[cfg(feature = "cbordata")]
+cbor_xor_filter!(CborXor32, u32, "xor32/0.0.1");
@@ -1,8 +1,10 @@
-mod builder;
-mod filter;
+pub(crate) mod builder;
+pub(crate) mod filter;
 
 pub use builder::Xor8Builder;
+pub use builder::XorBuilder;
 pub use filter::Xor8;
+pub use filter::XorFilter;
 
 #[cfg(test)]
 #[path = "xor8_test.rs"]
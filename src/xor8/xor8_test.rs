@@ -201,6 +201,102 @@ fn test_xor8() {
     }
 }
 
+#[test]
+fn test_xor8_errors() {
+    use crate::xor8::Xor8;
+    use crate::Error;
+
+    let mut builder = Xor8Builder::<BuildHasherDefault>::new();
+    let digests: Vec<u64> = (0..1000).chain(0..10).collect();
+    match builder.build_from_digests(&digests) {
+        Err(Error::TooManyIterations { duplicates, .. }) => assert_eq!(duplicates, 10),
+        res => panic!("unexpected {:?}", res.err()),
+    }
+
+    builder.populate(0..1000);
+    let filter = builder.build().expect("failed build");
+
+    let mut buf = filter.to_bytes();
+    assert!(Xor8::<BuildHasherDefault>::from_bytes(buf.clone()).is_ok());
+    buf[0] = b'x';
+    let res = Xor8::<BuildHasherDefault>::from_bytes(buf);
+    assert!(
+        matches!(res, Err(Error::InvalidFormat(_, _))),
+        "{:?}",
+        res.err()
+    );
+    let res = Xor8::<BuildHasherDefault>::from_bytes(vec![0; 3]);
+    assert!(
+        matches!(res, Err(Error::InvalidFormat(_, _))),
+        "{:?}",
+        res.err()
+    );
+
+    let file =
+        std::env::temp_dir().join(format!("xorfilter-missing-{}", random::<u64>()));
+    let res = Xor8::<BuildHasherDefault>::read_file(file.as_os_str());
+    assert!(matches!(res, Err(Error::IOError(_, _))), "{:?}", res.err());
+}
+
+#[test]
+fn test_xor8_build_stats() {
+    let seed: u64 = random();
+    println!("test_xor8_build_stats seed:{}", seed);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let keys = generate_unique_keys(&mut rng, 100_000);
+
+    let mut builder = Xor8Builder::<BuildHasherDefault>::new();
+    builder.populate(&keys);
+    builder.populate(&keys[..1000]);
+    let (filter, stats) = builder.build_with_stats().expect("failed build");
+    println!("test_xor8_build_stats {}", stats);
+
+    assert_eq!(stats.num_digests, keys.len() + 1000);
+    assert_eq!(stats.num_unique, keys.len());
+    assert_eq!(stats.num_duplicates, 1000);
+    assert_eq!(stats.seed, filter.seed);
+    assert_eq!(stats.array_length, filter.finger_prints.len());
+    assert!(stats.bits_per_key > 9.0 && stats.bits_per_key < 10.0);
+
+    let (_, stats) = builder.build_from_digests_with_stats(&[]).expect("failed build");
+    assert_eq!(stats.num_unique, 0);
+    assert_eq!(stats.bits_per_key, 0.0);
+}
+
+#[test]
+fn test_xor8_rng_seed() {
+    let seed: u64 = random();
+    println!("test_xor8_rng_seed seed:{}", seed);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let keys = generate_unique_keys(&mut rng, 10_000);
+
+    let build = |rng_seed: u64| {
+        let mut builder = Xor8Builder::<BuildHasherDefault>::new();
+        builder.rng_seed = rng_seed;
+        builder.populate(&keys);
+        builder.build().expect("failed build")
+    };
+
+    let (filter1, filter2) = (build(seed), build(seed));
+    assert_eq!(filter1.seed, filter2.seed);
+    assert_eq!(filter1.finger_prints, filter2.finger_prints);
+
+    let filter3 = build(seed.wrapping_add(1));
+    assert_ne!(filter1.seed, filter3.seed);
+    for key in keys.iter() {
+        assert!(filter3.contains(key), "key {} not present", key);
+    }
+
+    let mut builder = Xor8Builder::<BuildHasherDefault>::new();
+    let rng_seed = builder.set_random_seed();
+    assert_eq!(builder.rng_seed, rng_seed);
+    builder.populate(&keys);
+    let filter = builder.build().expect("failed build");
+    assert_eq!(filter.finger_prints, build(rng_seed).finger_prints);
+}
+
 #[test]
 #[ignore]
 fn test_xor8_billion() {
@@ -217,7 +313,7 @@ fn test_xor8_billion() {
 #[cfg(feature = "cbordata")]
 #[test]
 fn test_xor8_cbor() {
-    use crate::Xor8;
+    use crate::xor8::Xor8;
 
     let seed: u64 = random();
     println!("test_xor8_cbor seed:{}", seed);
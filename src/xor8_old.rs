@@ -4,6 +4,7 @@
 //! [original implementation](https://github.com/FastFilter/xorfilter)
 //! written in golang.
 
+use std::borrow::Borrow;
 #[allow(unused_imports)]
 use std::collections::hash_map::DefaultHasher;
 #[allow(unused_imports)]
@@ -14,13 +15,11 @@ use std::ffi;
 use std::fs;
 use std::hash::BuildHasher;
 use std::hash::Hash;
-use std::hash::Hasher;
-use std::io::ErrorKind;
 use std::io::Read;
 use std::io::Write;
-use std::io::{self};
 
 use crate::BuildHasherDefault;
+use crate::Error;
 use crate::Result;
 
 fn murmur64(mut h: u64) -> u64 {
@@ -155,29 +154,32 @@ where H: BuildHasher
     /// Insert 64-bit digest of a single key. Digest for the key shall be generated
     /// using the default-hasher or via hasher supplied via [Xor8::with_hasher] method.
     pub fn insert<K: ?Sized + Hash>(&mut self, key: &K) {
-        let hashed_key = {
-            let mut hasher = self.hash_builder.build_hasher();
-            key.hash(&mut hasher);
-            hasher.finish()
-        };
+        let hashed_key = self.hash_builder.hash_one(key);
         self.keys.as_mut().unwrap().insert(hashed_key, ());
     }
 
-    /// Populate with 64-bit digests for a collection of keys of type `K`. Digest for
-    /// key shall be generated using the default-hasher or via hasher supplied
-    /// via [Xor8::with_hasher] method.
-    pub fn populate<K: Hash>(&mut self, keys: &[K]) {
-        keys.iter().for_each(|key| {
-            let mut hasher = self.hash_builder.build_hasher();
-            key.hash(&mut hasher);
-            self.keys.as_mut().unwrap().insert(hasher.finish(), ());
+    /// Populate with 64-bit digests for a collection of keys, owned or borrowed.
+    /// Digest for key shall be generated using the default-hasher or via hasher
+    /// supplied via [Xor8::with_hasher] method.
+    pub fn populate<I>(&mut self, keys: I)
+    where
+        I: IntoIterator,
+        I::Item: Hash,
+    {
+        keys.into_iter().for_each(|key| {
+            let digest = self.hash_builder.hash_one(key);
+            self.keys.as_mut().unwrap().insert(digest, ());
         })
     }
 
     /// Populate with pre-compute collection of 64-bit digests.
-    pub fn populate_keys(&mut self, digests: &[u64]) {
-        for digest in digests.iter() {
-            self.keys.as_mut().unwrap().insert(*digest, ());
+    pub fn populate_keys<I>(&mut self, digests: I)
+    where
+        I: IntoIterator,
+        I::Item: Borrow<u64>,
+    {
+        for digest in digests.into_iter() {
+            self.keys.as_mut().unwrap().insert(*digest.borrow(), ());
         }
     }
 
@@ -274,7 +276,7 @@ where H: BuildHasher
                     let h2 = self.geth2(hash);
                     stack.push(keyindexvar);
 
-                    let mut s = unsafe { sets1.get_unchecked_mut(h1 as usize) };
+                    let s = unsafe { sets1.get_unchecked_mut(h1 as usize) };
                     s.xor_mask ^= hash;
                     s.count -= 1;
                     if s.count == 1 {
@@ -284,7 +286,7 @@ where H: BuildHasher
                         })
                     }
 
-                    let mut s = unsafe { sets2.get_unchecked_mut(h2 as usize) };
+                    let s = unsafe { sets2.get_unchecked_mut(h2 as usize) };
                     s.xor_mask ^= hash;
                     s.count -= 1;
                     if s.count == 1 {
@@ -304,7 +306,7 @@ where H: BuildHasher
                     keyindexvar.index += self.block_length;
                     stack.push(keyindexvar);
 
-                    let mut s = unsafe { sets0.get_unchecked_mut(h0 as usize) };
+                    let s = unsafe { sets0.get_unchecked_mut(h0 as usize) };
                     s.xor_mask ^= hash;
                     s.count -= 1;
                     if s.count == 1 {
@@ -314,7 +316,7 @@ where H: BuildHasher
                         })
                     }
 
-                    let mut s = unsafe { sets2.get_unchecked_mut(h2 as usize) };
+                    let s = unsafe { sets2.get_unchecked_mut(h2 as usize) };
                     s.xor_mask ^= hash;
                     s.count -= 1;
                     if s.count == 1 {
@@ -334,7 +336,7 @@ where H: BuildHasher
                     keyindexvar.index += 2 * self.block_length;
                     stack.push(keyindexvar);
 
-                    let mut s = unsafe { sets0.get_unchecked_mut(h0 as usize) };
+                    let s = unsafe { sets0.get_unchecked_mut(h0 as usize) };
                     s.xor_mask ^= hash;
                     s.count -= 1;
                     if s.count == 1 {
@@ -343,7 +345,7 @@ where H: BuildHasher
                             hash: s.xor_mask,
                         })
                     }
-                    let mut s = unsafe { sets1.get_unchecked_mut(h1 as usize) };
+                    let s = unsafe { sets1.get_unchecked_mut(h1 as usize) };
                     s.xor_mask ^= hash;
                     s.count -= 1;
                     if s.count == 1 {
@@ -399,11 +401,7 @@ where H: BuildHasher
     /// Contains tell you whether the key is likely part of the set, with false
     /// positive rate.
     pub fn contains<K: ?Sized + Hash>(&self, key: &K) -> bool {
-        let hashed_key = {
-            let mut hasher = self.hash_builder.build_hasher();
-            key.hash(&mut hasher);
-            hasher.finish()
-        };
+        let hashed_key = self.hash_builder.hash_one(key);
         self.contains_key(hashed_key)
     }
 
@@ -480,20 +478,20 @@ where H: Into<Vec<u8>> + From<Vec<u8>> + BuildHasher
 
     /// Write to file in binary format
     /// TODO Add chechsum of finger_prints into file headers
-    pub fn write_file(&self, path: &ffi::OsStr) -> io::Result<usize>
+    pub fn write_file(&self, path: &ffi::OsStr) -> Result<usize>
     where H: Clone {
-        let mut f = fs::File::create(path)?;
+        let mut f = err_at!(IOError, fs::File::create(path))?;
         let buf = self.to_bytes();
-        f.write_all(&buf)?;
+        err_at!(IOError, f.write_all(&buf))?;
         Ok(buf.len())
     }
 
     /// Read from file in binary format
-    pub fn read_file(path: &ffi::OsStr) -> io::Result<Self>
+    pub fn read_file(path: &ffi::OsStr) -> Result<Self>
     where H: Default {
-        let mut f = fs::File::open(path)?;
+        let mut f = err_at!(IOError, fs::File::open(path))?;
         let mut data = Vec::new();
-        f.read_to_end(&mut data)?;
+        err_at!(IOError, f.read_to_end(&mut data))?;
         Self::from_bytes(data)
     }
 
@@ -514,25 +512,20 @@ where H: Into<Vec<u8>> + From<Vec<u8>> + BuildHasher
         buf
     }
 
-    pub fn from_bytes(buf: Vec<u8>) -> io::Result<Self>
+    pub fn from_bytes(buf: Vec<u8>) -> Result<Self>
     where H: Default {
-        use std::io::Error;
-
         let mut n = 0;
 
         // validate the buf first.
         if Self::METADATA_LENGTH > buf.len() {
-            return Err(Error::new(ErrorKind::InvalidData, "invalid byte slice"));
+            return err_at!(InvalidFormat, msg: "invalid byte slice");
         }
 
         // check the signature
         if buf[n..4] == Xor8::<H>::SIGNATURE_V1 {
             return Self::from_bytes_v1(buf);
         } else if buf[n..4] != Xor8::<H>::SIGNATURE_V2 {
-            return Err(Error::new(
-                ErrorKind::InvalidData,
-                "File signature incorrect",
-            ));
+            return err_at!(InvalidFormat, msg: "File signature incorrect");
         }
 
         n += 4;
@@ -550,7 +543,7 @@ where H: Into<Vec<u8>> + From<Vec<u8>> + BuildHasher
         n += 4;
 
         if buf[n..].len() < (fp_len + hb_len) {
-            return Err(Error::new(ErrorKind::InvalidData, "invalid byte slice"));
+            return err_at!(InvalidFormat, msg: "invalid byte slice");
         }
 
         // fetch the finger print
@@ -568,23 +561,18 @@ where H: Into<Vec<u8>> + From<Vec<u8>> + BuildHasher
         })
     }
 
-    fn from_bytes_v1(buf: Vec<u8>) -> io::Result<Self>
+    fn from_bytes_v1(buf: Vec<u8>) -> Result<Self>
     where H: Default {
-        use std::io::Error;
-
         // validate the buf first.
         if Self::METADATA_LENGTH > buf.len() {
-            return Err(Error::new(ErrorKind::InvalidData, "invalid byte slice"));
+            return err_at!(InvalidFormat, msg: "invalid byte slice");
         }
         if buf[..4] != Xor8::<H>::SIGNATURE_V1 {
-            return Err(Error::new(
-                ErrorKind::InvalidData,
-                "File signature incorrect",
-            ));
+            return err_at!(InvalidFormat, msg: "File signature incorrect");
         }
         let fp_len = u32::from_be_bytes(buf[16..20].try_into().unwrap()) as usize;
         if buf[20..].len() < fp_len {
-            return Err(Error::new(ErrorKind::InvalidData, "invalid byte slice"));
+            return err_at!(InvalidFormat, msg: "invalid byte slice");
         }
         Ok(Xor8 {
             keys: None,
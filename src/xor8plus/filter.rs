@@ -0,0 +1,257 @@
+#[allow(unused_imports)]
+use std::collections::hash_map::DefaultHasher;
+#[allow(unused_imports)]
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::sync::Arc;
+
+#[cfg(feature = "cbordata")]
+use cbordata::Cbor;
+#[cfg(feature = "cbordata")]
+use cbordata::Cborize;
+#[cfg(feature = "cbordata")]
+use cbordata::FromCbor;
+#[cfg(feature = "cbordata")]
+use cbordata::IntoCbor;
+#[cfg(feature = "cbordata")]
+use cbordata::{self as cbor};
+
+use crate::xor8::filter::fingerprint;
+use crate::xor8::filter::mixsplit;
+use crate::xor8::filter::reduce;
+use crate::xor8::Xor8;
+use crate::BuildHasherDefault;
+use crate::Filter;
+
+/// Number of bitmap words, `64` slots each, indexed by a single rank entry.
+pub const RANK_WORDS: usize = 8;
+
+/// Type Xor8Plus is probabilistic data-structure to test membership of an element in
+/// a set.
+///
+/// Xor8Plus is the Xor+ variant of [Xor8], described in the xor filter paper. When
+/// built via [crate::xor8::XorBuilder::build_plus], keys are peeled from the first
+/// block in preference to the second, and from the second in preference to the
+/// third, which leaves most of the empty slots in the third block. Xor8Plus stores only
+/// the non-empty slots of the third block, along with a bitmap and a rank index to locate
+/// them, saving about 8% memory compared to [Xor8], for sizeable sets. It has the same
+/// false positive rate as [Xor8].
+///
+/// Xor8Plus is built using [crate::xor8::Xor8Builder]:
+///
+/// ```
+/// # use xorfilter::xor8::Xor8Builder;
+///
+/// let mut b: Xor8Builder = Xor8Builder::new();
+///
+/// b.populate(&["foo", "bar"]);
+/// let filter = b.build_plus().unwrap();
+///
+/// assert!(filter.contains("foo"));
+/// ```
+///
+/// Converting an [Xor8] filter, via `From`, yields the same lookups with less
+/// compression, since its keys are not peeled in that order.
+///
+/// If `RandomState` is used as BuildHasher, `std` has got this to say
+/// > _A particular instance RandomState will create the same instances
+/// > of Hasher, but the hashers created by two different RandomState_
+/// > instances are unlikely to produce the same result for the same values._
+///
+/// If [DefaultHasher] is used as BuildHasher, `std` has got this to say,
+/// > _The internal algorithm is not specified, and so its hashes
+/// > should not be relied upon over releases._
+#[derive(Clone, Debug)]
+pub struct Xor8Plus<H = BuildHasherDefault>
+where H: BuildHasher
+{
+    pub hash_builder: H,
+    pub seed: u64,
+    pub num_keys: Option<usize>,
+    pub block_length: u32,
+    /// First two blocks, followed by non-empty slots of the third block and a
+    /// trailing ZERO slot.
+    pub finger_prints: Arc<Vec<u8>>,
+    /// One bit for each slot in the third block, set if the slot is non-empty.
+    pub bitmap: Arc<Vec<u64>>,
+    /// Number of non-empty slots in the third block, preceding every
+    /// [RANK_WORDS] words of bitmap.
+    pub ranks: Arc<Vec<u32>>,
+}
+
+impl<H> From<Xor8<H>> for Xor8Plus<H>
+where H: BuildHasher
+{
+    fn from(filter: Xor8<H>) -> Self {
+        let bl = filter.block_length as usize;
+        let (head, third) = filter.finger_prints.split_at(2 * bl);
+
+        let mut finger_prints = head.to_vec();
+        let mut bitmap = vec![0_u64; bl.div_ceil(64)];
+        let mut ranks = vec![0_u32; bitmap.len().div_ceil(RANK_WORDS)];
+
+        for (i, fp) in third.iter().enumerate() {
+            if i % (64 * RANK_WORDS) == 0 {
+                ranks[i / (64 * RANK_WORDS)] = (finger_prints.len() - head.len()) as u32;
+            }
+            if *fp != 0 {
+                bitmap[i / 64] |= 1 << (i % 64);
+                finger_prints.push(*fp);
+            }
+        }
+        finger_prints.push(0);
+
+        Xor8Plus {
+            hash_builder: filter.hash_builder,
+            seed: filter.seed,
+            num_keys: filter.num_keys,
+            block_length: filter.block_length,
+            finger_prints: Arc::new(finger_prints),
+            bitmap: Arc::new(bitmap),
+            ranks: Arc::new(ranks),
+        }
+    }
+}
+
+impl<H> Xor8Plus<H>
+where H: BuildHasher
+{
+    #[allow(clippy::len_without_is_empty)]
+    /// Return the number of keys added/built into the bitmap index.
+    pub fn len(&self) -> Option<usize> {
+        self.num_keys
+    }
+
+    /// Return the size of index.
+    #[inline]
+    pub fn size_of(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.finger_prints.len()
+            + (self.bitmap.len() * 8)
+            + (self.ranks.len() * 4)
+    }
+
+    /// Contains tell you whether the key is likely part of the set, with false
+    /// positive rate.
+    pub fn contains<K: ?Sized + Hash>(&self, key: &K) -> bool {
+        let hashed_key = self.hash_builder.hash_one(key);
+        self.contains_digest(hashed_key)
+    }
+
+    /// Contains tell you whether the key, as pre-computed digest form, is likely
+    /// part of the set, with false positive rate.
+    pub fn contains_digest(&self, digest: u64) -> bool {
+        let hash = mixsplit(digest, self.seed);
+        let f = fingerprint(hash) as u8;
+        let r0 = hash as u32;
+        let r1 = hash.rotate_left(21) as u32;
+        let r2 = hash.rotate_left(42) as u32;
+        let h0 = reduce(r0, self.block_length) as usize;
+        let h1 = (reduce(r1, self.block_length) + self.block_length) as usize;
+        let h2 = reduce(r2, self.block_length) as usize;
+
+        // locate the slot in the compressed third block, an empty slot maps to
+        // the next non-empty slot and is masked out.
+        let (w, bit) = (h2 / 64, h2 % 64);
+        let mut rank = self.ranks[w / RANK_WORDS];
+        for word in self.bitmap[(w - (w % RANK_WORDS))..w].iter() {
+            rank += word.count_ones();
+        }
+        let word = self.bitmap[w];
+        let present = ((word >> bit) & 1) as u8;
+        let rank = rank + (word & ((1 << bit) - 1)).count_ones();
+        let off = (2 * self.block_length + rank) as usize;
+        let f2 = self.finger_prints[off] & present.wrapping_neg();
+
+        f == (self.finger_prints[h0] ^ self.finger_prints[h1] ^ f2)
+    }
+
+    pub fn get_hasher(&self) -> H::Hasher {
+        self.hash_builder.build_hasher()
+    }
+
+    /// Calculate hash of a key.
+    #[inline]
+    pub fn hash<K: Hash + ?Sized>(&self, key: &K) -> u64 {
+        let mut hasher = self.get_hasher();
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+impl<H> Filter for Xor8Plus<H>
+where H: BuildHasher
+{
+    fn contains_digest(&self, digest: u64) -> bool {
+        self.contains_digest(digest)
+    }
+
+    fn len(&self) -> Option<usize> {
+        self.num_keys
+    }
+
+    fn size_of(&self) -> usize {
+        self.size_of()
+    }
+}
+
+//------ Implement cbordata related functionalities
+
+// Intermediate type to serialize and de-serialized Xor8Plus into bytes.
+#[cfg(feature = "cbordata")]
+#[derive(Cborize)]
+struct CborXor8Plus {
+    hash_builder: Vec<u8>,
+    seed: u64,
+    num_keys: Option<usize>,
+    block_length: u32,
+    finger_prints: Vec<u8>,
+    bitmap: Vec<u64>,
+    ranks: Vec<u32>,
+}
+
+#[cfg(feature = "cbordata")]
+impl CborXor8Plus {
+    const ID: &'static str = "xor8plus/0.0.1";
+}
+
+#[cfg(feature = "cbordata")]
+impl<H> IntoCbor for Xor8Plus<H>
+where H: BuildHasher + Into<Vec<u8>>
+{
+    fn into_cbor(self) -> cbor::Result<Cbor> {
+        let val = CborXor8Plus {
+            hash_builder: self.hash_builder.into(),
+            seed: self.seed,
+            num_keys: self.num_keys,
+            block_length: self.block_length,
+            finger_prints: self.finger_prints.to_vec(),
+            bitmap: self.bitmap.to_vec(),
+            ranks: self.ranks.to_vec(),
+        };
+        val.into_cbor()
+    }
+}
+
+#[cfg(feature = "cbordata")]
+impl<H> FromCbor for Xor8Plus<H>
+where H: BuildHasher + From<Vec<u8>>
+{
+    fn from_cbor(val: Cbor) -> cbor::Result<Self> {
+        let val = CborXor8Plus::from_cbor(val)?;
+
+        let filter = Xor8Plus {
+            hash_builder: val.hash_builder.into(),
+            seed: val.seed,
+            num_keys: val.num_keys,
+            block_length: val.block_length,
+            finger_prints: Arc::new(val.finger_prints),
+            bitmap: Arc::new(val.bitmap),
+            ranks: Arc::new(val.ranks),
+        };
+
+        Ok(filter)
+    }
+}
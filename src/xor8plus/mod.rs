@@ -0,0 +1,12 @@
+//! Xor8Plus filter, Xor8 filter with a compressed third block.
+//!
+//! Refer to [Xor8Plus] for details.
+
+mod filter;
+
+pub use filter::Xor8Plus;
+pub use filter::RANK_WORDS;
+
+#[cfg(test)]
+#[path = "xor8plus_test.rs"]
+mod xor8plus_test;
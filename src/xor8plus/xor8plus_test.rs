@@ -0,0 +1,110 @@
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+
+#[cfg(feature = "cbordata")]
+use cbordata::FromCbor;
+#[cfg(feature = "cbordata")]
+use cbordata::IntoCbor;
+use rand::prelude::random;
+use rand::rngs::StdRng;
+use rand::Rng;
+use rand::SeedableRng;
+
+use crate::xor8::Xor8Builder;
+use crate::xor8plus::Xor8Plus;
+use crate::BuildHasherDefault;
+
+fn test_xor8plus_build<H>(name: &str, seed: u64, size: u32)
+where H: BuildHasher + Clone + Default {
+    println!("test_xor8plus_build<{}> size:{}", name, size);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let keys: Vec<u64> = (0..size).map(|_| rng.gen::<u64>()).collect();
+
+    let (xor8, plus) = {
+        let mut builder = Xor8Builder::<H>::new();
+        builder.populate(&keys);
+        let xor8 = builder.build().expect("failed build");
+        (xor8, builder.build_plus().expect("failed build"))
+    };
+    let filter = Xor8Plus::from(xor8.clone());
+
+    // contains api
+    for key in keys.iter() {
+        assert!(filter.contains(key), "key {} not present", key);
+        assert!(plus.contains(key), "key {} not present", key);
+    }
+    // contains_digest api
+    for key in keys.iter() {
+        let digest = filter.hash(key);
+        assert!(filter.contains_digest(digest), "key {} not present", key);
+        assert!(plus.contains_digest(digest), "key {} not present", key);
+    }
+
+    // same semantics as xor8, including false positives.
+    let (falsesize, mut matches) = (10_000_000, 0_f64);
+    for _ in 0..falsesize {
+        let key = rng.gen::<u64>();
+        assert_eq!(filter.contains(&key), xor8.contains(&key), "key {}", key);
+        if filter.contains(&key) {
+            matches += 1_f64;
+        }
+    }
+
+    let fpp = matches * 100.0 / (falsesize as f64);
+    println!("test_xor8plus_build<{}> false positive rate {}%", name, fpp);
+    assert!(fpp < 0.40, "fpp({}) >= 0.40", fpp);
+
+    let (a, b) = (plus.size_of() as f64, xor8.size_of() as f64);
+    let bpv = a * 8.0 / (keys.len() as f64);
+    println!(
+        "test_xor8plus_build<{}> bits per entry {} bits, {}",
+        name,
+        bpv,
+        a / b
+    );
+    if size > 10_000 {
+        assert!(a < (b * 0.93), "size_of {} >= {} * 0.93", a, b);
+    }
+}
+
+#[test]
+fn test_xor8plus() {
+    let mut seed: u64 = random();
+    println!("test_xor8plus seed:{}", seed);
+
+    for size in [0, 1, 2, 10, 1000, 10_000, 100_000, 1_000_000].iter() {
+        seed = seed.wrapping_add(*size as u64);
+        test_xor8plus_build::<RandomState>("RandomState", seed, *size);
+        test_xor8plus_build::<BuildHasherDefault>("BuildHasherDefault", seed, *size);
+    }
+}
+
+#[cfg(feature = "cbordata")]
+#[test]
+fn test_xor8plus_cbor() {
+    let seed: u64 = random();
+    println!("test_xor8plus_cbor seed:{}", seed);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let keys: Vec<u64> = (0..100_000).map(|_| rng.gen::<u64>()).collect();
+
+    let filter = {
+        let mut builder = Xor8Builder::<BuildHasherDefault>::new();
+        builder.populate(&keys);
+        builder.build_plus().expect("fail building xor8plus filter")
+    };
+
+    for key in keys.iter() {
+        assert!(filter.contains(key), "key {} not present", key);
+    }
+
+    let filter = {
+        let val = filter.into_cbor().unwrap();
+        Xor8Plus::<BuildHasherDefault>::from_cbor(val).unwrap()
+    };
+
+    for key in keys.iter() {
+        assert!(filter.contains(key), "key {} not present", key);
+    }
+}
@@ -0,0 +1,221 @@
+use std::borrow::Borrow;
+use std::collections::HashSet;
+use std::hash::BuildHasher;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::hasher::random_seed;
+use crate::packed::PackedArray;
+use crate::xor8::builder::xor_assign;
+use crate::xor8::builder::xor_block_length;
+use crate::xor8::builder::xor_peel;
+use crate::xor8::builder::XOR_MAX_KEYS;
+use crate::xor8::builder::XOR_RNG_SEED;
+use crate::xor8::filter::fingerprint;
+use crate::xor_packed::XorPacked;
+use crate::BuildHasherDefault;
+use crate::BuildStats;
+
+/// Builds an XorPacked filter, with fingerprints `bits` wide.
+///
+/// Example:
+/// ```
+/// # use xorfilter::xor_packed::XorPackedBuilder;
+///
+/// let mut b: XorPackedBuilder = XorPackedBuilder::new(12);
+///
+/// b.populate(&["foo", "bar"]);
+/// let filter = b.build().unwrap();
+///
+/// assert!(filter.contains("foo"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct XorPackedBuilder<H = BuildHasherDefault>
+where H: BuildHasher + Clone
+{
+    digests: HashSet<u64>,
+    hash_time: Duration,
+    bits: u32,
+    pub num_digests: usize,
+    pub hash_builder: H,
+    /// Seed for the random number generator drawing the peeling seeds.
+    pub rng_seed: u64,
+}
+
+impl<H> XorPackedBuilder<H>
+where H: BuildHasher + Clone
+{
+    /// New XorPacked builder, with `bits` wide fingerprints, initialized with
+    /// [BuildHasherDefault].
+    ///
+    /// Panics if `bits` is not within 1 and 32.
+    pub fn new(bits: u32) -> Self
+    where H: Default {
+        Self::with_hasher(bits, H::default())
+    }
+
+    /// New XorPacked builder, with `bits` wide fingerprints, initialized with
+    /// supplied `hasher`.
+    ///
+    /// Panics if `bits` is not within 1 and 32.
+    pub fn with_hasher(bits: u32, hash_builder: H) -> Self {
+        assert!((1..=32).contains(&bits), "bits:{} not within 1..=32", bits);
+
+        Self {
+            digests: HashSet::new(),
+            hash_time: Duration::default(),
+            bits,
+            num_digests: 0,
+            hash_builder,
+            rng_seed: XOR_RNG_SEED,
+        }
+    }
+
+    /// Seed the builds with a random `rng_seed`, and return the same.
+    pub fn set_random_seed(&mut self) -> u64 {
+        self.rng_seed = random_seed();
+        self.rng_seed
+    }
+
+    /// Return the width of fingerprints, in bits.
+    pub fn bits(&self) -> u32 {
+        self.bits
+    }
+
+    pub fn get_hasher(&self) -> H::Hasher {
+        self.hash_builder.build_hasher()
+    }
+
+    /// Calculate hash of a key.
+    #[inline]
+    pub fn hash<K: Hash + ?Sized>(&self, key: &K) -> u64 {
+        let mut hasher = self.get_hasher();
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Insert 64-bit digest of a single key.
+    ///
+    /// Digest for the key shall be generated using the default-hasher or via hasher
+    /// supplied via [XorPackedBuilder::with_hasher] method.
+    pub fn insert<K: ?Sized + Hash>(&mut self, key: &K) {
+        let digest = self.hash(key);
+
+        self.digests.insert(digest);
+        self.num_digests += 1;
+    }
+
+    /// Populate with 64-bit digests for a collection of keys, owned or
+    /// borrowed.
+    ///
+    /// Digest for key shall be generated using the default-hasher or via hasher supplied
+    /// via [XorPackedBuilder::with_hasher] method.
+    pub fn populate<I>(&mut self, keys: I)
+    where
+        I: IntoIterator,
+        I::Item: Hash,
+    {
+        let (mut n, start) = (0, Instant::now());
+
+        for key in keys.into_iter() {
+            n += 1;
+
+            let digest = self.hash(&key);
+            self.digests.insert(digest);
+        }
+
+        self.num_digests += n;
+        self.hash_time += start.elapsed();
+    }
+
+    /// Populate with pre-compute collection of 64-bit digests.
+    pub fn populate_digests<I>(&mut self, digests: I)
+    where
+        I: IntoIterator,
+        I::Item: Borrow<u64>,
+    {
+        let mut n = 0;
+
+        for digest in digests.into_iter() {
+            n += 1;
+            self.digests.insert(*digest.borrow());
+        }
+
+        self.num_digests += n;
+    }
+
+    /// Build bitmap for keys that where previously inserted using
+    /// [XorPackedBuilder::insert], [XorPackedBuilder::populate] and
+    /// [XorPackedBuilder::populate_digests] method.
+    pub fn build(&mut self) -> Result<XorPacked<H>, crate::Error> {
+        self.build_with_stats().map(|(filter, _)| filter)
+    }
+
+    /// Same as [XorPackedBuilder::build], and return statistics on the build.
+    pub fn build_with_stats(
+        &mut self,
+    ) -> Result<(XorPacked<H>, BuildStats), crate::Error> {
+        let digests = self.digests.iter().copied().collect::<Vec<u64>>();
+
+        let (filter, mut stats) = self.build_from_digests_with_stats(&digests)?;
+        stats.num_digests = self.num_digests;
+        stats.num_duplicates = self.num_digests - stats.num_unique;
+        stats.hash_time = self.hash_time;
+        Ok((filter, stats))
+    }
+
+    /// Build a bitmap for pre-computed 64-bit digests for keys.
+    ///
+    /// If keys where previously inserted using [XorPackedBuilder::insert] or
+    /// [XorPackedBuilder::populate] or [XorPackedBuilder::populate_digests] methods, they
+    /// shall be ignored.
+    ///
+    /// It is upto the caller to ensure that digests are unique, that there no duplicates,
+    /// duplicates fail the build with [crate::Error::TooManyIterations]. Filters are
+    /// sized with `u32`, at `1.23` slots per key more than about `3.49e9` digests
+    /// fail the build with [crate::Error::CapacityExceeded].
+    pub fn build_from_digests(
+        &mut self,
+        digests: &[u64],
+    ) -> Result<XorPacked<H>, crate::Error> {
+        self.build_from_digests_with_stats(digests).map(|(filter, _)| filter)
+    }
+
+    /// Same as [XorPackedBuilder::build_from_digests], and return statistics on the
+    /// build.
+    pub fn build_from_digests_with_stats(
+        &mut self,
+        digests: &[u64],
+    ) -> Result<(XorPacked<H>, BuildStats), crate::Error> {
+        if digests.len() > XOR_MAX_KEYS {
+            return Err(crate::Error::CapacityExceeded {
+                keys: digests.len(),
+                capacity: XOR_MAX_KEYS,
+            });
+        }
+
+        let mut ff = XorPacked::<H>::new(self.hash_builder.clone());
+
+        ff.num_keys = Some(digests.len());
+        ff.block_length = xor_block_length(digests.len());
+
+        let mut stats = BuildStats::default();
+        let mut rngcounter = self.rng_seed;
+        let (seed, stack) =
+            xor_peel(digests, ff.block_length, false, &mut rngcounter, &mut stats)?;
+        ff.seed = seed;
+
+        let start = Instant::now();
+        let mut finger_prints =
+            PackedArray::new(self.bits, (ff.block_length as usize) * 3);
+        xor_assign(&stack, ff.block_length, &mut finger_prints, fingerprint);
+        stats.assign_time = start.elapsed();
+        stats.set_array(finger_prints.len(), self.bits);
+        ff.finger_prints = Arc::new(finger_prints);
+
+        Ok((ff, stats))
+    }
+}
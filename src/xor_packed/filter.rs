@@ -0,0 +1,230 @@
+#[allow(unused_imports)]
+use std::collections::hash_map::DefaultHasher;
+#[allow(unused_imports)]
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::sync::Arc;
+
+#[cfg(feature = "cbordata")]
+use cbordata::Cbor;
+#[cfg(feature = "cbordata")]
+use cbordata::Cborize;
+#[cfg(feature = "cbordata")]
+use cbordata::FromCbor;
+#[cfg(feature = "cbordata")]
+use cbordata::IntoCbor;
+#[cfg(feature = "cbordata")]
+use cbordata::{self as cbor};
+
+use crate::packed::PackedArray;
+use crate::xor8::filter::fingerprint;
+use crate::xor8::filter::mixsplit;
+use crate::xor8::filter::reduce;
+use crate::BuildHasherDefault;
+use crate::Filter;
+
+/// Type XorPacked is probabilistic data-structure to test membership of an element in a
+/// set.
+///
+/// Fingerprints are `bits` wide, as chosen by [crate::xor_packed::XorPackedBuilder],
+/// and stored in a [PackedArray]. This implementation has a false positive rate of
+/// about `2^-bits` and a memory usage of about `1.23 * bits` bits per entry for
+/// sizeable sets.
+///
+/// XorPacked is parametrized over type `H` which is expected to implement [BuildHasher]
+/// trait, like types [RandomState] and [BuildHasherDefault]. When not supplied,
+/// [BuildHasherDefault] is used as the default hash-builder.
+///
+/// If `RandomState` is used as BuildHasher, `std` has got this to say
+/// > _A particular instance RandomState will create the same instances
+/// > of Hasher, but the hashers created by two different RandomState_
+/// > instances are unlikely to produce the same result for the same values._
+///
+/// If [DefaultHasher] is used as BuildHasher, `std` has got this to say,
+/// > _The internal algorithm is not specified, and so its hashes
+/// > should not be relied upon over releases._
+///
+/// The default type for parameter `H` might change when a reliable and commonly used
+/// BuildHasher type is available.
+#[derive(Clone, Debug)]
+pub struct XorPacked<H = BuildHasherDefault>
+where H: BuildHasher
+{
+    pub hash_builder: H,
+    pub seed: u64,
+    pub num_keys: Option<usize>,
+    pub block_length: u32,
+    pub finger_prints: Arc<PackedArray>,
+}
+
+impl<H> PartialEq for XorPacked<H>
+where H: BuildHasher
+{
+    fn eq(&self, other: &Self) -> bool {
+        let num_keys = match (self.num_keys, other.num_keys) {
+            (Some(a), Some(b)) => a == b,
+            (_, _) => true,
+        };
+
+        self.seed == other.seed
+            && num_keys
+            && self.block_length == other.block_length
+            && self.finger_prints == other.finger_prints
+    }
+}
+
+impl<H> XorPacked<H>
+where H: BuildHasher
+{
+    pub(crate) fn new(hash_builder: H) -> Self {
+        Self {
+            hash_builder,
+            seed: 0,
+            num_keys: None,
+            block_length: 0,
+            finger_prints: Arc::new(PackedArray::default()),
+        }
+    }
+}
+
+impl<H> XorPacked<H>
+where H: BuildHasher
+{
+    #[allow(clippy::len_without_is_empty)]
+    /// Return the number of keys added/built into the bitmap index.
+    pub fn len(&self) -> Option<usize> {
+        self.num_keys
+    }
+
+    /// Return the size of index.
+    #[inline]
+    pub fn size_of(&self) -> usize {
+        std::mem::size_of::<Self>() + self.finger_prints.size_of()
+    }
+
+    /// Return the width of fingerprints, in bits.
+    #[inline]
+    pub fn bits(&self) -> u32 {
+        self.finger_prints.bits()
+    }
+
+    /// Contains tell you whether the key is likely part of the set, with false
+    /// positive rate.
+    pub fn contains<K: ?Sized + Hash>(&self, key: &K) -> bool {
+        let hashed_key = self.hash_builder.hash_one(key);
+        self.contains_digest(hashed_key)
+    }
+
+    /// Contains tell you whether the key, as pre-computed digest form, is likely
+    /// part of the set, with false positive rate.
+    pub fn contains_digest(&self, digest: u64) -> bool {
+        let hash = mixsplit(digest, self.seed);
+        let f = fingerprint(hash) & self.finger_prints.mask();
+        let r0 = hash as u32;
+        let r1 = hash.rotate_left(21) as u32;
+        let r2 = hash.rotate_left(42) as u32;
+        let h0 = reduce(r0, self.block_length) as usize;
+        let h1 = (reduce(r1, self.block_length) + self.block_length) as usize;
+        let h2 = (reduce(r2, self.block_length) + 2 * self.block_length) as usize;
+        let fp = &self.finger_prints;
+        f == (fp.get(h0) ^ fp.get(h1) ^ fp.get(h2))
+    }
+
+    pub fn get_hasher(&self) -> H::Hasher {
+        self.hash_builder.build_hasher()
+    }
+
+    /// Calculate hash of a key.
+    #[inline]
+    pub fn hash<K: Hash + ?Sized>(&self, key: &K) -> u64 {
+        let mut hasher = self.get_hasher();
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+impl<H> Filter for XorPacked<H>
+where H: BuildHasher
+{
+    fn contains_digest(&self, digest: u64) -> bool {
+        self.contains_digest(digest)
+    }
+
+    fn len(&self) -> Option<usize> {
+        self.num_keys
+    }
+
+    fn size_of(&self) -> usize {
+        self.size_of()
+    }
+}
+
+//------ Implement cbordata related functionalities
+
+// Intermediate type to serialize and de-serialized XorPacked into bytes.
+#[cfg(feature = "cbordata")]
+#[derive(Cborize)]
+struct CborXorPacked {
+    hash_builder: Vec<u8>,
+    seed: u64,
+    num_keys: Option<usize>,
+    block_length: u32,
+    bits: u32,
+    finger_prints: Vec<u64>,
+}
+
+#[cfg(feature = "cbordata")]
+impl CborXorPacked {
+    const ID: &'static str = "xorpacked/0.0.1";
+}
+
+#[cfg(feature = "cbordata")]
+impl<H> IntoCbor for XorPacked<H>
+where H: BuildHasher + Into<Vec<u8>>
+{
+    fn into_cbor(self) -> cbor::Result<Cbor> {
+        let val = CborXorPacked {
+            hash_builder: self.hash_builder.into(),
+            seed: self.seed,
+            num_keys: self.num_keys,
+            block_length: self.block_length,
+            bits: self.finger_prints.bits(),
+            finger_prints: self.finger_prints.as_words().to_vec(),
+        };
+        val.into_cbor()
+    }
+}
+
+#[cfg(feature = "cbordata")]
+impl<H> FromCbor for XorPacked<H>
+where H: BuildHasher + From<Vec<u8>>
+{
+    fn from_cbor(val: Cbor) -> cbor::Result<Self> {
+        let val = CborXorPacked::from_cbor(val)?;
+
+        let len = (val.block_length as usize) * 3;
+        let finger_prints =
+            match PackedArray::from_words(val.bits, len, val.finger_prints) {
+                Some(finger_prints) => finger_prints,
+                None => {
+                    let msg = format!("invalid packed fingerprints, bits:{}", val.bits);
+                    return Err(cbor::Error::FailConvert(
+                        format!("{}:{}", file!(), line!()),
+                        msg,
+                    ));
+                }
+            };
+
+        let filter = XorPacked {
+            hash_builder: val.hash_builder.into(),
+            seed: val.seed,
+            num_keys: val.num_keys,
+            block_length: val.block_length,
+            finger_prints: Arc::new(finger_prints),
+        };
+
+        Ok(filter)
+    }
+}
@@ -0,0 +1,14 @@
+//! XorPacked filter, 3-wise xor filter with bit-packed fingerprints of arbitrary width.
+//!
+//! Uses the same construction as [crate::xor8], fingerprint width is chosen when
+//! creating the builder, trading memory for a false positive rate of about `2^-bits`.
+
+mod builder;
+mod filter;
+
+pub use builder::XorPackedBuilder;
+pub use filter::XorPacked;
+
+#[cfg(test)]
+#[path = "xor_packed_test.rs"]
+mod xor_packed_test;
@@ -39,7 +39,7 @@ fn test_same_filter_bytes_encoding_tl1() {
     use std::path;
 
     let keys: Vec<u32> = (1..10000).map(|i| (i * 2) + 1).collect();
-    let missing: Vec<u32> = (1..20).map(|i| (i * 2)).collect();
+    let missing: Vec<u32> = (1..20).map(|i| i * 2).collect();
 
     let file_path = {
         let mut loc = path::PathBuf::new();